@@ -1,14 +1,18 @@
 use anyhow::Result;
 use chrono::Utc;
 use colored::*;
+use crate::estimate::Calibration;
+use crate::graph::TaskGraph;
 use crate::task::{Task, TaskStatus};
-use crate::storage::TaskStorage;
+use crate::storage::TaskStore;
 use crate::claude_integration::ClaudeTaskAdvice;
-use std::path::PathBuf;
+
+/// A task forecast to take this many times longer than a typical one should be split up
+const LARGE_TASK_FACTOR: f64 = 3.0;
 
 pub struct TaskAdvisor {
     task: Task,
-    all_tasks: Vec<Task>,
+    graph: TaskGraph,
 }
 
 #[derive(Debug)]
@@ -37,6 +41,10 @@ pub struct TaskContext {
     pub dependent_tasks: Vec<u32>,
     pub completion_percentage: Option<(usize, usize)>,
     pub similar_completed_tasks: Vec<String>,
+    /// Hours the task should take, judging by its estimate and past actuals
+    pub forecast_hours: Option<f64>,
+    /// Median hours a done task took
+    pub typical_hours: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -46,12 +54,12 @@ pub struct SuggestedAction {
 }
 
 impl TaskAdvisor {
-    pub fn new(task: Task, storage: &TaskStorage, _tasks_dir: PathBuf) -> Result<Self> {
-        let all_tasks = storage.list_all_tasks()?;
+    pub fn new(task: Task, storage: &dyn TaskStore) -> Result<Self> {
+        let graph = storage.load_graph()?;
         
         Ok(Self {
             task,
-            all_tasks,
+            graph,
         })
     }
 
@@ -72,26 +80,18 @@ impl TaskAdvisor {
         let now = Utc::now();
         let age_days = (now - self.task.created_at).num_days();
         
-        let time_in_current_status = match self.task.status {
-            TaskStatus::Done => {
-                self.task.completed_at
-                    .map(|completed| (now - completed).num_days())
-                    .unwrap_or(0)
-            }
-            _ => (now - self.task.updated_at).num_days(),
-        };
+        let time_in_current_status = (now - self.task.status_since()).num_days();
 
         // Find tasks that depend on this task
-        let dependent_tasks: Vec<u32> = self.all_tasks.iter()
-            .filter(|t| t.dependencies.contains(&self.task.id))
+        let dependent_tasks: Vec<u32> = self.graph.dependents(self.task.id)
+            .iter()
             .map(|t| t.id)
             .collect();
 
         // Find tasks this task is waiting on
         let blocking_tasks: Vec<u32> = self.task.dependencies.iter()
             .filter(|&&dep_id| {
-                self.all_tasks.iter()
-                    .find(|t| t.id == dep_id)
+                self.graph.get(dep_id)
                     .map(|t| t.status != TaskStatus::Done)
                     .unwrap_or(false)
             })
@@ -100,7 +100,7 @@ impl TaskAdvisor {
 
         // Calculate completion percentage if has subtasks
         let completion_percentage = if !self.task.subtasks.is_empty() {
-            Some(self.task.subtask_progress(&self.all_tasks))
+            Some(self.graph.progress(&self.task))
         } else {
             None
         };
@@ -108,6 +108,8 @@ impl TaskAdvisor {
         // Find similar completed tasks
         let similar_completed_tasks = self.find_similar_completed_tasks();
 
+        let calibration = Calibration::from_tasks(self.graph.tasks());
+
         TaskContext {
             age_days,
             time_in_current_status,
@@ -115,13 +117,15 @@ impl TaskAdvisor {
             dependent_tasks,
             completion_percentage,
             similar_completed_tasks,
+            forecast_hours: calibration.forecast_hours(&self.task),
+            typical_hours: calibration.typical_hours,
         }
     }
 
     fn find_similar_completed_tasks(&self) -> Vec<String> {
         let task_words: Vec<&str> = self.task.title.split_whitespace().collect();
         
-        self.all_tasks.iter()
+        self.graph.tasks().iter()
             .filter(|t| t.status == TaskStatus::Done && t.id != self.task.id)
             .filter(|t| {
                 let other_words: Vec<&str> = t.title.split_whitespace().collect();
@@ -183,7 +187,7 @@ impl TaskAdvisor {
 
         // Status-specific logic
         match self.task.status {
-            TaskStatus::InProgress => {
+            TaskStatus::InProgress | TaskStatus::Custom(_) => {
                 if context.time_in_current_status > 14 {
                     reasons.push(format!("In progress for {} days", context.time_in_current_status));
                     reasons.push("May be stalled".to_string());
@@ -241,10 +245,20 @@ impl TaskAdvisor {
                     return (Recommendation::Continue, reasons);
                 }
 
-                // Complex task without subtasks
-                if self.task.complexity == Some(crate::task::Complexity::Complex) && self.task.subtasks.is_empty() {
-                    reasons.push("Complex task without subtasks".to_string());
-                    return (Recommendation::Decompose, reasons);
+                // Large task without subtasks, sized by past actuals when there are any
+                if self.task.subtasks.is_empty() {
+                    match (context.forecast_hours, context.typical_hours) {
+                        (Some(forecast), Some(typical)) if forecast > typical * LARGE_TASK_FACTOR => {
+                            reasons.push(format!("Forecast to take {:.1}h, over {} times a typical task ({:.1}h)", forecast, LARGE_TASK_FACTOR, typical));
+                            return (Recommendation::Decompose, reasons);
+                        }
+                        (Some(_), Some(_)) => {}
+                        _ if self.task.complexity == Some(crate::task::Complexity::Complex) => {
+                            reasons.push("Complex task without subtasks".to_string());
+                            return (Recommendation::Decompose, reasons);
+                        }
+                        _ => {}
+                    }
                 }
 
                 // Default for pending
@@ -388,6 +402,11 @@ pub fn display_advice(advice: &TaskAdvice, task: &Task, detailed: bool) -> Vec<S
                 completed, total, (completed as f32 / total as f32) * 100.0);
         }
         
+        if let Some(forecast) = advice.context.forecast_hours {
+            println!("  • Forecast: {:.1}h{}", forecast,
+                advice.context.typical_hours.map(|t| format!(" (typical task: {:.1}h)", t)).unwrap_or_default());
+        }
+        
         if !advice.context.similar_completed_tasks.is_empty() {
             println!("  • Similar completed tasks:");
             for similar in &advice.context.similar_completed_tasks {
@@ -0,0 +1,86 @@
+use anyhow::Result;
+
+/// A markdown checkbox item (`- [ ] ...` or `- [x] ...`) in a task's description
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecklistItem {
+    pub text: String,
+    pub checked: bool,
+    /// Index of the description line the item is on
+    line: usize,
+}
+
+/// Split a checkbox line into its checked state and text
+fn parse_item(line: &str) -> Option<(bool, &str)> {
+    let rest = line.trim_start().strip_prefix(['-', '*', '+'])?.strip_prefix(' ')?;
+    let checked = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let text = &rest[3..];
+    (text.is_empty() || text.starts_with(' ')).then(|| (checked, text.trim()))
+}
+
+/// The checkbox items in `description`, in order. Items inside fenced code
+/// blocks are examples, not steps, and are skipped.
+pub fn parse(description: &str) -> Vec<ChecklistItem> {
+    let mut items = Vec::new();
+    let mut in_code = false;
+    for (line, text) in description.lines().enumerate() {
+        if text.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if let Some((checked, text)) = parse_item(text).filter(|_| !in_code) {
+            items.push(ChecklistItem { text: text.to_string(), checked, line });
+        }
+    }
+    items
+}
+
+/// `description` with checklist item `number` (counting from 1) checked or unchecked
+pub fn set_checked(description: &str, number: usize, checked: bool) -> Result<String> {
+    let items = parse(description);
+    let item = number.checked_sub(1).and_then(|index| items.get(index)).ok_or_else(|| match items.len() {
+        0 => anyhow::anyhow!("There are no checklist items. Add `- [ ] ...` lines to the description"),
+        count => anyhow::anyhow!("There is no checklist item {}; items are numbered 1 to {}", number, count),
+    })?;
+
+    let lines = description.split_inclusive('\n').enumerate().map(|(index, line)| {
+        if index != item.line {
+            return line.to_string();
+        }
+        let open = line.find('[').unwrap_or_default();
+        let mark = if checked { "x" } else { " " };
+        format!("{}{}{}", &line[..=open], mark, &line[open + 2..])
+    });
+    Ok(lines.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTION: &str = "Before the release:\n\n- [ ] Bump the version\n- [x] Update the changelog\n  * [X] Nested step\n- [] Not an item\n\n```\n- [ ] Example in a code block\n```\n";
+
+    #[test]
+    fn test_parse_checklist() {
+        let items = parse(DESCRIPTION);
+        let summary: Vec<(&str, bool)> = items.iter().map(|i| (i.text.as_str(), i.checked)).collect();
+        assert_eq!(summary, vec![("Bump the version", false), ("Update the changelog", true), ("Nested step", true)]);
+        assert!(parse("No steps here").is_empty());
+    }
+
+    #[test]
+    fn test_set_checked_rewrites_only_that_line() {
+        let checked = set_checked(DESCRIPTION, 1, true).unwrap();
+        assert_eq!(checked, DESCRIPTION.replacen("- [ ] Bump", "- [x] Bump", 1));
+        assert!(parse(&checked).iter().all(|i| i.checked));
+
+        let unchecked = set_checked(&checked, 3, false).unwrap();
+        assert!(unchecked.contains("  * [ ] Nested step\n"));
+
+        assert!(set_checked(DESCRIPTION, 0, true).is_err());
+        assert!(set_checked(DESCRIPTION, 4, true).is_err());
+    }
+}
@@ -240,6 +240,10 @@ pub fn get_task_advice(task: &Task, all_tasks: &[Task]) -> Result<ClaudeTaskAdvi
             t.priority))
         .collect();
     
+    let notes: Vec<String> = task.notes.iter()
+        .map(|n| format!("- {} by {}: {}", n.at.format("%Y-%m-%d %H:%M"), n.author, n.text))
+        .collect();
+    
     // Construct a prompt that asks Claude to analyze the task
     let system_prompt = format!(r#"You are an expert task advisor integrated with a development workflow. Analyze the given task in the context of the codebase and tasks that have been completed or modified since this task was created.
 
@@ -254,6 +258,9 @@ Current task details:
 - Dependencies: {:?}
 - Subtasks: {} subtasks
 
+Notes on this task, oldest first:
+{}
+
 Tasks completed AFTER this task was created:
 {}
 
@@ -264,13 +271,14 @@ Current working directory: {}
 
 Analyze this task considering:
 1. The codebase structure and existing code
-2. Tasks completed or modified since this was created - they may have:
+2. The notes on the task, which record progress and findings since it was written
+3. Tasks completed or modified since this was created - they may have:
    - Already addressed this task's needs
    - Changed the project context making this task obsolete
    - Shifted priorities or technical direction
-3. Whether the task is still relevant given what has happened since
-4. Task age and current status
-5. Dependencies and blockers
+4. Whether the task is still relevant given what has happened since
+5. Task age and current status
+6. Dependencies and blockers
 
 Provide advice in the following JSON format:
 {{
@@ -309,6 +317,7 @@ Output ONLY valid JSON, no additional text."#,
         task.created_at.format("%Y-%m-%d"),
         task.dependencies.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", "),
         task.subtasks.len(),
+        if notes.is_empty() { "None".to_string() } else { notes.join("\n") },
         if completed_after.is_empty() { "None".to_string() } else { completed_after.join("\n") },
         if modified_after.is_empty() { "None".to_string() } else { modified_after.join("\n") },
         current_dir.display()
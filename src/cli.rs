@@ -1,9 +1,17 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::relation::RelationKind;
+use crate::task::TaskRef;
 
 #[derive(Parser)]
 #[command(name = "trusty")]
 #[command(about = "A task manager for Claude Code", long_about = None)]
 pub struct Cli {
+    /// Project directory to use instead of searching up from the current directory
+    #[arg(long, global = true, value_name = "PATH")]
+    pub project: Option<PathBuf>,
+    
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -12,6 +20,10 @@ pub struct Cli {
 pub enum Commands {
     /// List all tasks
     List {
+        /// Only tasks matching this query, e.g. status:pending,blocked priority:high -tag:spike due<7d oauth
+        #[arg(value_name = "QUERY")]
+        query: Vec<String>,
+        
         /// Show all tasks including completed ones
         #[arg(long)]
         all: bool,
@@ -23,6 +35,22 @@ pub enum Commands {
         /// Show completed tasks from the last N minutes
         #[arg(long, value_name = "MINUTES")]
         recent: Option<u64>,
+        
+        /// Filter by custom field (e.g. team=web, estimate>=3); repeatable
+        #[arg(long = "field", value_name = "FILTER")]
+        fields: Vec<String>,
+        
+        /// Show only open tasks past their due date
+        #[arg(long)]
+        overdue: bool,
+        
+        /// Show only open tasks due within DAYS days (default 3)
+        #[arg(long, value_name = "DAYS", num_args = 0..=1, default_missing_value = "3")]
+        due_soon: Option<i64>,
+        
+        /// Show only tasks assigned to this milestone
+        #[arg(long, value_name = "NAME")]
+        milestone: Option<String>,
     },
     
     /// Add a new task
@@ -32,7 +60,7 @@ pub enum Commands {
         title: Option<String>,
         
         /// Task description
-        #[arg(long)]
+        #[arg(long, allow_hyphen_values = true)]
         description: Option<String>,
         
         /// Task priority (high, medium, low)
@@ -50,44 +78,132 @@ pub enum Commands {
         /// Generate task from natural language prompt using Claude
         #[arg(long, conflicts_with_all = ["description", "priority", "tags"])]
         prompt: Option<String>,
+        
+        /// Set a custom field defined in .trusty/config; repeatable
+        #[arg(long = "field", value_name = "KEY=VALUE")]
+        fields: Vec<String>,
+        
+        /// Due date: YYYY-MM-DD, today, tomorrow, next fri, +3d, +2w, +1m
+        #[arg(long, value_name = "DATE")]
+        due: Option<String>,
+        
+        /// Date to start work; `next` won't suggest the task before it
+        #[arg(long, alias = "start", value_name = "DATE")]
+        scheduled: Option<String>,
+        
+        /// Set the task aside until DATE
+        #[arg(long, value_name = "DATE")]
+        deferred_until: Option<String>,
+        
+        /// Size estimate: points (5), hours (4h) or optimistic/likely/pessimistic (2/4/8h)
+        #[arg(long, value_name = "ESTIMATE")]
+        estimate: Option<String>,
+        
+        /// Repeat the task: daily, weekly, monthly, yearly, "every 2 weeks", "every mon,thu" or "cron:DAY MONTH WEEKDAY"
+        #[arg(long, value_name = "RULE")]
+        recur: Option<String>,
+        
+        /// Last date an occurrence may be due
+        #[arg(long, value_name = "DATE", requires = "recur")]
+        recur_until: Option<String>,
+        
+        /// Number of occurrences, counting this one
+        #[arg(long, value_name = "N", requires = "recur")]
+        recur_count: Option<u32>,
+        
+        /// Milestone to plan the task for (see `trusty milestone`)
+        #[arg(long, value_name = "NAME")]
+        milestone: Option<String>,
     },
     
     /// Show task details
     Show {
-        /// Task ID
-        id: u32,
+        /// Task ID or uid
+        id: TaskRef,
         
         /// Include subtasks in the display
         #[arg(long)]
         with_subtasks: bool,
     },
     
+    /// Add a timestamped note to a task, kept apart from its description
+    Note {
+        /// Task ID or uid
+        id: TaskRef,
+
+        /// The note text
+        text: String,
+    },
+    
+    /// Check off items of a task's checklist (the `- [ ]` lines in its description)
+    Check {
+        /// Task ID or uid
+        id: TaskRef,
+
+        /// Item numbers, counting from 1 as `show` lists them
+        #[arg(required = true)]
+        items: Vec<usize>,
+    },
+    
+    /// Uncheck items of a task's checklist
+    Uncheck {
+        /// Task ID or uid
+        id: TaskRef,
+
+        /// Item numbers, counting from 1 as `show` lists them
+        #[arg(required = true)]
+        items: Vec<usize>,
+    },
+    
+    /// Show a task's status changes and edits, and who made them
+    History {
+        /// Task ID or uid
+        id: TaskRef,
+    },
+    
     /// Update task status
     SetStatus {
-        /// Task ID
-        #[arg(long)]
-        id: u32,
+        /// Task ID or uid
+        #[arg(long, required_unless_present = "filter", conflicts_with = "filter")]
+        id: Option<TaskRef>,
+        
+        /// Update every task matching this query instead, e.g. "tag:spike is:open"
+        #[arg(long = "where", value_name = "QUERY", allow_hyphen_values = true)]
+        filter: Option<String>,
         
-        /// New status (pending, in-progress, done, blocked, deferred, cancelled)
+        /// New status (pending, in-progress, done, blocked, deferred, cancelled, or one from the workflow)
         #[arg(long)]
         status: String,
         
         /// Also update all subtasks to the same status
         #[arg(long)]
         cascade: bool,
+        
+        /// Why the task was closed, for statuses whose workflow guard asks for one
+        #[arg(long)]
+        resolution: Option<String>,
+        
+        /// Ignore the workflow's transition rules and guards
+        #[arg(long)]
+        force: bool,
     },
     
     /// Edit a task
     Edit {
-        /// Task ID
-        id: u32,
+        /// Task ID or uid
+        #[arg(required_unless_present = "filter", conflicts_with = "filter")]
+        id: Option<TaskRef>,
+        
+        /// Edit every task matching this query instead, e.g. "tag:spike is:open"
+        #[arg(long = "where", value_name = "QUERY", allow_hyphen_values = true)]
+        filter: Option<String>,
         
         /// New title
         #[arg(long)]
         title: Option<String>,
         
         /// New description
-        #[arg(long)]
+        #[arg(long, allow_hyphen_values = true)]
         description: Option<String>,
         
         /// New priority
@@ -97,48 +213,114 @@ pub enum Commands {
         /// New complexity
         #[arg(long)]
         complexity: Option<String>,
+        
+        /// Size estimate (none to clear): points (5), hours (4h) or optimistic/likely/pessimistic (2/4/8h)
+        #[arg(long, value_name = "ESTIMATE")]
+        estimate: Option<String>,
+        
+        /// Set a custom field defined in .trusty/config; repeatable
+        #[arg(long = "field", value_name = "KEY=VALUE")]
+        fields: Vec<String>,
+        
+        /// Remove a custom field; repeatable
+        #[arg(long = "unset-field", value_name = "KEY")]
+        unset_fields: Vec<String>,
+        
+        /// Add a tag; repeatable
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        
+        /// Remove a tag; repeatable
+        #[arg(long = "untag", value_name = "TAG")]
+        untags: Vec<String>,
+        
+        /// Due date (none to clear): YYYY-MM-DD, today, tomorrow, next fri, +3d, +2w, +1m
+        #[arg(long, value_name = "DATE")]
+        due: Option<String>,
+        
+        /// Date to start work; `next` won't suggest the task before it
+        #[arg(long, alias = "start", value_name = "DATE")]
+        scheduled: Option<String>,
+        
+        /// Set the task aside until DATE
+        #[arg(long, value_name = "DATE")]
+        deferred_until: Option<String>,
+        
+        /// Milestone to plan the task for (none to clear)
+        #[arg(long, value_name = "NAME")]
+        milestone: Option<String>,
     },
     
-    /// Delete a task
+    /// Move a task to the trash
     Delete {
-        /// Task ID
-        id: u32,
+        /// Task ID or uid
+        #[arg(required_unless_present = "filter", conflicts_with = "filter")]
+        id: Option<TaskRef>,
+        
+        /// Trash every task matching this query instead, e.g. "status:cancelled"
+        #[arg(long = "where", value_name = "QUERY", allow_hyphen_values = true)]
+        filter: Option<String>,
     },
     
     /// Add a dependency to a task
     AddDep {
-        /// Task ID
+        /// Task ID or uid
         #[arg(long)]
-        task: u32,
+        task: TaskRef,
         
         /// Dependency task ID
         #[arg(long)]
-        dep: u32,
+        dep: TaskRef,
     },
     
     /// Remove a dependency from a task
     RemoveDep {
-        /// Task ID
+        /// Task ID or uid
         #[arg(long)]
-        task: u32,
+        task: TaskRef,
         
         /// Dependency task ID
         #[arg(long)]
-        dep: u32,
+        dep: TaskRef,
+    },
+    
+    /// Link a task to another: relates-to, duplicates, follow-up-of,
+    /// supersedes, or after (should come after, without blocking)
+    Relate {
+        /// Task ID or uid
+        task: TaskRef,
+        
+        /// relates-to, duplicates, follow-up-of, supersedes or after
+        kind: RelationKind,
+        
+        /// The other task's ID or uid
+        other: TaskRef,
+    },
+    
+    /// Remove a link added with relate
+    Unrelate {
+        /// Task ID or uid
+        task: TaskRef,
+        
+        /// relates-to, duplicates, follow-up-of, supersedes or after
+        kind: RelationKind,
+        
+        /// The other task's ID or uid
+        other: TaskRef,
     },
     
     /// Add a subtask to an existing task
     AddSubtask {
         /// Parent task ID
         #[arg(long)]
-        task: u32,
+        task: TaskRef,
         
         /// Subtask title
-        #[arg(required_unless_present = "prompt")]
+        #[arg(required_unless_present_any = ["prompt", "existing"])]
         title: Option<String>,
         
         /// Subtask description
-        #[arg(long)]
+        #[arg(long, allow_hyphen_values = true)]
         description: Option<String>,
         
         /// Priority (inherits from parent if not specified)
@@ -152,31 +334,172 @@ pub enum Commands {
         /// Generate subtask from natural language prompt
         #[arg(long)]
         prompt: Option<String>,
+        
+        /// Make an existing task a subtask instead of creating one
+        #[arg(long, conflicts_with_all = ["title", "prompt"])]
+        existing: Option<TaskRef>,
     },
     
     /// Remove a subtask from a task
     RemoveSubtask {
         /// Parent task ID
         #[arg(long)]
-        task: u32,
+        task: TaskRef,
         
         /// Subtask ID to remove
         #[arg(long)]
-        subtask: u32,
+        subtask: TaskRef,
+    },
+    
+    /// Move a task under another parent, or out to the top level, keeping its ID and history
+    Move {
+        /// Task ID or uid
+        id: TaskRef,
+        
+        /// The new parent task
+        #[arg(long, value_name = "PARENT", required_unless_present = "top_level")]
+        under: Option<TaskRef>,
+        
+        /// Make it a top-level task
+        #[arg(long, conflicts_with = "under")]
+        top_level: bool,
+    },
+    
+    /// Change the position of a subtask among its siblings
+    Reorder {
+        /// Parent task ID or uid
+        parent: TaskRef,
+        
+        /// The subtask to move
+        id: TaskRef,
+        
+        /// Put it just before this sibling
+        #[arg(long, value_name = "ID", required_unless_present = "after", conflicts_with = "after")]
+        before: Option<TaskRef>,
+        
+        /// Put it just after this sibling
+        #[arg(long, value_name = "ID")]
+        after: Option<TaskRef>,
     },
     
     /// Mark a task (and optionally all subtasks) as complete
     Complete {
-        /// Task ID
-        id: u32,
+        /// Task ID or uid
+        #[arg(required_unless_present = "filter", conflicts_with = "filter")]
+        id: Option<TaskRef>,
+        
+        /// Complete every task matching this query instead, e.g. "tag:release is:open"
+        #[arg(long = "where", value_name = "QUERY", allow_hyphen_values = true)]
+        filter: Option<String>,
         
         /// Also mark all subtasks as complete
         #[arg(long)]
         all: bool,
+        
+        /// Why the task was closed, for workflows that ask for one
+        #[arg(long)]
+        resolution: Option<String>,
+        
+        /// Ignore the workflow's transition rules and guards
+        #[arg(long)]
+        force: bool,
     },
-    
+
+    /// Set a task aside until a date or condition, when it returns to pending
+    Defer {
+        /// Task ID or uid
+        id: TaskRef,
+
+        /// A date (YYYY-MM-DD, tomorrow, next fri, +2w), a task to wait for (#5 or #<uid>),
+        /// dependencies (all of the task's dependencies done) or file:PATH (relative to the project root)
+        #[arg(long, value_name = "WHEN")]
+        until: String,
+
+        /// Ignore the workflow's transition rules and guards
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Start a timer on a task, stopping any timer you already have running
+    Start {
+        /// Task ID or uid
+        id: TaskRef,
+    },
+
+    /// Stop your running timer and log the time on its task
+    Stop,
+
+    /// Log time spent on a task by hand
+    LogTime {
+        /// Task ID or uid
+        id: TaskRef,
+
+        /// How long: 45m, 2h, 1h30m, 1.5h
+        duration: String,
+
+        /// What the time was spent on
+        note: Option<String>,
+
+        /// The day the work was done (default today)
+        #[arg(long, value_name = "DATE")]
+        date: Option<String>,
+    },
+
+    /// Compare estimates with actual time on done tasks, by tag and complexity
+    Estimates,
+
+    /// Report logged time by day and tag
+    Timesheet {
+        /// This week, Monday to Sunday (the default)
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        week: bool,
+
+        /// First day to include
+        #[arg(long, value_name = "DATE")]
+        from: Option<String>,
+
+        /// Last day to include (default today)
+        #[arg(long, value_name = "DATE")]
+        to: Option<String>,
+    },
+
     /// Initialize trusty in the current directory
-    Init,
+    Init {
+        /// Storage backend for this project (markdown, json, sqlite)
+        #[arg(long, value_parser = ["markdown", "json", "sqlite"])]
+        storage: Option<String>,
+        
+        /// Task ID scheme (numeric, ulid). Use ulid when tasks are created on several git branches
+        #[arg(long, value_parser = ["numeric", "ulid"])]
+        id_scheme: Option<String>,
+        
+        /// Register the trusty merge driver in .gitattributes and the local git config
+        #[arg(long)]
+        git: bool,
+    },
+    
+    /// Three-way merge of a task file; invoked by git as `trusty merge-driver %O %A %B`
+    MergeDriver {
+        /// Common ancestor version (%O)
+        base: PathBuf,
+        
+        /// Our version (%A); receives the merge result
+        ours: PathBuf,
+        
+        /// Their version (%B)
+        theirs: PathBuf,
+    },
+    
+    /// Give every task a uid and switch the project to the ulid ID scheme
+    MigrateIds,
+    
+    /// Check dependencies and subtasks for cycles, dangling IDs and shared
+    /// subtasks, and report task files that can't be read
+    Doctor {
+        /// Remove the offending links
+        #[arg(long)]
+        fix: bool,
+    },
     
     /// Add trusty project manager agent to Claude
     AddAgent {
@@ -220,7 +543,7 @@ pub enum Commands {
         keep: bool,
     },
     
-    /// Delete all tasks in the current project
+    /// Move all tasks in the current project to the trash
     Nuke {
         /// Skip confirmation prompt
         #[arg(short = 'y', long = "yes")]
@@ -230,7 +553,7 @@ pub enum Commands {
     /// Decompose a task into subtasks using AI
     Decompose {
         /// Task ID to decompose
-        id: u32,
+        id: TaskRef,
         
         /// Number of subtasks to create (default: 3-5)
         #[arg(long, default_value = "4")]
@@ -243,6 +566,10 @@ pub enum Commands {
     
     /// Get the recommended next task to work on
     Next {
+        /// Only consider tasks matching this query, e.g. tag:backend
+        #[arg(value_name = "QUERY")]
+        query: Vec<String>,
+        
         /// Automatically set the task to in-progress
         #[arg(long)]
         start: bool,
@@ -254,6 +581,10 @@ pub enum Commands {
     
     /// Identify and prune stale or completed tasks
     Prune {
+        /// Only suggest pruning tasks matching this query, e.g. tag:spike
+        #[arg(value_name = "QUERY")]
+        query: Vec<String>,
+        
         /// Run in dry-run mode (show what would be pruned without making changes)
         #[arg(long)]
         dry_run: bool,
@@ -265,6 +596,10 @@ pub enum Commands {
         /// Maximum number of tasks to suggest for pruning
         #[arg(long, default_value = "10")]
         limit: usize,
+        
+        /// Apply actions even where the workflow wouldn't allow them
+        #[arg(long)]
+        force: bool,
     },
     
     /// Import tasks from a file
@@ -283,6 +618,10 @@ pub enum Commands {
         /// Preview what would be imported without making changes
         #[arg(long)]
         preview: bool,
+        
+        /// Import statuses even where the workflow wouldn't allow them
+        #[arg(long)]
+        force: bool,
     },
     
     /// Task-specific operations
@@ -291,6 +630,53 @@ pub enum Commands {
         command: TaskCommands,
     },
     
+    /// Revert the most recent changes
+    Undo {
+        /// Number of operations to undo
+        #[arg(default_value = "1")]
+        count: usize,
+        
+        /// Undo even if the tasks were changed again afterwards
+        #[arg(long)]
+        force: bool,
+    },
+    
+    /// Reapply operations reverted by undo
+    Redo {
+        /// Number of operations to redo
+        #[arg(default_value = "1")]
+        count: usize,
+        
+        /// Redo even if the tasks were changed again afterwards
+        #[arg(long)]
+        force: bool,
+    },
+    
+    /// Show the history of changes that undo and redo work through
+    Journal {
+        /// Number of operations to show
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+    
+    /// List, restore or permanently remove deleted tasks
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommands,
+    },
+    
+    /// List or stop recurring task series
+    Recur {
+        #[command(subcommand)]
+        command: RecurCommands,
+    },
+    
+    /// Plan releases or epics and track their progress
+    Milestone {
+        #[command(subcommand)]
+        command: MilestoneCommands,
+    },
+    
     /// Analyze tasks for business/user value and suggest focus areas
     Focus {
         /// Path to Product Requirements Document (PRD) for alignment analysis
@@ -300,10 +686,6 @@ pub enum Commands {
         /// Preview analysis without making changes
         #[arg(long)]
         preview: bool,
-        
-        /// Use Claude AI for deeper value analysis
-        #[arg(long)]
-        ai: bool,
     },
 }
 
@@ -313,7 +695,7 @@ pub enum TaskCommands {
     Advice {
         /// Task ID to analyze
         #[arg(long)]
-        id: u32,
+        id: TaskRef,
         
         /// Show detailed analysis
         #[arg(long)]
@@ -331,4 +713,107 @@ pub enum TaskCommands {
         #[arg(long)]
         history: bool,
     },
-}
\ No newline at end of file
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommands {
+    /// Show deleted tasks
+    List,
+    
+    /// Bring deleted tasks back, along with the references other tasks had to them
+    Restore {
+        /// Task IDs or uids
+        #[arg(required_unless_present = "all")]
+        ids: Vec<TaskRef>,
+        
+        /// Restore everything in the trash
+        #[arg(long, conflicts_with = "ids")]
+        all: bool,
+    },
+    
+    /// Permanently remove deleted tasks
+    Purge {
+        /// Task IDs or uids
+        #[arg(required_unless_present = "all")]
+        ids: Vec<TaskRef>,
+        
+        /// Empty the trash
+        #[arg(long, conflicts_with = "ids")]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RecurCommands {
+    /// Show each recurring series and its upcoming occurrence
+    List,
+    
+    /// Stop a series from creating further occurrences; its open task stays
+    Stop {
+        /// ID or uid of any task in the series
+        id: TaskRef,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MilestoneCommands {
+    /// Create a milestone
+    Add {
+        /// Milestone name, e.g. v1.2
+        name: String,
+        
+        /// Target date: YYYY-MM-DD, next fri, +2w, ...
+        #[arg(long, value_name = "DATE")]
+        due: Option<String>,
+        
+        /// What the milestone is about
+        #[arg(long, allow_hyphen_values = true)]
+        description: Option<String>,
+    },
+    
+    /// Change a milestone's target date or description
+    Edit {
+        /// Milestone name
+        name: String,
+        
+        /// New target date (none to clear)
+        #[arg(long, value_name = "DATE")]
+        due: Option<String>,
+        
+        /// New description
+        #[arg(long, allow_hyphen_values = true)]
+        description: Option<String>,
+    },
+    
+    /// Show each milestone with its progress
+    List,
+    
+    /// Show a milestone's progress, remaining blockers and projected finish
+    Show {
+        /// Milestone name
+        name: String,
+    },
+    
+    /// Plan tasks for a milestone, taking them out of any other
+    Assign {
+        /// Milestone name
+        name: String,
+        
+        /// Task IDs or uids
+        #[arg(required = true)]
+        ids: Vec<TaskRef>,
+    },
+    
+    /// Take tasks out of their milestone
+    Unassign {
+        /// Task IDs or uids
+        #[arg(required = true)]
+        ids: Vec<TaskRef>,
+    },
+    
+    /// Delete a milestone; its tasks stay, unassigned
+    Remove {
+        /// Milestone name
+        name: String,
+    },
+}
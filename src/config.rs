@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fields::{self, FieldDef};
+use crate::storage;
+use crate::workflow::Workflow;
+
+pub const CONFIG_FILE: &str = "config";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// One markdown file per task in `.trusty/tasks/`
+    #[default]
+    Markdown,
+    /// All tasks in a single `.trusty/tasks.json` file
+    Json,
+    /// Embedded SQLite database at `.trusty/tasks.db`
+    Sqlite,
+}
+
+impl StorageBackend {
+    /// Location of the task data for this backend inside the `.trusty` directory
+    pub fn location(&self, trusty_dir: &Path) -> PathBuf {
+        match self {
+            StorageBackend::Markdown => trusty_dir.join("tasks"),
+            StorageBackend::Json => trusty_dir.join("tasks.json"),
+            StorageBackend::Sqlite => trusty_dir.join("tasks.db"),
+        }
+    }
+}
+
+impl std::str::FromStr for StorageBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "markdown" => Ok(StorageBackend::Markdown),
+            "json" => Ok(StorageBackend::Json),
+            "sqlite" => Ok(StorageBackend::Sqlite),
+            _ => anyhow::bail!("Invalid storage backend: {}. Use markdown, json, or sqlite", s),
+        }
+    }
+}
+
+impl std::fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageBackend::Markdown => write!(f, "markdown"),
+            StorageBackend::Json => write!(f, "json"),
+            StorageBackend::Sqlite => write!(f, "sqlite"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdScheme {
+    /// Sequential numeric IDs only
+    #[default]
+    Numeric,
+    /// Short random uids on disk with numeric IDs as local aliases, so tasks
+    /// created on different git branches never collide
+    Ulid,
+}
+
+impl std::str::FromStr for IdScheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "numeric" => Ok(IdScheme::Numeric),
+            "ulid" => Ok(IdScheme::Ulid),
+            _ => anyhow::bail!("Invalid ID scheme: {}. Use numeric or ulid", s),
+        }
+    }
+}
+
+impl std::fmt::Display for IdScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IdScheme::Numeric => write!(f, "numeric"),
+            IdScheme::Ulid => write!(f, "ulid"),
+        }
+    }
+}
+
+/// Project-level settings stored in `.trusty/config` (YAML)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub storage: StorageBackend,
+    #[serde(default)]
+    pub id_scheme: IdScheme,
+    /// User-defined task fields, keyed by frontmatter name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_fields: BTreeMap<String, FieldDef>,
+    /// Custom statuses, allowed transitions and guards
+    #[serde(default, skip_serializing_if = "Workflow::is_default")]
+    pub workflow: Workflow,
+    /// Count a task whose checklist is all checked as done, the way one
+    /// whose subtasks are all done is
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub checklists_complete_tasks: bool,
+}
+
+impl ProjectConfig {
+    /// Load the project config, falling back to defaults when no config file exists
+    pub fn load(trusty_dir: &Path) -> Result<Self> {
+        let path = trusty_dir.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file: {:?}", path))?;
+        let config: Self = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {:?}", path))?;
+        fields::validate_schema(&config.custom_fields)?;
+        config.workflow.validate().with_context(|| format!("Invalid workflow in {:?}", path))?;
+        
+        Ok(config)
+    }
+
+    pub fn save(&self, trusty_dir: &Path) -> Result<()> {
+        let path = trusty_dir.join(CONFIG_FILE);
+        let content = serde_yaml::to_string(self)?;
+        storage::atomic_write(&path, content.as_bytes())
+            .with_context(|| format!("Failed to write config file: {:?}", path))
+    }
+}
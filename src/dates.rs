@@ -0,0 +1,121 @@
+use anyhow::Result;
+use chrono::{Datelike, Days, Local, Months, NaiveDate, Weekday};
+
+/// Tasks due within this many days count as due soon
+pub const DUE_SOON_DAYS: i64 = 3;
+
+/// The local calendar date
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Parse a date given on the command line, relative to `today`.
+///
+/// Accepts ISO dates (`2025-03-14`), `today`, `tomorrow`, `yesterday`,
+/// weekdays (`fri`, `next friday`: the first one after today), offsets
+/// (`+3d`, `+2w`, `+1m`, `in 3 days`) and `next week` / `next month`.
+pub fn parse_date(input: &str, today: NaiveDate) -> Result<NaiveDate> {
+    let text = input.trim().to_lowercase();
+    let invalid = || anyhow::anyhow!(
+        "Invalid date '{}'. Use YYYY-MM-DD, today, tomorrow, a weekday like 'next fri', or an offset like +3d, +2w, +1m",
+        input
+    );
+
+    if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+        return Ok(date);
+    }
+
+    match text.as_str() {
+        "today" => return Ok(today),
+        "tomorrow" => return Ok(today + Days::new(1)),
+        "yesterday" => return Ok(today - Days::new(1)),
+        "next week" => return Ok(today + Days::new(7)),
+        "next month" => return today.checked_add_months(Months::new(1)).ok_or_else(invalid),
+        _ => {}
+    }
+
+    let weekday = text.strip_prefix("next ").unwrap_or(&text);
+    if let Ok(weekday) = weekday.parse::<Weekday>() {
+        let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+        return Ok(today + Days::new(if ahead == 0 { 7 } else { ahead as u64 }));
+    }
+
+    // `+3d` or `in 3 days`
+    let offset = text.strip_prefix('+')
+        .or_else(|| text.strip_prefix("in "))
+        .ok_or_else(invalid)?
+        .replace(' ', "");
+    let split = offset.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (count, unit) = offset.split_at(split);
+    let count: u32 = count.parse().map_err(|_| invalid())?;
+
+    let date = match unit {
+        "d" | "day" | "days" => today.checked_add_days(Days::new(count.into())),
+        "w" | "week" | "weeks" => today.checked_add_days(Days::new(u64::from(count) * 7)),
+        "m" | "month" | "months" => today.checked_add_months(Months::new(count)),
+        _ => None,
+    };
+    date.ok_or_else(invalid)
+}
+
+/// Parse a date for `edit`, where `none` clears it
+pub fn parse_optional_date(input: &str, today: NaiveDate) -> Result<Option<NaiveDate>> {
+    if input.trim().eq_ignore_ascii_case("none") {
+        Ok(None)
+    } else {
+        parse_date(input, today).map(Some)
+    }
+}
+
+/// The Monday of the week `date` falls in
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday().into())
+}
+
+/// `in 3 days`, `today`, `2 days ago` and so on
+pub fn describe(date: NaiveDate, today: NaiveDate) -> String {
+    match (date - today).num_days() {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 => "yesterday".to_string(),
+        days if days > 0 => format!("in {} days", days),
+        days => format!("{} days ago", -days),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_date() {
+        // A Wednesday
+        let today = date("2025-01-15");
+
+        assert_eq!(parse_date("2025-03-14", today).unwrap(), date("2025-03-14"));
+        assert_eq!(parse_date("Tomorrow", today).unwrap(), date("2025-01-16"));
+        assert_eq!(parse_date("fri", today).unwrap(), date("2025-01-17"));
+        assert_eq!(parse_date("next friday", today).unwrap(), date("2025-01-17"));
+        assert_eq!(parse_date("wed", today).unwrap(), date("2025-01-22"));
+        assert_eq!(parse_date("+3d", today).unwrap(), date("2025-01-18"));
+        assert_eq!(parse_date("+2w", today).unwrap(), date("2025-01-29"));
+        assert_eq!(parse_date("in 1 month", today).unwrap(), date("2025-02-15"));
+        assert_eq!(parse_date("next week", today).unwrap(), date("2025-01-22"));
+
+        assert!(parse_date("someday", today).is_err());
+        assert!(parse_date("+3y", today).is_err());
+        assert_eq!(parse_optional_date("none", today).unwrap(), None);
+    }
+
+    #[test]
+    fn test_describe() {
+        let today = date("2025-01-15");
+        assert_eq!(describe(date("2025-01-18"), today), "in 3 days");
+        assert_eq!(describe(date("2025-01-13"), today), "2 days ago");
+        assert_eq!(describe(today, today), "today");
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::dates;
+use crate::graph::TaskGraph;
+use crate::history::{self, HistoryChange};
+use crate::storage::TaskStore;
+use crate::task::{Task, TaskRef, TaskStatus};
+
+/// The actor recorded in history when a deferred task wakes up
+pub const WAKE_ACTOR: &str = "wake";
+
+/// Something other than a date that brings a deferred task back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum WakeCondition {
+    /// Every dependency of the task is done
+    DependenciesDone,
+    /// A file exists, relative to the project root
+    FileExists(String),
+}
+
+impl WakeCondition {
+    pub fn is_met(&self, task: &Task, graph: &TaskGraph, project_root: &Path) -> bool {
+        match self {
+            WakeCondition::DependenciesDone => task.dependencies.iter()
+                .all(|&dep| graph.get(dep).is_some_and(|t| t.status == TaskStatus::Done)),
+            WakeCondition::FileExists(path) => project_root.join(path).exists(),
+        }
+    }
+
+    /// Completes "until …"
+    pub fn describe(&self) -> String {
+        match self {
+            WakeCondition::DependenciesDone => "its dependencies are done".to_string(),
+            WakeCondition::FileExists(path) => format!("{} exists", path),
+        }
+    }
+}
+
+impl fmt::Display for WakeCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WakeCondition::DependenciesDone => write!(f, "dependencies"),
+            WakeCondition::FileExists(path) => write!(f, "file:{}", path),
+        }
+    }
+}
+
+impl FromStr for WakeCondition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s == "dependencies" || s == "deps" {
+            return Ok(WakeCondition::DependenciesDone);
+        }
+        match s.strip_prefix("file:").map(str::trim) {
+            Some(path) if !path.is_empty() => Ok(WakeCondition::FileExists(path.to_string())),
+            _ => anyhow::bail!("Invalid wake condition '{}'. Use dependencies or file:PATH", s),
+        }
+    }
+}
+
+impl From<WakeCondition> for String {
+    fn from(condition: WakeCondition) -> Self {
+        condition.to_string()
+    }
+}
+
+impl TryFrom<String> for WakeCondition {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+/// What `trusty defer --until` was given
+#[derive(Debug, Clone, PartialEq)]
+pub enum Until {
+    Date(NaiveDate),
+    /// Another task, by ID or uid: it becomes a dependency and the task
+    /// wakes once its dependencies are done
+    Task(TaskRef),
+    Condition(WakeCondition),
+}
+
+/// Parse `--until`: `#5` or `#<uid>`, `dependencies`, `file:PATH`, or a date
+/// as accepted by [`dates::parse_date`]
+pub fn parse_until(input: &str, today: NaiveDate) -> Result<Until> {
+    let text = input.trim();
+    if let Ok(id) = text.parse::<u32>() {
+        return Ok(Until::Task(TaskRef::Id(id)));
+    }
+    if let Some(task) = text.strip_prefix('#') {
+        return task.parse().map(Until::Task);
+    }
+    if text.starts_with("file:") || text == "dependencies" || text == "deps" {
+        return text.parse().map(Until::Condition);
+    }
+    dates::parse_date(text, today).map(Until::Date)
+}
+
+/// Whether a deferred task is due to come back
+pub fn should_wake(task: &Task, graph: &TaskGraph, project_root: &Path, today: NaiveDate) -> bool {
+    task.status == TaskStatus::Deferred
+        && (task.deferred_until.is_some_and(|date| date <= today)
+            || task.wake_when.as_ref().is_some_and(|condition| condition.is_met(task, graph, project_root)))
+}
+
+/// Move every deferred task whose date has come or whose condition is met
+/// back to pending. Waking up isn't held to the workflow's rules: the
+/// `defer --until` that set the date already was.
+pub fn wake_up(storage: &dyn TaskStore, project_root: &Path, today: NaiveDate) -> Result<Vec<Task>> {
+    let _lock = storage.lock()?;
+    let graph = storage.load_graph()?;
+    let _actor = history::act_as(WAKE_ACTOR);
+
+    let mut woken = Vec::new();
+    for task in graph.tasks().iter().filter(|t| should_wake(t, &graph, project_root, today)) {
+        let mut task = task.clone();
+        task.set_status(TaskStatus::Pending);
+        task.deferred_until = None;
+        task.wake_when = None;
+        storage.save_task(&task)?;
+        woken.push(task);
+    }
+    Ok(woken)
+}
+
+/// Woke up and hasn't been touched since
+pub fn is_newly_woken(task: &Task) -> bool {
+    let Some(last) = task.history.last() else {
+        return false;
+    };
+    // Waking up records the status change and the cleared date in one save
+    task.status == TaskStatus::Pending
+        && task.history.iter().rev()
+            .take_while(|event| event.at == last.at && event.actor == WAKE_ACTOR)
+            .any(|event| matches!(event.change, HistoryChange::Status { from: TaskStatus::Deferred, .. }))
+}
@@ -1,37 +1,50 @@
 use colored::*;
 use comfy_table::{Cell, Color, ContentArrangement, Table};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
+use crate::dates::{self, DUE_SOON_DAYS};
+use crate::defer;
+use crate::graph::TaskGraph;
 use crate::task::{Task, TaskStatus};
 
 pub struct TaskDisplay;
 
 impl TaskDisplay {
-    pub fn display_task_list(tasks: &[Task], project_path: &str) {
+    /// Show `tasks`, which may be a filtered subset of the tasks in `graph`
+    pub fn display_task_list(tasks: &[Task], graph: &TaskGraph, project_path: &str) {
         println!("Listing tasks from: {}", project_path.cyan());
         
-        let stats = Self::calculate_stats(tasks);
+        let stats = Self::calculate_stats(tasks, graph);
         
+        Self::display_woken(tasks);
         Self::display_dashboard(&stats);
-        Self::display_dependency_info(tasks, &stats);
-        Self::display_table(tasks);
+        Self::display_overdue(tasks);
+        Self::display_dependency_info(tasks, graph, &stats);
+        Self::display_table(tasks, graph);
         
-        if let Some(next_task) = Self::get_next_recommended_task(tasks) {
+        if let Some(next_task) = Self::get_next_recommended_task(tasks, graph) {
             Self::display_recommended_task(next_task);
         }
     }
     
-    pub fn get_next_task(tasks: &[Task]) -> Option<&Task> {
-        Self::get_next_recommended_task(tasks)
+    pub fn get_next_task<'a>(tasks: &'a [Task], graph: &TaskGraph) -> Option<&'a Task> {
+        Self::get_next_recommended_task(tasks, graph)
     }
 
-    fn calculate_stats(tasks: &[Task]) -> TaskStats {
+    fn calculate_stats(tasks: &[Task], graph: &TaskGraph) -> TaskStats {
         let mut stats = TaskStats::default();
+        let today = dates::today();
         
         for task in tasks {
+            if task.is_overdue(today) {
+                stats.overdue += 1;
+            } else if task.is_due_within(DUE_SOON_DAYS, today) {
+                stats.due_soon += 1;
+            }
+            
             match task.status {
                 TaskStatus::Done => stats.done += 1,
-                TaskStatus::InProgress => stats.in_progress += 1,
+                TaskStatus::InProgress | TaskStatus::Custom(_) => stats.in_progress += 1,
                 TaskStatus::Pending => stats.pending += 1,
                 TaskStatus::Blocked => stats.blocked += 1,
                 TaskStatus::Deferred => stats.deferred += 1,
@@ -62,18 +75,13 @@ impl TaskDisplay {
             stats.avg_deps = stats.total_deps as f32 / stats.total as f32;
         }
         
-        // Find tasks ready to work on
-        let completed: HashSet<_> = tasks.iter()
-            .filter(|t| t.status == TaskStatus::Done)
-            .map(|t| t.id)
-            .collect();
-        
+        // Find tasks ready to work on; dependencies may be done tasks that are filtered out of `tasks`
         stats.ready_tasks = tasks.iter()
-            .filter(|t| t.is_ready(&completed))
+            .filter(|t| graph.is_ready(t))
             .count();
         
         stats.blocked_by_deps = tasks.iter()
-            .filter(|t| t.status == TaskStatus::Pending && !t.dependencies.is_empty() && !t.is_ready(&completed))
+            .filter(|t| t.status == TaskStatus::Pending && !t.dependencies.is_empty() && !graph.is_ready(t))
             .count();
         
         stats
@@ -90,6 +98,7 @@ impl TaskDisplay {
 │   {:.0}% {:.0}%                                              │
 │   Done: {}  In Progress: {}  Pending: {}  Blocked: {}   │
 │   Deferred: {}  Cancelled: {}                          │
+│   Overdue: {}  Due soon: {}                            │
 │                                                      │
 │   Subtasks Progress:                                 │
 │   {} {:.0}% {:.0}%               │
@@ -106,6 +115,7 @@ impl TaskDisplay {
             stats.completion_percent, stats.completion_percent,
             stats.done, stats.in_progress, stats.pending, stats.blocked,
             stats.deferred, stats.cancelled,
+            stats.overdue, stats.due_soon,
             Self::create_progress_bar(0.0), 0.0, 0.0,
             0, 0, 0, 0,
             0, 0, 0,
@@ -117,12 +127,40 @@ impl TaskDisplay {
         println!("{}", dashboard);
     }
 
-    fn display_dependency_info(tasks: &[Task], stats: &TaskStats) {
+    /// Overdue tasks, most overdue first, in red below the dashboard
+    fn display_woken(tasks: &[Task]) {
+        let woken: Vec<&Task> = tasks.iter().filter(|t| defer::is_newly_woken(t)).collect();
+        if woken.is_empty() {
+            return;
+        }
+        
+        println!("{}", format!("⏰ {} deferred task(s) woke up:", woken.len()).cyan().bold());
+        for task in woken {
+            println!("{}", format!("   #{} {}", task.id, Self::truncate(&task.title, 50)).cyan());
+        }
+    }
+
+    fn display_overdue(tasks: &[Task]) {
+        let today = dates::today();
+        let mut overdue: Vec<&Task> = tasks.iter().filter(|t| t.is_overdue(today)).collect();
+        if overdue.is_empty() {
+            return;
+        }
+        overdue.sort_by_key(|t| (t.due, t.id));
+        
+        println!("{}", format!("⚠️  {} overdue task(s):", overdue.len()).red().bold());
+        for task in overdue {
+            let due = task.due.map(|due| format!("due {}, {}", due, dates::describe(due, today))).unwrap_or_default();
+            println!("{}", format!("   #{} {} ({})", task.id, Self::truncate(&task.title, 50), due).red());
+        }
+    }
+
+    fn display_dependency_info(tasks: &[Task], graph: &TaskGraph, stats: &TaskStats) {
         let most_depended = stats.dep_count.iter()
             .max_by_key(|(_, count)| *count)
             .map(|(id, _)| id);
         
-        let next_task = Self::get_next_recommended_task(tasks);
+        let next_task = Self::get_next_recommended_task(tasks, graph);
         
         let dep_info = format!(
             "╭──────────────────────────────────────────────────────╮
@@ -162,7 +200,7 @@ impl TaskDisplay {
         println!("{}", dep_info);
     }
 
-    fn display_table(tasks: &[Task]) {
+    fn display_table(tasks: &[Task], graph: &TaskGraph) {
         let mut table = Table::new();
         table
             .set_content_arrangement(ContentArrangement::Dynamic)
@@ -171,23 +209,31 @@ impl TaskDisplay {
                 Cell::new("Title"),
                 Cell::new("Status"),
                 Cell::new("Priority"),
+                Cell::new("Due"),
                 Cell::new("Dependencies"),
                 Cell::new("Complexity"),
             ]);
 
+        let today = dates::today();
         for task in tasks {
             // Compute effective status considering subtasks
-            let effective_status = task.compute_effective_status(tasks);
-            let status_display = if !task.subtasks.is_empty() && effective_status != task.status {
-                let (completed, total) = task.subtask_progress(tasks);
+            let effective_status = graph.effective_status(task);
+            let mut status_display = if !task.subtasks.is_empty() && effective_status != task.status {
+                let (completed, total) = graph.progress(task);
                 format!("{} ({}/{} done)", effective_status, completed, total)
+            } else if effective_status != task.status {
+                effective_status.to_string()
             } else {
                 task.status.to_string()
             };
+            if let Some((checked, total)) = task.checklist_progress() {
+                status_display.push_str(&format!(" ☑ {}/{}", checked, total));
+            }
             
             let status_cell = match effective_status {
                 TaskStatus::Done => Cell::new(status_display).fg(Color::Green),
                 TaskStatus::InProgress => Cell::new(status_display).fg(Color::Yellow),
+                TaskStatus::Custom(_) => Cell::new(status_display).fg(Color::Cyan),
                 TaskStatus::Blocked => Cell::new(status_display).fg(Color::Red),
                 TaskStatus::Cancelled => Cell::new(status_display).fg(Color::DarkGrey),
                 _ => Cell::new(status_display),
@@ -199,12 +245,21 @@ impl TaskDisplay {
                 crate::task::Priority::Low => Cell::new(task.priority.to_string()).fg(Color::Blue),
             };
 
+            let due_display = task.due.map(|due| due.to_string()).unwrap_or_default();
+            let due_cell = if task.is_overdue(today) {
+                Cell::new(due_display).fg(Color::Red)
+            } else if task.is_due_within(DUE_SOON_DAYS, today) {
+                Cell::new(due_display).fg(Color::Yellow)
+            } else {
+                Cell::new(due_display)
+            };
+
             let deps_display = if task.dependencies.is_empty() {
                 "None".to_string()
             } else {
                 task.dependencies.iter()
                     .map(|id| {
-                        if tasks.iter().any(|t| t.id == *id) {
+                        if graph.contains(*id) {
                             id.to_string()
                         } else {
                             format!("{} (Not found)", id)
@@ -214,6 +269,12 @@ impl TaskDisplay {
                     .join(", ")
             };
 
+            let title = if task.recurrence.is_some() {
+                format!("{} ↻", Self::truncate(&task.title, 33))
+            } else {
+                Self::truncate(&task.title, 35)
+            };
+
             let complexity_display = task.complexity
                 .as_ref()
                 .map(|c| c.to_string())
@@ -221,9 +282,10 @@ impl TaskDisplay {
 
             table.add_row(vec![
                 Cell::new(task.id),
-                Cell::new(Self::truncate(&task.title, 35)),
+                Cell::new(title),
                 status_cell,
                 priority_cell,
+                due_cell,
                 Cell::new(deps_display),
                 Cell::new(complexity_display),
             ]);
@@ -263,16 +325,27 @@ impl TaskDisplay {
         println!("{}", recommendation.bright_yellow());
     }
 
-    fn get_next_recommended_task(tasks: &[Task]) -> Option<&Task> {
-        let completed: HashSet<_> = tasks.iter()
-            .filter(|t| t.status == TaskStatus::Done)
-            .map(|t| t.id)
-            .collect();
-
+    /// The ready task to work on first: overdue tasks, then tasks due soon,
+    /// then by priority. Tasks scheduled or deferred to a later date wait.
+    fn get_next_recommended_task<'a>(tasks: &'a [Task], graph: &TaskGraph) -> Option<&'a Task> {
+        let today = dates::today();
         tasks.iter()
-            .filter(|t| t.is_ready(&completed))
+            .filter(|t| graph.is_ready(t) && !t.is_waiting(today))
             .min_by_key(|t| {
+                let urgency = if t.is_overdue(today) {
+                    0
+                } else if t.is_due_within(DUE_SOON_DAYS, today) {
+                    1
+                } else {
+                    2
+                };
+                // Among overdue or due-soon tasks the earlier deadline wins over priority
+                let deadline = if urgency < 2 { t.due } else { None };
                 (
+                    urgency,
+                    deadline,
+                    // Soft ordering: a task that should come after an open one waits its turn
+                    graph.should_come_later(t),
                     match t.priority {
                         crate::task::Priority::High => 0,
                         crate::task::Priority::Medium => 1,
@@ -283,10 +356,10 @@ impl TaskDisplay {
             })
     }
 
-    fn create_progress_bar(percent: f32) -> String {
-        let filled = (percent / 100.0 * 30.0) as usize;
+    pub fn create_progress_bar(percent: f32) -> String {
+        let filled = ((percent / 100.0 * 30.0) as usize).min(30);
         let empty = 30 - filled;
-        format!("{}{}", "░".repeat(filled), "░".repeat(empty))
+        format!("{}{}", "█".repeat(filled), "░".repeat(empty))
     }
 
     fn truncate(s: &str, max_len: usize) -> String {
@@ -307,6 +380,8 @@ struct TaskStats {
     blocked: usize,
     deferred: usize,
     cancelled: usize,
+    overdue: usize,
+    due_soon: usize,
     high_priority: usize,
     medium_priority: usize,
     low_priority: usize,
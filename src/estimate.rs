@@ -0,0 +1,327 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::history::HistoryChange;
+use crate::task::{Complexity, Task, TaskStatus};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum EstimateUnit {
+    Points,
+    Hours,
+}
+
+/// A task's size: the likely value, optionally with an optimistic and a
+/// pessimistic bound. Stored as text, e.g. `2/4/8h`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(into = "String", try_from = "StoredEstimate")]
+pub struct Estimate {
+    pub value: f64,
+    pub unit: EstimateUnit,
+    pub optimistic: Option<f64>,
+    pub pessimistic: Option<f64>,
+}
+
+/// A bare number is read as points, so a hand-written `sizing: 3` loads
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredEstimate {
+    Number(f64),
+    Text(String),
+}
+
+impl Estimate {
+    /// The PERT mean `(o + 4m + p) / 6` for a range, otherwise the value
+    pub fn expected(&self) -> f64 {
+        match (self.optimistic, self.pessimistic) {
+            (Some(low), Some(high)) => (low + 4.0 * self.value + high) / 6.0,
+            _ => self.value,
+        }
+    }
+}
+
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 { format!("{}", n) } else { format!("{:.1}", n) }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(low), Some(high)) = (self.optimistic, self.pessimistic) {
+            write!(f, "{}/{}/{}", low, self.value, high)?;
+        } else {
+            write!(f, "{}", self.value)?;
+        }
+        match self.unit {
+            EstimateUnit::Hours => write!(f, "h"),
+            EstimateUnit::Points => write!(f, "pt"),
+        }
+    }
+}
+
+impl FromStr for Estimate {
+    type Err = anyhow::Error;
+
+    /// `5` or `5pt` for points, `4h` for hours, or `2/4/8h` for an
+    /// optimistic/likely/pessimistic range
+    fn from_str(s: &str) -> Result<Self> {
+        let text = s.trim().to_lowercase();
+        let invalid = || anyhow::anyhow!("Invalid estimate '{}'. Use points (5, 5pt), hours (4h) or a range (2/4/8h)", s);
+
+        let (numbers, unit) = if let Some(numbers) = text.strip_suffix('h') {
+            (numbers, EstimateUnit::Hours)
+        } else {
+            (text.strip_suffix("pt").unwrap_or(&text), EstimateUnit::Points)
+        };
+        let numbers: Vec<f64> = numbers.split('/')
+            .map(|n| n.trim().parse::<f64>().ok().filter(|n| n.is_finite() && *n >= 0.0))
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+
+        let (value, optimistic, pessimistic) = match numbers[..] {
+            [value] => (value, None, None),
+            [low, value, high] if low <= value && value <= high => (value, Some(low), Some(high)),
+            [_, _, _] => anyhow::bail!("Invalid estimate '{}': the range must be optimistic/likely/pessimistic, smallest first", s),
+            _ => return Err(invalid()),
+        };
+        Ok(Estimate { value, unit, optimistic, pessimistic })
+    }
+}
+
+impl From<Estimate> for String {
+    fn from(estimate: Estimate) -> Self {
+        estimate.to_string()
+    }
+}
+
+impl TryFrom<StoredEstimate> for Estimate {
+    type Error = anyhow::Error;
+
+    fn try_from(stored: StoredEstimate) -> Result<Self> {
+        match stored {
+            StoredEstimate::Number(n) => n.to_string().parse(),
+            StoredEstimate::Text(s) => s.parse(),
+        }
+    }
+}
+
+/// Expected points and hours, summed over a task and its subtasks
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct EstimateTotal {
+    pub points: f64,
+    pub hours: f64,
+}
+
+impl EstimateTotal {
+    pub fn add(&mut self, estimate: &Estimate) {
+        match estimate.unit {
+            EstimateUnit::Points => self.points += estimate.expected(),
+            EstimateUnit::Hours => self.hours += estimate.expected(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points == 0.0 && self.hours == 0.0
+    }
+}
+
+impl fmt::Display for EstimateTotal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.hours > 0.0 {
+            parts.push(format!("{}h", format_number(self.hours)));
+        }
+        if self.points > 0.0 {
+            parts.push(format!("{}pt", format_number(self.points)));
+        }
+        write!(f, "{}", parts.join(" + "))
+    }
+}
+
+/// How long a done task actually took, in hours: the time logged on it, or
+/// else the time from when work started to completion
+pub fn actual_hours(task: &Task) -> Option<f64> {
+    if task.status != TaskStatus::Done {
+        return None;
+    }
+    if task.logged_minutes() > 0 {
+        return Some(f64::from(task.logged_minutes()) / 60.0);
+    }
+
+    let started = task.history.iter().find_map(|event| match &event.change {
+        HistoryChange::Status { to, .. } if to.is_active() => Some(event.at),
+        _ => None,
+    })?;
+    let elapsed = task.completed_at? - started;
+    Some(elapsed.num_minutes().max(1) as f64 / 60.0)
+}
+
+/// Estimated against actual time for a group of done tasks
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Accuracy {
+    pub tasks: usize,
+    pub estimated: f64,
+    pub actual_hours: f64,
+}
+
+impl Accuracy {
+    /// Actual hours per estimated hour or point
+    pub fn ratio(&self) -> Option<f64> {
+        (self.estimated > 0.0).then(|| self.actual_hours / self.estimated)
+    }
+}
+
+/// Accuracy of `unit` estimates on done tasks, grouped by `keys`. A task
+/// counts towards every group it is in.
+pub fn accuracy_by(tasks: &[Task], unit: EstimateUnit, keys: impl Fn(&Task) -> Vec<String>) -> BTreeMap<String, Accuracy> {
+    let mut groups: BTreeMap<String, Accuracy> = BTreeMap::new();
+    for task in tasks {
+        let Some(estimate) = task.estimate.as_ref().filter(|e| e.unit == unit) else {
+            continue;
+        };
+        let Some(actual) = actual_hours(task) else {
+            continue;
+        };
+        for key in keys(task) {
+            let group = groups.entry(key).or_default();
+            group.tasks += 1;
+            group.estimated += estimate.expected();
+            group.actual_hours += actual;
+        }
+    }
+    groups
+}
+
+/// A task's tags, or `(untagged)`
+pub fn tag_keys(task: &Task) -> Vec<String> {
+    if task.tags.is_empty() { vec!["(untagged)".to_string()] } else { task.tags.clone() }
+}
+
+/// A task's complexity, or `(unsized)`
+pub fn complexity_key(task: &Task) -> Vec<String> {
+    vec![task.complexity.as_ref().map_or_else(|| "(unsized)".to_string(), Complexity::to_string)]
+}
+
+/// What finished tasks say about how long work takes here
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Calibration {
+    /// Actual hours per estimated hour
+    pub hours_ratio: Option<f64>,
+    /// Actual hours per point
+    pub hours_per_point: Option<f64>,
+    /// Median actual hours by complexity
+    pub by_complexity: BTreeMap<String, f64>,
+    /// Median actual hours over all done tasks
+    pub typical_hours: Option<f64>,
+}
+
+impl Calibration {
+    pub fn from_tasks(tasks: &[Task]) -> Self {
+        let all = |_: &Task| vec![String::new()];
+        let ratio = |unit| accuracy_by(tasks, unit, all).remove("").and_then(|a| a.ratio());
+
+        let mut by_complexity: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+        let mut actuals = Vec::new();
+        for task in tasks {
+            if let Some(actual) = actual_hours(task) {
+                actuals.push(actual);
+                if let Some(complexity) = &task.complexity {
+                    by_complexity.entry(complexity.to_string()).or_default().push(actual);
+                }
+            }
+        }
+
+        Calibration {
+            hours_ratio: ratio(EstimateUnit::Hours),
+            hours_per_point: ratio(EstimateUnit::Points),
+            by_complexity: by_complexity.into_iter().map(|(k, v)| (k, median(v))).collect(),
+            typical_hours: (!actuals.is_empty()).then(|| median(actuals)),
+        }
+    }
+
+    /// Forecast hours for `task` from its estimate, or failing that from
+    /// how long done tasks of the same complexity took
+    pub fn forecast_hours(&self, task: &Task) -> Option<f64> {
+        match &task.estimate {
+            Some(estimate) if estimate.unit == EstimateUnit::Hours => Some(estimate.expected() * self.hours_ratio.unwrap_or(1.0)),
+            Some(estimate) if self.hours_per_point.is_some() => self.hours_per_point.map(|h| estimate.expected() * h),
+            _ => task.complexity.as_ref().and_then(|c| self.by_complexity.get(&c.to_string()).copied()),
+        }
+    }
+
+    /// Effort from 0 to 1, where a task forecast to take as long as a
+    /// typical done task scores 0.5. `None` without enough history.
+    pub fn effort(&self, task: &Task) -> Option<f32> {
+        let forecast = self.forecast_hours(task)?;
+        let typical = self.typical_hours.filter(|t| *t > 0.0)?;
+        Some((forecast / (forecast + typical)) as f32)
+    }
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Priority;
+    use crate::timelog::TimeEntry;
+
+    fn done(id: u32, estimate: &str, minutes: u32, complexity: Option<Complexity>) -> Task {
+        let mut task = Task::new(id, format!("Task {}", id), String::new(), Priority::Medium);
+        task.estimate = Some(estimate.parse().unwrap());
+        task.complexity = complexity;
+        task.time_log.push(TimeEntry { started_at: task.created_at, minutes, actor: "human".to_string(), note: None });
+        task.set_status(TaskStatus::Done);
+        task
+    }
+
+    #[test]
+    fn test_parse_estimates() {
+        let range: Estimate = "2/4/12h".parse().unwrap();
+        assert_eq!((range.optimistic, range.value, range.pessimistic), (Some(2.0), 4.0, Some(12.0)));
+        assert_eq!(range.expected(), 5.0);
+        assert_eq!(range.to_string(), "2/4/12h");
+        assert_eq!("5".parse::<Estimate>().unwrap().to_string(), "5pt");
+        assert_eq!("1.5h".parse::<Estimate>().unwrap().unit, EstimateUnit::Hours);
+
+        assert!("8/4/2h".parse::<Estimate>().is_err());
+        assert!("2/4h".parse::<Estimate>().is_err());
+        assert!("lots".parse::<Estimate>().is_err());
+
+        let stored: Estimate = serde_yaml::from_str("3").unwrap();
+        assert_eq!(stored.to_string(), "3pt");
+        assert_eq!(serde_yaml::to_string(&range).unwrap().trim(), "2/4/12h");
+    }
+
+    #[test]
+    fn test_calibration_from_history() {
+        let tasks = vec![
+            done(1, "2h", 180, Some(Complexity::Simple)),
+            done(2, "4h", 360, Some(Complexity::Complex)),
+            done(3, "3", 120, Some(Complexity::Simple)),
+        ];
+        let calibration = Calibration::from_tasks(&tasks);
+        assert_eq!(calibration.hours_ratio, Some(1.5));
+        assert_eq!(calibration.hours_per_point.map(|h| (h * 100.0).round()), Some(67.0));
+        assert_eq!(calibration.by_complexity["simple"], 2.5);
+        assert_eq!(calibration.typical_hours, Some(3.0));
+
+        let mut open = Task::new(4, "Open".to_string(), String::new(), Priority::Medium);
+        open.estimate = Some("2h".parse().unwrap());
+        assert_eq!(calibration.forecast_hours(&open), Some(3.0));
+        assert_eq!(calibration.effort(&open), Some(0.5));
+
+        open.estimate = None;
+        open.complexity = Some(Complexity::Complex);
+        assert_eq!(calibration.forecast_hours(&open), Some(6.0));
+
+        let by_tag = accuracy_by(&tasks, EstimateUnit::Hours, tag_keys);
+        assert_eq!(by_tag["(untagged)"], Accuracy { tasks: 2, estimated: 6.0, actual_hours: 9.0 });
+    }
+}
@@ -0,0 +1,278 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// Frontmatter keys that trusty manages itself and that custom fields may not shadow
+pub const RESERVED_KEYS: &[&str] = &[
+    "id", "title", "status", "priority", "complexity", "dependencies",
+    "subtasks", "tags", "created_at", "updated_at", "completed_at",
+    "uid", "history", "resolution", "due", "scheduled", "deferred_until",
+    "wake_when", "recurrence", "series", "time_log", "sizing", "relations",
+    "in_milestone",
+];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Number,
+    /// ISO date (`YYYY-MM-DD`)
+    Date,
+    /// One of a fixed set of `values`
+    Enum,
+}
+
+impl std::fmt::Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldType::String => write!(f, "string"),
+            FieldType::Number => write!(f, "number"),
+            FieldType::Date => write!(f, "date"),
+            FieldType::Enum => write!(f, "enum"),
+        }
+    }
+}
+
+/// A user-defined field declared under `custom_fields` in `.trusty/config`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldDef {
+    #[serde(rename = "type")]
+    pub kind: FieldType,
+    /// Allowed values for `enum` fields
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl FieldDef {
+    /// Parse a command-line value into the YAML value stored in the task frontmatter
+    pub fn parse_value(&self, name: &str, raw: &str) -> Result<Value> {
+        let raw = raw.trim();
+        match self.kind {
+            FieldType::String => Ok(Value::String(raw.to_string())),
+            FieldType::Number => {
+                if let Ok(n) = raw.parse::<i64>() {
+                    Ok(Value::Number(n.into()))
+                } else if let Ok(n) = raw.parse::<f64>() {
+                    Ok(Value::Number(n.into()))
+                } else {
+                    anyhow::bail!("Invalid value for field '{}': '{}' is not a number", name, raw)
+                }
+            }
+            FieldType::Date => {
+                let date = NaiveDate::parse_from_str(raw, "%Y-%m-%d").map_err(|_| {
+                    anyhow::anyhow!("Invalid value for field '{}': '{}' is not a date (use YYYY-MM-DD)", name, raw)
+                })?;
+                Ok(Value::String(date.format("%Y-%m-%d").to_string()))
+            }
+            FieldType::Enum => {
+                if self.values.iter().any(|v| v == raw) {
+                    Ok(Value::String(raw.to_string()))
+                } else {
+                    anyhow::bail!(
+                        "Invalid value for field '{}': '{}'. Use one of: {}",
+                        name, raw, self.values.join(", ")
+                    )
+                }
+            }
+        }
+    }
+
+    /// Order two stored values according to the field type
+    fn compare(&self, a: &Value, b: &Value) -> Option<Ordering> {
+        match self.kind {
+            FieldType::Number => a.as_f64()?.partial_cmp(&b.as_f64()?),
+            FieldType::Date => {
+                let a = NaiveDate::parse_from_str(&format_value(a), "%Y-%m-%d").ok()?;
+                let b = NaiveDate::parse_from_str(&format_value(b), "%Y-%m-%d").ok()?;
+                Some(a.cmp(&b))
+            }
+            FieldType::String | FieldType::Enum => Some(format_value(a).cmp(&format_value(b))),
+        }
+    }
+}
+
+/// Check a custom field schema for names that collide with built-in keys
+pub fn validate_schema(schema: &BTreeMap<String, FieldDef>) -> Result<()> {
+    for (name, def) in schema {
+        if RESERVED_KEYS.contains(&name.as_str()) {
+            anyhow::bail!("Custom field '{}' clashes with a built-in task field", name);
+        }
+        if def.kind == FieldType::Enum && def.values.is_empty() {
+            anyhow::bail!("Enum field '{}' must list its allowed values", name);
+        }
+    }
+    Ok(())
+}
+
+/// Look up a field in the schema with a helpful error for unknown names
+pub fn lookup<'a>(schema: &'a BTreeMap<String, FieldDef>, name: &str) -> Result<&'a FieldDef> {
+    schema.get(name).ok_or_else(|| {
+        if schema.is_empty() {
+            anyhow::anyhow!("Unknown custom field '{}'. Define it under custom_fields in .trusty/config", name)
+        } else {
+            anyhow::anyhow!(
+                "Unknown custom field '{}'. Defined fields: {}",
+                name, schema.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        }
+    })
+}
+
+/// Parse a `key=value` assignment against the schema
+pub fn parse_assignment(schema: &BTreeMap<String, FieldDef>, assignment: &str) -> Result<(String, Value)> {
+    let (name, raw) = assignment.split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Invalid field assignment '{}'. Use KEY=VALUE", assignment))?;
+    let name = name.trim();
+    let def = lookup(schema, name)?;
+    Ok((name.to_string(), def.parse_value(name, raw)?))
+}
+
+/// Render a stored value for display
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Null => "~".to_string(),
+        other => serde_yaml::to_string(other).unwrap_or_default().trim_end().to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A `list --field` filter such as `estimate>=3` or `team=web`
+#[derive(Debug, Clone)]
+pub struct FieldFilter {
+    pub name: String,
+    pub op: FilterOp,
+    pub value: Value,
+    def: FieldDef,
+}
+
+impl FieldFilter {
+    pub fn parse(schema: &BTreeMap<String, FieldDef>, expr: &str) -> Result<Self> {
+        // Two-character operators first so `>=` is not read as `>`
+        const OPS: &[(&str, FilterOp)] = &[
+            ("!=", FilterOp::Ne),
+            ("<=", FilterOp::Le),
+            (">=", FilterOp::Ge),
+            ("=", FilterOp::Eq),
+            ("<", FilterOp::Lt),
+            (">", FilterOp::Gt),
+        ];
+
+        let (idx, token, op) = OPS.iter()
+            .filter_map(|(token, op)| expr.find(token).map(|idx| (idx, *token, *op)))
+            .min_by_key(|(idx, token, _)| (*idx, std::cmp::Reverse(token.len())))
+            .ok_or_else(|| anyhow::anyhow!("Invalid field filter '{}'. Use KEY=VALUE, KEY!=VALUE, KEY<VALUE or KEY>VALUE", expr))?;
+
+        let name = expr[..idx].trim();
+        let def = lookup(schema, name)?;
+
+        if matches!(op, FilterOp::Lt | FilterOp::Le | FilterOp::Gt | FilterOp::Ge)
+            && matches!(def.kind, FieldType::String | FieldType::Enum)
+        {
+            anyhow::bail!("Field '{}' is a {} field and only supports = and !=", name, def.kind);
+        }
+
+        let value = def.parse_value(name, &expr[idx + token.len()..])?;
+
+        Ok(Self {
+            name: name.to_string(),
+            op,
+            value,
+            def: def.clone(),
+        })
+    }
+
+    pub fn matches(&self, fields: &BTreeMap<String, Value>) -> bool {
+        let Some(actual) = fields.get(&self.name) else {
+            return self.op == FilterOp::Ne;
+        };
+
+        match self.def.compare(actual, &self.value) {
+            Some(ordering) => match self.op {
+                FilterOp::Eq => ordering == Ordering::Equal,
+                FilterOp::Ne => ordering != Ordering::Equal,
+                FilterOp::Lt => ordering == Ordering::Less,
+                FilterOp::Le => ordering != Ordering::Greater,
+                FilterOp::Gt => ordering == Ordering::Greater,
+                FilterOp::Ge => ordering != Ordering::Less,
+            },
+            // Stored value doesn't fit the schema (e.g. hand-edited); only `!=` can match
+            None => self.op == FilterOp::Ne,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> BTreeMap<String, FieldDef> {
+        let mut schema = BTreeMap::new();
+        schema.insert("estimate".to_string(), FieldDef { kind: FieldType::Number, values: vec![], description: None });
+        schema.insert("review".to_string(), FieldDef { kind: FieldType::Date, values: vec![], description: None });
+        schema.insert("jira".to_string(), FieldDef { kind: FieldType::String, values: vec![], description: None });
+        schema.insert("team".to_string(), FieldDef {
+            kind: FieldType::Enum,
+            values: vec!["web".to_string(), "api".to_string()],
+            description: None,
+        });
+        schema
+    }
+
+    #[test]
+    fn test_parse_assignment_by_type() {
+        let schema = schema();
+
+        assert_eq!(parse_assignment(&schema, "estimate=3").unwrap().1, Value::Number(3.into()));
+        assert_eq!(parse_assignment(&schema, "estimate=1.5").unwrap().1, Value::Number(1.5.into()));
+        assert_eq!(parse_assignment(&schema, "review=2024-03-01").unwrap().1, Value::String("2024-03-01".to_string()));
+        assert_eq!(parse_assignment(&schema, "jira=PROJ-1=2").unwrap().1, Value::String("PROJ-1=2".to_string()));
+        assert!(parse_assignment(&schema, "estimate=lots").is_err());
+        assert!(parse_assignment(&schema, "review=tomorrow").is_err());
+        assert!(parse_assignment(&schema, "team=mobile").is_err());
+        assert!(parse_assignment(&schema, "unknown=1").is_err());
+    }
+
+    #[test]
+    fn test_field_filters() {
+        let schema = schema();
+        let mut fields = BTreeMap::new();
+        fields.insert("estimate".to_string(), Value::Number(5.into()));
+        fields.insert("review".to_string(), Value::String("2024-03-01".to_string()));
+        fields.insert("team".to_string(), Value::String("web".to_string()));
+
+        assert!(FieldFilter::parse(&schema, "estimate>=5").unwrap().matches(&fields));
+        assert!(!FieldFilter::parse(&schema, "estimate<5").unwrap().matches(&fields));
+        assert!(FieldFilter::parse(&schema, "review>2024-02-28").unwrap().matches(&fields));
+        assert!(FieldFilter::parse(&schema, "team=web").unwrap().matches(&fields));
+        assert!(FieldFilter::parse(&schema, "team!=api").unwrap().matches(&fields));
+        assert!(!FieldFilter::parse(&schema, "jira=X").unwrap().matches(&fields));
+        assert!(FieldFilter::parse(&schema, "jira!=X").unwrap().matches(&fields));
+        assert!(FieldFilter::parse(&schema, "team>web").is_err());
+    }
+
+    #[test]
+    fn test_schema_rejects_reserved_names() {
+        let mut schema = schema();
+        assert!(validate_schema(&schema).is_ok());
+
+        schema.insert("status".to_string(), FieldDef { kind: FieldType::String, values: vec![], description: None });
+        assert!(validate_schema(&schema).is_err());
+    }
+}
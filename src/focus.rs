@@ -1,11 +1,10 @@
 use anyhow::Result;
 use colored::*;
-use std::collections::HashMap;
 use std::path::PathBuf;
 use std::fs;
+use crate::estimate::Calibration;
 use crate::task::{Task, Priority};
-use crate::storage::TaskStorage;
-use crate::claude_integration;
+use crate::storage::TaskStore;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ValueCategory {
@@ -22,14 +21,12 @@ pub struct FocusAssessment {
     pub value_score: f32,           // 0.0 to 1.0
     pub effort_score: f32,          // 0.0 to 1.0
     pub impact_score: f32,          // 0.0 to 1.0
-    pub alignment_score: f32,       // 0.0 to 1.0 (with PRD if provided)
     pub blocking_value: f32,        // How much value this unblocks
     pub recommendation: String,
-    pub reasoning: Vec<String>,
 }
 
-pub struct FocusAnalyzer {
-    storage: TaskStorage,
+pub struct FocusAnalyzer<'a> {
+    storage: &'a dyn TaskStore,
     prd_content: Option<String>,
 }
 
@@ -52,8 +49,8 @@ pub struct ReprioritizationSuggestion {
     pub reason: String,
 }
 
-impl FocusAnalyzer {
-    pub fn new(storage: TaskStorage) -> Self {
+impl<'a> FocusAnalyzer<'a> {
+    pub fn new(storage: &'a dyn TaskStore) -> Self {
         Self {
             storage,
             prd_content: None,
@@ -66,9 +63,10 @@ impl FocusAnalyzer {
     }
 
     pub fn analyze_all_tasks(&self) -> Result<FocusReport> {
-        let all_tasks = self.storage.list_all_tasks()?;
-        let pending_tasks: Vec<Task> = all_tasks.into_iter()
-            .filter(|t| matches!(t.status, crate::task::TaskStatus::Pending | crate::task::TaskStatus::InProgress))
+        let graph = self.storage.load_graph()?;
+        let pending_tasks: Vec<Task> = graph.tasks().iter()
+            .filter(|t| t.status == crate::task::TaskStatus::Pending || t.status.is_active())
+            .cloned()
             .collect();
 
         if pending_tasks.is_empty() {
@@ -83,39 +81,29 @@ impl FocusAnalyzer {
             });
         }
 
-        // Use Claude to analyze tasks if available
-        let ai_analysis = if self.prd_content.is_some() || pending_tasks.len() > 3 {
-            self.get_ai_value_analysis(&pending_tasks)?
-        } else {
-            None
-        };
+        // Effort is sized against how long finished tasks actually took
+        let calibration = Calibration::from_tasks(graph.tasks());
 
         // Assess each task
         let mut assessments = Vec::new();
         for task in pending_tasks {
-            let assessment = if let Some(ref ai) = ai_analysis {
-                self.assess_task_with_ai(&task, ai)
-            } else {
-                self.assess_task_locally(&task)
-            };
-            assessments.push(assessment);
+            let dependents = graph.dependents(task.id);
+            assessments.push(self.assess_task_locally(&task, &dependents, &calibration));
         }
 
         // Generate report
         self.generate_report(assessments)
     }
 
-    fn assess_task_locally(&self, task: &Task) -> FocusAssessment {
-        let mut reasoning = Vec::new();
-        
+    fn assess_task_locally(&self, task: &Task, dependents: &[&Task], calibration: &Calibration) -> FocusAssessment {
         // Determine value category based on heuristics
-        let value_category = self.categorize_task_value(task, &mut reasoning);
+        let value_category = self.categorize_task_value(task);
         
         // Calculate scores
         let value_score = self.calculate_value_score(task, &value_category);
-        let effort_score = self.calculate_effort_score(task);
-        let impact_score = self.calculate_impact_score(task);
-        let blocking_value = self.calculate_blocking_value(task);
+        let effort_score = self.calculate_effort_score(task, calibration);
+        let impact_score = self.calculate_impact_score(task, dependents);
+        let blocking_value = self.calculate_blocking_value(dependents);
         
         // Generate recommendation
         let recommendation = self.generate_recommendation(&value_category, value_score, effort_score);
@@ -126,14 +114,12 @@ impl FocusAnalyzer {
             value_score,
             effort_score,
             impact_score,
-            alignment_score: 0.5, // Default without PRD
             blocking_value,
             recommendation,
-            reasoning,
         }
     }
 
-    fn categorize_task_value(&self, task: &Task, reasoning: &mut Vec<String>) -> ValueCategory {
+    fn categorize_task_value(&self, task: &Task) -> ValueCategory {
         // Keywords that suggest strategic importance
         let strategic_keywords = ["user", "customer", "revenue", "core", "critical", "launch", "mvp", "release"];
         let maintenance_keywords = ["fix", "bug", "refactor", "cleanup", "technical debt", "update dependencies"];
@@ -145,35 +131,30 @@ impl FocusAnalyzer {
         
         // Check for strategic keywords
         if strategic_keywords.iter().any(|&kw| combined.contains(kw)) {
-            reasoning.push("Contains strategic keywords suggesting user/business value".to_string());
             return ValueCategory::Strategic;
         }
         
         // High priority tasks are often strategic
         if task.priority == Priority::High && !maintenance_keywords.iter().any(|&kw| combined.contains(kw)) {
-            reasoning.push("High priority task without maintenance indicators".to_string());
             return ValueCategory::Strategic;
         }
         
         // Check for maintenance work
         if maintenance_keywords.iter().any(|&kw| combined.contains(kw)) {
-            reasoning.push("Appears to be maintenance or technical debt work".to_string());
             return ValueCategory::Maintenance;
         }
         
         // Check for speculative work
         if speculative_keywords.iter().any(|&kw| combined.contains(kw)) {
-            reasoning.push("Appears to be exploratory or speculative work".to_string());
             return ValueCategory::Speculative;
         }
         
         // Default to tactical
-        reasoning.push("Standard implementation task".to_string());
         ValueCategory::Tactical
     }
 
     fn calculate_value_score(&self, task: &Task, category: &ValueCategory) -> f32 {
-        let base_score = match category {
+        let base_score: f32 = match category {
             ValueCategory::Strategic => 0.8,
             ValueCategory::Tactical => 0.6,
             ValueCategory::Maintenance => 0.4,
@@ -190,13 +171,14 @@ impl FocusAnalyzer {
         (base_score + priority_modifier).min(1.0)
     }
 
-    fn calculate_effort_score(&self, task: &Task) -> f32 {
-        let complexity_score = match task.complexity.as_ref() {
+    fn calculate_effort_score(&self, task: &Task, calibration: &Calibration) -> f32 {
+        // The forecast from estimates and past actuals, falling back to a guess from complexity
+        let complexity_score = calibration.effort(task).unwrap_or(match task.complexity.as_ref() {
             Some(crate::task::Complexity::Simple) => 0.2,
             Some(crate::task::Complexity::Medium) => 0.5,
             Some(crate::task::Complexity::Complex) => 0.8,
             None => 0.5, // Default to medium
-        };
+        });
         
         // More subtasks = more effort
         let subtask_modifier = (task.subtasks.len() as f32 * 0.1).min(0.2);
@@ -204,15 +186,10 @@ impl FocusAnalyzer {
         (complexity_score + subtask_modifier).min(1.0)
     }
 
-    fn calculate_impact_score(&self, task: &Task) -> f32 {
+    fn calculate_impact_score(&self, task: &Task, dependents: &[&Task]) -> f32 {
         // Tasks with many dependents have high impact
-        let all_tasks = self.storage.list_all_tasks().unwrap_or_default();
-        let dependent_count = all_tasks.iter()
-            .filter(|t| t.dependencies.contains(&task.id))
-            .count();
-        
-        let dependency_score = (dependent_count as f32 * 0.2).min(0.6);
-        let value_modifier = match self.categorize_task_value(task, &mut vec![]) {
+        let dependency_score = (dependents.len() as f32 * 0.2).min(0.6);
+        let value_modifier = match self.categorize_task_value(task) {
             ValueCategory::Strategic => 0.4,
             ValueCategory::Tactical => 0.2,
             _ => 0.1,
@@ -221,15 +198,10 @@ impl FocusAnalyzer {
         dependency_score + value_modifier
     }
 
-    fn calculate_blocking_value(&self, task: &Task) -> f32 {
-        let all_tasks = self.storage.list_all_tasks().unwrap_or_default();
-        let blocked_tasks: Vec<&Task> = all_tasks.iter()
-            .filter(|t| t.dependencies.contains(&task.id))
-            .collect();
-        
+    fn calculate_blocking_value(&self, dependents: &[&Task]) -> f32 {
         // Sum up the value of blocked tasks
-        blocked_tasks.iter()
-            .map(|t| self.calculate_value_score(t, &self.categorize_task_value(t, &mut vec![])))
+        dependents.iter()
+            .map(|t| self.calculate_value_score(t, &self.categorize_task_value(t)))
             .sum::<f32>()
             .min(1.0)
     }
@@ -257,18 +229,6 @@ impl FocusAnalyzer {
         }
     }
 
-    fn get_ai_value_analysis(&self, tasks: &[Task]) -> Result<Option<HashMap<u32, AIValueInsight>>> {
-        // This would call Claude to analyze all tasks for value
-        // For now, returning None to use local analysis
-        Ok(None)
-    }
-
-    fn assess_task_with_ai(&self, task: &Task, ai_insights: &HashMap<u32, AIValueInsight>) -> FocusAssessment {
-        // Would combine AI insights with local analysis
-        // For now, falling back to local assessment
-        self.assess_task_locally(task)
-    }
-
     fn generate_report(&self, mut assessments: Vec<FocusAssessment>) -> Result<FocusReport> {
         // Sort by value score descending
         assessments.sort_by(|a, b| b.value_score.partial_cmp(&a.value_score).unwrap());
@@ -291,7 +251,7 @@ impl FocusAnalyzer {
         // Generate reprioritization suggestions
         let mut reprioritization_suggestions = Vec::new();
         for assessment in &assessments {
-            let suggested_priority = self.suggest_priority(&assessment);
+            let suggested_priority = self.suggest_priority(assessment);
             if suggested_priority != assessment.task.priority {
                 reprioritization_suggestions.push(ReprioritizationSuggestion {
                     task_id: assessment.task.id,
@@ -372,15 +332,6 @@ impl FocusAnalyzer {
     }
 }
 
-// Placeholder for AI insights structure
-struct AIValueInsight {
-    task_id: u32,
-    business_value: f32,
-    user_impact: f32,
-    strategic_alignment: f32,
-    technical_debt_reduction: f32,
-}
-
 impl std::fmt::Display for ValueCategory {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
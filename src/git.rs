@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::storage;
+
+const DRIVER_NAME: &str = "trusty";
+
+/// Files inside `.trusty` that belong to one checkout and shouldn't be committed
+const LOCAL_FILES: &[&str] = &["lock", "next_id", "journal", "index"];
+
+/// Run the merge driver on git's `%O %A %B` files, writing the result to `%A`.
+///
+/// Returns false when the merge left conflict markers, which git expects to
+/// be reported through a non-zero exit status.
+pub fn run_merge_driver(base: &Path, ours: &Path, theirs: &Path) -> Result<bool> {
+    let read = |path: &Path| fs::read_to_string(path)
+        .with_context(|| format!("Failed to read merge input: {:?}", path));
+
+    let outcome = storage::merge_task_files(&read(base)?, &read(ours)?, &read(theirs)?)?;
+    storage::atomic_write(ours, outcome.content.as_bytes())?;
+
+    Ok(!outcome.conflicted)
+}
+
+/// Register the merge driver for `tasks_dir` in `.gitattributes` and the local git config
+pub fn install_merge_driver(trusty_dir: &Path, tasks_dir: &Path) -> Result<PathBuf> {
+    let root = PathBuf::from(git(trusty_dir, &["rev-parse", "--show-toplevel"])?.trim());
+
+    // Git matches attribute patterns against paths relative to the repository root
+    let tasks_dir = fs::canonicalize(tasks_dir)
+        .with_context(|| format!("Failed to resolve task directory: {:?}", tasks_dir))?;
+    let relative = tasks_dir.strip_prefix(fs::canonicalize(&root)?)
+        .with_context(|| format!("{:?} is not inside the git repository at {:?}", tasks_dir, root))?;
+    let pattern = format!("{}/*.md", relative.to_string_lossy().replace('\\', "/"));
+    let rule = format!("{} merge={}", pattern, DRIVER_NAME);
+
+    let attributes_path = root.join(".gitattributes");
+    let mut attributes = fs::read_to_string(&attributes_path).unwrap_or_default();
+    if !attributes.lines().any(|line| line.trim() == rule) {
+        if !attributes.is_empty() && !attributes.ends_with('\n') {
+            attributes.push('\n');
+        }
+        attributes.push_str(&rule);
+        attributes.push('\n');
+        fs::write(&attributes_path, attributes)
+            .with_context(|| format!("Failed to write {:?}", attributes_path))?;
+    }
+
+    git(trusty_dir, &["config", &format!("merge.{}.name", DRIVER_NAME), "trusty task file merge"])?;
+    git(trusty_dir, &["config", &format!("merge.{}.driver", DRIVER_NAME), "trusty merge-driver %O %A %B"])?;
+
+    let ignore_path = trusty_dir.join(".gitignore");
+    let mut ignore = fs::read_to_string(&ignore_path).unwrap_or_default();
+    for file in LOCAL_FILES {
+        if !ignore.lines().any(|line| line.trim() == *file) {
+            ignore.push_str(file);
+            ignore.push('\n');
+        }
+    }
+    fs::write(&ignore_path, ignore)
+        .with_context(|| format!("Failed to write {:?}", ignore_path))?;
+
+    Ok(attributes_path)
+}
+
+/// Run git in `dir`, which may be a project other than the working directory
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git. Is it installed?")?;
+
+    if !output.status.success() {
+        anyhow::bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,531 @@
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::estimate::EstimateTotal;
+use crate::relation::{Relation, RelationKind};
+use crate::task::{Task, TaskStatus};
+
+/// A dependency or subtask link that breaks the graph's invariants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    SelfDependency(u32),
+    SelfSubtask(u32),
+    MissingDependency { task: u32, dep: u32 },
+    MissingSubtask { task: u32, subtask: u32 },
+    /// Each task depends on the next; the last one is the first again
+    DependencyCycle(Vec<u32>),
+    /// Each task is a subtask of the previous; the last one is the first again
+    SubtaskCycle(Vec<u32>),
+    MultipleParents { subtask: u32, parents: Vec<u32> },
+    DuplicateSubtask { task: u32, subtask: u32 },
+    SelfRelation { task: u32, kind: RelationKind },
+    MissingRelation { task: u32, kind: RelationKind, related: u32 },
+}
+
+/// A task's dependencies in ID order, so that walks are deterministic
+fn dependencies(task: &Task) -> Vec<u32> {
+    let mut deps: Vec<u32> = task.dependencies.iter().copied().collect();
+    deps.sort();
+    deps
+}
+
+fn path(ids: &[u32]) -> String {
+    ids.iter().map(|id| format!("#{}", id)).collect::<Vec<_>>().join(" → ")
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::SelfDependency(id) => write!(f, "Task #{} depends on itself", id),
+            Problem::SelfSubtask(id) => write!(f, "Task #{} is its own subtask", id),
+            Problem::MissingDependency { task, dep } => write!(f, "Task #{} depends on #{}, which doesn't exist", task, dep),
+            Problem::MissingSubtask { task, subtask } => write!(f, "Task #{} lists subtask #{}, which doesn't exist", task, subtask),
+            Problem::DependencyCycle(cycle) => write!(f, "Dependency cycle: {}", path(cycle)),
+            Problem::SubtaskCycle(cycle) => write!(f, "Subtask cycle: {}", path(cycle)),
+            Problem::MultipleParents { subtask, parents } => write!(
+                f,
+                "Task #{} is a subtask of {}",
+                subtask,
+                parents.iter().map(|id| format!("#{}", id)).collect::<Vec<_>>().join(" and ")
+            ),
+            Problem::DuplicateSubtask { task, subtask } => write!(f, "Task #{} lists subtask #{} more than once", task, subtask),
+            Problem::SelfRelation { task, kind } => write!(f, "Task #{} {} itself", task, kind),
+            Problem::MissingRelation { task, kind, related } => write!(f, "Task #{} {} #{}, which doesn't exist", task, kind, related),
+        }
+    }
+}
+
+impl std::error::Error for Problem {}
+
+impl Problem {
+    /// Remove the link behind this problem from `tasks`, describing the
+    /// change. A cycle loses the link that closes it; a subtask with several
+    /// parents stays with the lowest-ID one.
+    pub fn fix(&self, tasks: &mut HashMap<u32, Task>) -> Option<String> {
+        let fixed = match self {
+            Problem::SelfDependency(id) => {
+                tasks.get_mut(id)?.remove_dependency(*id);
+                format!("Removed #{}'s dependency on itself", id)
+            }
+            Problem::SelfSubtask(id) => {
+                tasks.get_mut(id)?.subtasks.retain(|s| s != id);
+                format!("Removed #{} from its own subtasks", id)
+            }
+            Problem::MissingDependency { task, dep } => {
+                tasks.get_mut(task)?.remove_dependency(*dep);
+                format!("Removed missing dependency #{} from #{}", dep, task)
+            }
+            Problem::MissingSubtask { task, subtask } => {
+                tasks.get_mut(task)?.subtasks.retain(|s| s != subtask);
+                format!("Removed missing subtask #{} from #{}", subtask, task)
+            }
+            Problem::DependencyCycle(cycle) => {
+                let [.., from, to] = cycle.as_slice() else { return None };
+                tasks.get_mut(from)?.remove_dependency(*to);
+                format!("Removed #{} from the dependencies of #{}", to, from)
+            }
+            Problem::SubtaskCycle(cycle) => {
+                let [.., parent, child] = cycle.as_slice() else { return None };
+                tasks.get_mut(parent)?.subtasks.retain(|s| s != child);
+                format!("Removed subtask #{} from #{}", child, parent)
+            }
+            Problem::MultipleParents { subtask, parents } => {
+                let (keep, others) = parents.split_first()?;
+                for parent in others {
+                    tasks.get_mut(parent)?.subtasks.retain(|s| s != subtask);
+                }
+                format!("Kept #{} only under #{}", subtask, keep)
+            }
+            Problem::DuplicateSubtask { task, subtask } => {
+                let mut seen = false;
+                tasks.get_mut(task)?.subtasks.retain(|s| s != subtask || !std::mem::replace(&mut seen, true));
+                format!("Removed duplicate subtask #{} from #{}", subtask, task)
+            }
+            Problem::SelfRelation { task, kind } => {
+                tasks.get_mut(task)?.remove_relation(*kind, *task);
+                format!("Removed #{}'s {} link to itself", task, kind)
+            }
+            Problem::MissingRelation { task, kind, related } => {
+                tasks.get_mut(task)?.remove_relation(*kind, *related);
+                format!("Removed missing {} link #{} from #{}", kind, related, task)
+            }
+        };
+        Some(fixed)
+    }
+}
+
+/// All of a project's tasks, indexed for the lookups that commands otherwise
+/// do with a linear scan per task.
+///
+/// Built once per command from `list_all_tasks`. Effective statuses are
+/// computed on first use and remembered, so asking for every task's status
+/// walks each subtask tree only once.
+pub struct TaskGraph {
+    /// Sorted by ID, like `list_all_tasks` returns them
+    tasks: Vec<Task>,
+    positions: HashMap<u32, usize>,
+    edges: ReverseEdges,
+    /// Whether a fully checked checklist makes a task effectively done
+    checklists: bool,
+    effective: RefCell<HashMap<u32, TaskStatus>>,
+}
+
+/// The links pointing at each task, the reverse of what task files hold.
+/// The markdown backend keeps them in its index between commands.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReverseEdges {
+    /// Subtask ID → the task that lists it. A subtask claimed by several
+    /// parents keeps the one with the lowest ID.
+    parents: HashMap<u32, u32>,
+    /// Task ID → tasks that depend on it, in ID order
+    dependents: HashMap<u32, Vec<u32>>,
+    /// Task ID → links other tasks hold to it, as (kind, holder), in ID order
+    inverse_relations: HashMap<u32, Vec<(RelationKind, u32)>>,
+}
+
+impl ReverseEdges {
+    /// Collect the edges of `tasks`, which must be sorted by ID
+    pub fn new(tasks: &[Task]) -> Self {
+        let mut edges = Self::default();
+        for task in tasks {
+            for &subtask in &task.subtasks {
+                edges.parents.entry(subtask).or_insert(task.id);
+            }
+            for &dep in &task.dependencies {
+                edges.dependents.entry(dep).or_default().push(task.id);
+            }
+            for relation in &task.relations {
+                edges.inverse_relations.entry(relation.task).or_default().push((relation.kind, task.id));
+            }
+        }
+        edges
+    }
+}
+
+impl TaskGraph {
+    pub fn new(mut tasks: Vec<Task>) -> Self {
+        tasks.sort_by_key(|t| t.id);
+        let edges = ReverseEdges::new(&tasks);
+        Self::with_edges(tasks, edges)
+    }
+
+    /// A graph of `tasks`, sorted by ID, whose reverse edges are already known
+    pub fn with_edges(tasks: Vec<Task>, edges: ReverseEdges) -> Self {
+        let positions = tasks.iter().enumerate().map(|(position, task)| (task.id, position)).collect();
+        Self { tasks, positions, edges, checklists: false, effective: RefCell::new(HashMap::new()) }
+    }
+
+    /// Roll checklists up into effective statuses too, as the project's
+    /// `checklists_complete_tasks` setting asks
+    pub fn with_checklists(mut self, enabled: bool) -> Self {
+        self.checklists = enabled;
+        self
+    }
+
+    /// All tasks, sorted by ID
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Task> {
+        self.positions.get(&id).map(|&position| &self.tasks[position])
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.positions.contains_key(&id)
+    }
+
+    /// The task that lists `id` as a subtask
+    pub fn parent(&self, id: u32) -> Option<&Task> {
+        self.edges.parents.get(&id).and_then(|&parent| self.get(parent))
+    }
+
+    /// Tasks that depend on `id`
+    pub fn dependents(&self, id: u32) -> Vec<&Task> {
+        self.edges.dependents.get(&id)
+            .map(|ids| ids.iter().filter_map(|&dependent| self.get(dependent)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Links other tasks hold to `id`, as (kind, the task holding the link)
+    pub fn inverse_relations(&self, id: u32) -> Vec<(RelationKind, &Task)> {
+        self.edges.inverse_relations.get(&id)
+            .map(|links| links.iter().filter_map(|&(kind, holder)| Some((kind, self.get(holder)?))).collect())
+            .unwrap_or_default()
+    }
+
+    /// Every link of `task` as it reads from `task`: its own links by name
+    /// and other tasks' links by inverse name (`duplicated-by`, `before`),
+    /// sorted by name and ID
+    pub fn relations(&self, task: &Task) -> Vec<(&'static str, u32)> {
+        let mut links: Vec<(&'static str, u32)> = task.relations.iter()
+            .map(|r| (r.kind.name(), r.task))
+            .chain(self.inverse_relations(task.id).into_iter().map(|(kind, holder)| (kind.inverse_name(), holder.id)))
+            .collect();
+        links.sort();
+        // Two tasks that each say they relate to the other are one link
+        links.dedup();
+        links
+    }
+
+    /// Whether `task` should wait for an `after` task that is still open.
+    /// This only orders suggestions; it never blocks the task.
+    pub fn should_come_later(&self, task: &Task) -> bool {
+        task.relations.iter()
+            .filter(|r| r.kind == RelationKind::After)
+            .any(|r| self.get(r.task).is_some_and(|t| !t.status.is_closed()))
+    }
+
+    /// The subtasks of `task` that exist, in order
+    pub fn subtasks(&self, task: &Task) -> Vec<&Task> {
+        task.subtasks.iter().filter_map(|&id| self.get(id)).collect()
+    }
+
+    /// Parent, grandparent and so on, nearest first
+    pub fn ancestors(&self, id: u32) -> Vec<&Task> {
+        let mut ancestors = Vec::new();
+        let mut seen = HashSet::from([id]);
+        let mut current = id;
+
+        while let Some(parent) = self.parent(current) {
+            // A subtask cycle would otherwise loop forever
+            if !seen.insert(parent.id) {
+                break;
+            }
+            ancestors.push(parent);
+            current = parent.id;
+        }
+
+        ancestors
+    }
+
+    /// Every task below `id` in the subtask tree, depth first
+    pub fn descendants(&self, id: u32) -> Vec<&Task> {
+        let mut descendants = Vec::new();
+        let mut seen = HashSet::from([id]);
+        let mut stack: Vec<u32> = self.get(id).map(|t| t.subtasks.iter().rev().copied().collect()).unwrap_or_default();
+
+        while let Some(next) = stack.pop() {
+            if !seen.insert(next) {
+                continue;
+            }
+            if let Some(task) = self.get(next) {
+                descendants.push(task);
+                stack.extend(task.subtasks.iter().rev());
+            }
+        }
+
+        descendants
+    }
+
+    /// Pending, with every dependency done. A dependency that no longer exists blocks the task.
+    /// With checklists enabled, an all-checked task counts as done rather than pending.
+    pub fn is_ready(&self, task: &Task) -> bool {
+        task.status == TaskStatus::Pending
+            && !self.is_checked_off(task)
+            && task.dependencies.iter().all(|&dep| {
+                self.get(dep).is_some_and(|t| t.status == TaskStatus::Done || self.is_checked_off(t))
+            })
+    }
+
+    /// Open, but done by its checklist when checklists complete tasks
+    fn is_checked_off(&self, task: &Task) -> bool {
+        self.checklists
+            && !task.status.is_closed()
+            && task.checklist_progress().is_some_and(|(checked, total)| checked == total)
+    }
+
+    /// Status rolled up from the subtask tree: done when every subtask is
+    /// done, otherwise in progress or blocked if any subtask is (custom
+    /// statuses count as in progress), otherwise the task's own status.
+    ///
+    /// With checklists enabled, an open task whose checklist is all checked
+    /// counts as done, and done subtasks with unchecked items left leave it
+    /// in progress.
+    pub fn effective_status(&self, task: &Task) -> TaskStatus {
+        let checklist_done = match task.checklist_progress() {
+            Some((checked, total)) if self.checklists => Some(checked == total),
+            _ => None,
+        };
+        if task.subtasks.is_empty() {
+            return match checklist_done {
+                Some(true) if !task.status.is_closed() => TaskStatus::Done,
+                _ => task.status.clone(),
+            };
+        }
+        if let Some(status) = self.effective.borrow().get(&task.id) {
+            return status.clone();
+        }
+
+        // Provisional entry, so a subtask cycle ends at the task's own status
+        self.effective.borrow_mut().insert(task.id, task.status.clone());
+
+        let statuses: Vec<TaskStatus> = self.subtasks(task).into_iter()
+            .map(|subtask| self.effective_status(subtask))
+            .collect();
+
+        let status = if statuses.is_empty() {
+            task.status.clone()
+        } else if statuses.iter().all(|s| *s == TaskStatus::Done) {
+            if checklist_done == Some(false) { TaskStatus::InProgress } else { TaskStatus::Done }
+        } else if statuses.iter().any(TaskStatus::is_active) {
+            TaskStatus::InProgress
+        } else if statuses.contains(&TaskStatus::Blocked) {
+            TaskStatus::Blocked
+        } else {
+            task.status.clone()
+        };
+
+        self.effective.borrow_mut().insert(task.id, status.clone());
+        status
+    }
+
+    /// Subtasks whose effective status is done, out of all listed subtasks
+    pub fn progress(&self, task: &Task) -> (usize, usize) {
+        let completed = self.subtasks(task).into_iter()
+            .filter(|subtask| self.effective_status(subtask) == TaskStatus::Done)
+            .count();
+        (completed, task.subtasks.len())
+    }
+
+    /// Expected size of `task` and everything below it
+    pub fn estimate_total(&self, task: &Task) -> EstimateTotal {
+        let mut total = EstimateTotal::default();
+        for estimate in std::iter::once(task).chain(self.descendants(task.id)).filter_map(|t| t.estimate.as_ref()) {
+            total.add(estimate);
+        }
+        total
+    }
+
+    /// Minutes logged on `task` and everything below it
+    pub fn time_spent(&self, task: &Task) -> u32 {
+        task.logged_minutes() + self.descendants(task.id).into_iter().map(Task::logged_minutes).sum::<u32>()
+    }
+
+    /// Whether `task` may depend on `dep` without a self-reference, a
+    /// dangling ID or a cycle
+    pub fn check_dependency(&self, task: u32, dep: u32) -> Result<(), Problem> {
+        if task == dep {
+            return Err(Problem::SelfDependency(task));
+        }
+        if !self.contains(dep) {
+            return Err(Problem::MissingDependency { task, dep });
+        }
+        match self.find_path(dep, task, dependencies) {
+            Some(mut cycle) => {
+                cycle.insert(0, task);
+                Err(Problem::DependencyCycle(cycle))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Whether `subtask` may be listed under `parent`: it must exist, have no
+    /// other parent and not be above `parent` in the tree
+    pub fn check_subtask(&self, parent: u32, subtask: u32) -> Result<(), Problem> {
+        if parent == subtask {
+            return Err(Problem::SelfSubtask(parent));
+        }
+        if !self.contains(subtask) {
+            return Err(Problem::MissingSubtask { task: parent, subtask });
+        }
+        if let Some(existing) = self.edges.parents.get(&subtask).filter(|&&p| p != parent) {
+            let mut parents = vec![*existing, parent];
+            parents.sort();
+            return Err(Problem::MultipleParents { subtask, parents });
+        }
+        match self.find_path(subtask, parent, |t| t.subtasks.clone()) {
+            Some(mut cycle) => {
+                cycle.insert(0, parent);
+                Err(Problem::SubtaskCycle(cycle))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Whether `task` may hold the link `relation`: not to itself, and only to a task that exists
+    pub fn check_relation(&self, task: u32, relation: Relation) -> Result<(), Problem> {
+        if task == relation.task {
+            return Err(Problem::SelfRelation { task, kind: relation.kind });
+        }
+        if !self.contains(relation.task) {
+            return Err(Problem::MissingRelation { task, kind: relation.kind, related: relation.task });
+        }
+        Ok(())
+    }
+
+    /// Every integrity problem in the project, in task order
+    pub fn problems(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        let mut parents: HashMap<u32, Vec<u32>> = HashMap::new();
+
+        for task in &self.tasks {
+            if task.dependencies.contains(&task.id) {
+                problems.push(Problem::SelfDependency(task.id));
+            }
+            if task.subtasks.contains(&task.id) {
+                problems.push(Problem::SelfSubtask(task.id));
+            }
+            for &dep in &task.dependencies {
+                if !self.contains(dep) {
+                    problems.push(Problem::MissingDependency { task: task.id, dep });
+                }
+            }
+            for &relation in &task.relations {
+                if let Err(problem) = self.check_relation(task.id, relation) {
+                    problems.push(problem);
+                }
+            }
+
+            let mut listed = HashSet::new();
+            for &subtask in &task.subtasks {
+                if !listed.insert(subtask) {
+                    problems.push(Problem::DuplicateSubtask { task: task.id, subtask });
+                } else if !self.contains(subtask) {
+                    problems.push(Problem::MissingSubtask { task: task.id, subtask });
+                } else if subtask != task.id {
+                    parents.entry(subtask).or_default().push(task.id);
+                }
+            }
+        }
+
+        let mut shared: Vec<(u32, Vec<u32>)> = parents.into_iter().filter(|(_, p)| p.len() > 1).collect();
+        shared.sort();
+        problems.extend(shared.into_iter().map(|(subtask, parents)| Problem::MultipleParents { subtask, parents }));
+
+        problems.extend(self.cycles(dependencies).into_iter().map(Problem::DependencyCycle));
+        problems.extend(self.cycles(|t| t.subtasks.clone()).into_iter().map(Problem::SubtaskCycle));
+        problems
+    }
+
+    /// Shortest path from `from` to `to` along `edges`, both ends included
+    fn find_path(&self, from: u32, to: u32, edges: impl Fn(&Task) -> Vec<u32>) -> Option<Vec<u32>> {
+        let mut came_from = HashMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![to];
+                let mut step = to;
+                while step != from {
+                    step = came_from[&step];
+                    path.push(step);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.get(current).map(&edges).unwrap_or_default() {
+                if let Entry::Vacant(entry) = came_from.entry(next) {
+                    entry.insert(current);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// One cycle per back edge found by a depth-first walk of `edges`,
+    /// ignoring self-references. Each path ends where it started.
+    fn cycles(&self, edges: impl Fn(&Task) -> Vec<u32>) -> Vec<Vec<u32>> {
+        let mut cycles = Vec::new();
+        let mut finished = HashSet::new();
+
+        for root in &self.tasks {
+            if finished.contains(&root.id) {
+                continue;
+            }
+            // The current path, with each task's remaining outgoing edges
+            let mut path: Vec<u32> = vec![root.id];
+            let mut pending: Vec<Vec<u32>> = vec![edges(root).into_iter().rev().collect()];
+
+            while let Some(remaining) = pending.last_mut() {
+                let Some(next) = remaining.pop() else {
+                    finished.insert(path.pop().unwrap());
+                    pending.pop();
+                    continue;
+                };
+                let current = *path.last().unwrap();
+                if next == current || finished.contains(&next) {
+                    continue;
+                }
+                if let Some(start) = path.iter().position(|&id| id == next) {
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(next);
+                    cycles.push(cycle);
+                    continue;
+                }
+                if let Some(task) = self.get(next) {
+                    path.push(next);
+                    pending.push(edges(task).into_iter().rev().collect());
+                }
+            }
+        }
+
+        cycles
+    }
+}
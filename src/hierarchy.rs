@@ -0,0 +1,79 @@
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::graph::TaskGraph;
+use crate::storage::TaskStore;
+
+/// Where to put a subtask relative to one of its siblings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placement {
+    Before(u32),
+    After(u32),
+}
+
+/// Make task `id` a subtask of `parent`, at the end of its list, or a
+/// top-level task when `parent` is `None`. Every other task listing `id`
+/// lets go of it. Returns the parents it was taken from, or `None` if it
+/// was already where it was asked to go.
+pub fn move_task(storage: &dyn TaskStore, id: u32, parent: Option<u32>) -> Result<Option<Vec<u32>>> {
+    let _lock = storage.lock()?;
+    let mut tasks = storage.list_all_tasks()?;
+    for task in [Some(id), parent].into_iter().flatten() {
+        if !tasks.iter().any(|t| t.id == task) {
+            anyhow::bail!("Task #{} not found", task);
+        }
+    }
+
+    let current: Vec<u32> = tasks.iter().filter(|t| t.subtasks.contains(&id)).map(|t| t.id).collect();
+    if current == parent.into_iter().collect::<Vec<_>>() {
+        return Ok(None);
+    }
+
+    for task in tasks.iter_mut().filter(|t| current.contains(&t.id) && Some(t.id) != parent) {
+        task.subtasks.retain(|&s| s != id);
+        task.updated_at = Utc::now();
+    }
+
+    if let Some(parent) = parent {
+        // Checked without the old links, so only a real cycle or a missing parent stops the move
+        let graph = TaskGraph::new(tasks.clone());
+        graph.check_subtask(parent, id)?;
+        if let Some(task) = tasks.iter_mut().find(|t| t.id == parent) {
+            if !task.subtasks.contains(&id) {
+                task.add_subtask(id);
+            }
+        }
+    }
+
+    for task in tasks.iter().filter(|t| current.contains(&t.id) || Some(t.id) == parent) {
+        storage.save_task(task)?;
+    }
+    Ok(Some(current))
+}
+
+/// Move subtask `id` of `parent` next to its sibling named in `placement`
+pub fn reorder(storage: &dyn TaskStore, parent: u32, id: u32, placement: Placement) -> Result<()> {
+    let _lock = storage.lock()?;
+    let mut task = storage.load_task(parent)?;
+    let anchor = match placement {
+        Placement::Before(anchor) | Placement::After(anchor) => anchor,
+    };
+    for subtask in [id, anchor] {
+        if !task.subtasks.contains(&subtask) {
+            anyhow::bail!("Task #{} is not a subtask of #{}", subtask, parent);
+        }
+    }
+    if id == anchor {
+        anyhow::bail!("Can't place task #{} relative to itself", id);
+    }
+
+    task.subtasks.retain(|&s| s != id);
+    let position = task.subtasks.iter().position(|&s| s == anchor).unwrap_or_default();
+    let position = match placement {
+        Placement::Before(_) => position,
+        Placement::After(_) => position + 1,
+    };
+    task.subtasks.insert(position, id);
+    task.updated_at = Utc::now();
+    storage.save_task(&task)
+}
@@ -0,0 +1,203 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt;
+
+use crate::graph::TaskGraph;
+use crate::storage::{LockGuard, TaskStore};
+use crate::task::{Priority, Task, TaskRef, TaskStatus};
+
+/// Environment variable naming who is running `trusty`, e.g. an agent's name
+pub const ACTOR_ENV: &str = "TRUSTY_ACTOR";
+
+thread_local! {
+    static ACTOR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// One entry in a task's append-only history
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryEvent {
+    pub at: DateTime<Utc>,
+    /// `human`, an agent name from `TRUSTY_ACTOR`, or an internal actor such as `prune` or `import`
+    pub actor: String,
+    #[serde(flatten)]
+    pub change: HistoryChange,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum HistoryChange {
+    Created { status: TaskStatus },
+    /// Came back from the trash or an undo
+    Restored,
+    Status { from: TaskStatus, to: TaskStatus },
+    Priority { from: Priority, to: Priority },
+    /// Any other fields, by name
+    Edited { fields: Vec<String> },
+}
+
+impl fmt::Display for HistoryChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryChange::Created { status } => write!(f, "created ({})", status),
+            HistoryChange::Restored => write!(f, "restored"),
+            HistoryChange::Status { from, to } => write!(f, "status {} → {}", from, to),
+            HistoryChange::Priority { from, to } => write!(f, "priority {} → {}", from, to),
+            HistoryChange::Edited { fields } => write!(f, "edited {}", fields.join(", ")),
+        }
+    }
+}
+
+/// Who changes made by this process are attributed to
+pub fn current_actor() -> String {
+    ACTOR.with(|actor| actor.borrow().clone())
+        .or_else(|| std::env::var(ACTOR_ENV).ok().filter(|a| !a.trim().is_empty()))
+        .unwrap_or_else(|| "human".to_string())
+}
+
+/// Attribute changes to `actor` until the guard is dropped
+pub fn act_as(actor: &str) -> ActorGuard {
+    let previous = ACTOR.with(|current| current.replace(Some(actor.to_string())));
+    ActorGuard { previous }
+}
+
+pub struct ActorGuard {
+    previous: Option<String>,
+}
+
+impl Drop for ActorGuard {
+    fn drop(&mut self) {
+        ACTOR.with(|current| *current.borrow_mut() = self.previous.take());
+    }
+}
+
+/// Append events describing how `task` differs from its stored version.
+///
+/// The stored history always wins over whatever the caller passes in, so a
+/// save can only ever add to the trail, even when it writes back an old
+/// snapshot (as undo does).
+pub fn stamp(before: Option<&Task>, task: &mut Task) {
+    let mut events = Vec::new();
+
+    match before {
+        None if task.history.is_empty() => events.push(HistoryChange::Created { status: task.status.clone() }),
+        None => events.push(HistoryChange::Restored),
+        Some(before) => {
+            task.history = before.history.clone();
+
+            if before.status != task.status {
+                events.push(HistoryChange::Status { from: before.status.clone(), to: task.status.clone() });
+            }
+            if before.priority != task.priority {
+                events.push(HistoryChange::Priority { from: before.priority.clone(), to: task.priority.clone() });
+            }
+
+            let fields = changed_fields(before, task);
+            if !fields.is_empty() {
+                events.push(HistoryChange::Edited { fields });
+            }
+        }
+    }
+
+    let actor = current_actor();
+    let now = Utc::now();
+    task.history.extend(events.into_iter().map(|change| HistoryEvent { at: now, actor: actor.clone(), change }));
+}
+
+/// Wraps a store and stamps every saved task with how it differs from the
+/// stored version.
+///
+/// Belongs inside the locking layer, so concurrent writers each append to
+/// the latest trail.
+pub struct HistoryStore {
+    inner: Box<dyn TaskStore>,
+}
+
+impl HistoryStore {
+    pub fn new(inner: Box<dyn TaskStore>) -> Self {
+        Self { inner }
+    }
+}
+
+impl TaskStore for HistoryStore {
+    fn load_task(&self, id: u32) -> Result<Task> {
+        self.inner.load_task(id)
+    }
+
+    fn save_task(&self, task: &Task) -> Result<()> {
+        let mut task = task.clone();
+        let before = self.inner.load_task(task.id).ok();
+        stamp(before.as_ref(), &mut task);
+        self.inner.save_task(&task)
+    }
+
+    fn delete_task(&self, id: u32) -> Result<()> {
+        self.inner.delete_task(id)
+    }
+
+    fn list_all_tasks(&self) -> Result<Vec<Task>> {
+        self.inner.list_all_tasks()
+    }
+
+    fn load_graph(&self) -> Result<TaskGraph> {
+        self.inner.load_graph()
+    }
+
+    fn unreadable(&self) -> Result<Vec<(String, String)>> {
+        self.inner.unreadable()
+    }
+
+    fn next_id(&self) -> Result<u32> {
+        self.inner.next_id()
+    }
+
+    fn allocate_id(&self) -> Result<u32> {
+        self.inner.allocate_id()
+    }
+
+    fn lock(&self) -> Result<LockGuard<'_>> {
+        self.inner.lock()
+    }
+
+    fn resolve(&self, task: &TaskRef) -> Result<u32> {
+        self.inner.resolve(task)
+    }
+}
+
+fn changed_fields(before: &Task, after: &Task) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut check = |name: &str, changed: bool| {
+        if changed {
+            fields.push(name.to_string());
+        }
+    };
+
+    check("uid", before.uid != after.uid);
+    check("title", before.title != after.title);
+    check("description", before.description != after.description);
+    check("notes", before.notes != after.notes);
+    check("complexity", before.complexity != after.complexity);
+    check("sizing", before.estimate != after.estimate);
+    check("dependencies", before.dependencies != after.dependencies);
+    check("subtasks", before.subtasks != after.subtasks);
+    check("relations", before.relations != after.relations);
+    check("tags", before.tags != after.tags);
+    check("due", before.due != after.due);
+    check("scheduled", before.scheduled != after.scheduled);
+    check("deferred_until", before.deferred_until != after.deferred_until);
+    check("wake_when", before.wake_when != after.wake_when);
+    check("recurrence", before.recurrence != after.recurrence);
+    check("series", before.series != after.series);
+    check("time_log", before.time_log != after.time_log);
+    check("resolution", before.resolution != after.resolution);
+    check("in_milestone", before.milestone != after.milestone);
+
+    for key in before.extra.keys().chain(after.extra.keys()) {
+        if before.extra.get(key) != after.extra.get(key) && !fields.contains(key) {
+            fields.push(key.clone());
+        }
+    }
+
+    fields
+}
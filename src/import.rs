@@ -2,8 +2,11 @@ use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
+use crate::graph::TaskGraph;
+use crate::history;
 use crate::task::{Task, Priority, TaskStatus, Complexity};
-use crate::storage::TaskStorage;
+use crate::storage::TaskStore;
+use crate::workflow::Workflow;
 use chrono::{DateTime, Utc};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -55,6 +58,8 @@ struct ImportTask {
     updated_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     completed_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resolution: Option<String>,
 }
 
 fn default_priority() -> String {
@@ -65,15 +70,20 @@ fn default_status() -> String {
     "pending".to_string()
 }
 
-pub struct TaskImporter {
-    storage: TaskStorage,
+pub struct TaskImporter<'a> {
+    storage: &'a dyn TaskStore,
+    workflow: &'a Workflow,
+    /// Import statuses the workflow wouldn't allow
+    force: bool,
     id_mapping: HashMap<u32, u32>, // old_id -> new_id
 }
 
-impl TaskImporter {
-    pub fn new(storage: TaskStorage) -> Self {
+impl<'a> TaskImporter<'a> {
+    pub fn new(storage: &'a dyn TaskStore, workflow: &'a Workflow, force: bool) -> Self {
         Self {
             storage,
+            workflow,
+            force,
             id_mapping: HashMap::new(),
         }
     }
@@ -145,6 +155,7 @@ impl TaskImporter {
                     created_at: None,
                     updated_at: None,
                     completed_at: None,
+                    resolution: None,
                 });
                 in_description = false;
             }
@@ -231,56 +242,78 @@ impl TaskImporter {
     }
 
     fn import_tasks(&mut self, import_tasks: Vec<ImportTask>, duplicate_handling: DuplicateHandling) -> Result<ImportResult> {
+        // Held for the whole import so other processes can't interleave writes
+        let _lock = self.storage.lock()?;
+        let _actor = history::act_as("import");
         let existing_tasks = self.storage.list_all_tasks()?;
-        let mut result = ImportResult::default();
 
-        // Get next available ID
-        let mut next_id = existing_tasks.iter()
-            .map(|t| t.id)
-            .max()
-            .unwrap_or(0) + 1;
+        // Decide what happens to every task and check the links it would
+        // create before anything is written
+        let mut next_id = self.storage.next_id()?;
+        let actions: Vec<ImportAction> = import_tasks.iter()
+            .map(|import_task| self.plan_task(import_task, &existing_tasks, duplicate_handling, &mut next_id))
+            .collect();
+        self.check_plan(&import_tasks, &actions, &existing_tasks)?;
 
         // First pass: Import tasks without dependencies/subtasks
-        for import_task in &import_tasks {
-            match self.process_single_task(import_task, &existing_tasks, duplicate_handling, &mut next_id)? {
-                ImportAction::Created(id) => {
+        let mut result = ImportResult::default();
+        let mut written = Vec::new();
+        for (import_task, action) in import_tasks.iter().zip(&actions) {
+            let id = match action {
+                ImportAction::Created { title, .. } => {
+                    let id = self.storage.allocate_id()?;
+                    let mut task = self.convert_import_task(import_task, id)?;
+                    task.title = title.clone();
+                    self.storage.save_task(&task)?;
+                    written.push((import_task, id));
                     result.created += 1;
-                    if let Some(old_id) = import_task.id {
-                        self.id_mapping.insert(old_id, id);
-                    }
+                    id
+                }
+                ImportAction::Updated(id) => {
+                    self.storage.save_task(&self.convert_import_task(import_task, *id)?)?;
+                    written.push((import_task, *id));
+                    result.updated += 1;
+                    *id
+                }
+                ImportAction::Skipped(id) => {
+                    result.skipped += 1;
+                    *id
                 }
-                ImportAction::Updated => result.updated += 1,
-                ImportAction::Skipped => result.skipped += 1,
+            };
+            if let Some(old_id) = import_task.id {
+                self.id_mapping.insert(old_id, id);
             }
         }
 
-        // Second pass: Update dependencies and subtasks with new IDs
-        self.update_relationships()?;
+        // Second pass: Link the written tasks using their new IDs
+        for (import_task, id) in written {
+            if !import_task.dependencies.is_empty() || !import_task.subtasks.is_empty() {
+                let mut task = self.storage.load_task(id)?;
+                link(&mut task, import_task, &self.id_mapping);
+                self.storage.save_task(&task)?;
+            }
+        }
 
         result.total = import_tasks.len();
         Ok(result)
     }
 
-    fn process_single_task(
+    /// What importing `import_task` will do. `next_id` is the ID the next
+    /// created task will get.
+    fn plan_task(
         &self,
         import_task: &ImportTask,
         existing_tasks: &[Task],
         duplicate_handling: DuplicateHandling,
         next_id: &mut u32,
-    ) -> Result<ImportAction> {
+    ) -> ImportAction {
         // Check for duplicates by title
         let duplicate = existing_tasks.iter()
             .find(|t| t.title == import_task.title);
 
         match (duplicate, duplicate_handling) {
-            (Some(_), DuplicateHandling::Skip) => {
-                Ok(ImportAction::Skipped)
-            }
-            (Some(existing), DuplicateHandling::Overwrite) => {
-                let task = self.convert_import_task(import_task, existing.id)?;
-                self.storage.save_task(&task)?;
-                Ok(ImportAction::Updated)
-            }
+            (Some(existing), DuplicateHandling::Skip) => ImportAction::Skipped(existing.id),
+            (Some(existing), DuplicateHandling::Overwrite) => ImportAction::Updated(existing.id),
             (Some(_), DuplicateHandling::Rename) | (None, _) => {
                 let mut title = import_task.title.clone();
                 if duplicate.is_some() {
@@ -297,13 +330,59 @@ impl TaskImporter {
 
                 let id = *next_id;
                 *next_id += 1;
+                ImportAction::Created { id, title }
+            }
+        }
+    }
 
-                let mut task = self.convert_import_task(import_task, id)?;
-                task.title = title;
-                self.storage.save_task(&task)?;
-                Ok(ImportAction::Created(id))
+    /// Reject the import if its tasks would add a cycle, a self-reference, a
+    /// dangling ID or a second parent to the project, or enter a status the
+    /// workflow doesn't allow. Problems the project already has are left to
+    /// `trusty doctor`.
+    fn check_plan(&self, import_tasks: &[ImportTask], actions: &[ImportAction], existing_tasks: &[Task]) -> Result<()> {
+        let mapping: HashMap<u32, u32> = import_tasks.iter().zip(actions)
+            .filter_map(|(import_task, action)| Some((import_task.id?, action.id())))
+            .collect();
+
+        let mut tasks: HashMap<u32, Task> = existing_tasks.iter().map(|t| (t.id, t.clone())).collect();
+        for (import_task, action) in import_tasks.iter().zip(actions) {
+            if !matches!(action, ImportAction::Skipped(_)) {
+                let mut task = self.convert_import_task(import_task, action.id())?;
+                link(&mut task, import_task, &mapping);
+                tasks.insert(task.id, task);
             }
         }
+
+        let before = TaskGraph::new(existing_tasks.to_vec()).problems();
+        let graph = TaskGraph::new(tasks.into_values().collect());
+        let mut problems: Vec<String> = graph.problems()
+            .into_iter()
+            .filter(|problem| !before.contains(problem))
+            .map(|problem| problem.to_string())
+            .collect();
+
+        if !self.force {
+            for (import_task, action) in import_tasks.iter().zip(actions) {
+                // New tasks start out pending and move to their imported status
+                let from = match action {
+                    ImportAction::Skipped(_) => continue,
+                    ImportAction::Updated(id) => existing_tasks.iter().find(|t| t.id == *id).map(|t| t.status.clone()),
+                    ImportAction::Created { .. } => None,
+                };
+                let Some(imported) = graph.get(action.id()) else { continue };
+                let mut task = imported.clone();
+                task.status = from.unwrap_or(TaskStatus::Pending);
+
+                for violation in self.workflow.violations(&task, &imported.status, &graph, import_task.resolution.as_deref()) {
+                    problems.push(format!("'{}' can't be imported as {}: {} (use --force to override)", import_task.title, imported.status.name(), violation));
+                }
+            }
+        }
+
+        if !problems.is_empty() {
+            anyhow::bail!("Nothing was imported:\n  {}", problems.join("\n  "));
+        }
+        Ok(())
     }
 
     fn convert_import_task(&self, import_task: &ImportTask, id: u32) -> Result<Task> {
@@ -314,15 +393,8 @@ impl TaskImporter {
             _ => Priority::Medium,
         };
 
-        let status = match import_task.status.to_lowercase().as_str() {
-            "pending" => TaskStatus::Pending,
-            "in-progress" => TaskStatus::InProgress,
-            "done" => TaskStatus::Done,
-            "blocked" => TaskStatus::Blocked,
-            "deferred" => TaskStatus::Deferred,
-            "cancelled" => TaskStatus::Cancelled,
-            _ => TaskStatus::Pending,
-        };
+        let status = self.workflow.parse_status(&import_task.status)
+            .map_err(|e| anyhow::anyhow!("Task '{}': {}", import_task.title, e))?;
 
         let complexity = import_task.complexity.as_ref().and_then(|c| {
             match c.to_lowercase().as_str() {
@@ -335,6 +407,7 @@ impl TaskImporter {
 
         let mut task = Task::new(id, import_task.title.clone(), import_task.description.clone(), priority);
         task.status = status;
+        task.resolution = import_task.resolution.clone();
         task.complexity = complexity;
         task.tags = import_task.tags.clone();
         
@@ -351,51 +424,30 @@ impl TaskImporter {
         // Dependencies and subtasks will be updated in second pass
         Ok(task)
     }
+}
 
-    fn update_relationships(&self) -> Result<()> {
-        let tasks = self.storage.list_all_tasks()?;
-        
-        for task in tasks {
-            let mut updated = false;
-            let mut updated_task = task.clone();
-
-            // Update dependencies with new IDs
-            let new_deps: Vec<u32> = task.dependencies.iter()
-                .filter_map(|&old_id| self.id_mapping.get(&old_id).copied())
-                .collect();
-            
-            if !new_deps.is_empty() {
-                updated_task.dependencies.clear();
-                for dep in new_deps {
-                    updated_task.dependencies.insert(dep);
-                }
-                updated = true;
-            }
-
-            // Update subtasks with new IDs
-            let new_subtasks: Vec<u32> = task.subtasks.iter()
-                .filter_map(|&old_id| self.id_mapping.get(&old_id).copied())
-                .collect();
-            
-            if !new_subtasks.is_empty() {
-                updated_task.subtasks = new_subtasks;
-                updated = true;
-            }
-
-            if updated {
-                self.storage.save_task(&updated_task)?;
-            }
-        }
-
-        Ok(())
-    }
+/// Set `task`'s links from the import file. IDs of tasks in the file are
+/// mapped to their IDs in the project; any other ID refers to an existing task.
+fn link(task: &mut Task, import_task: &ImportTask, mapping: &HashMap<u32, u32>) {
+    let resolve = |id: &u32| mapping.get(id).copied().unwrap_or(*id);
+    task.dependencies = import_task.dependencies.iter().map(resolve).collect();
+    task.subtasks = import_task.subtasks.iter().map(resolve).collect();
 }
 
 #[derive(Debug)]
 enum ImportAction {
-    Created(u32),
-    Updated,
-    Skipped,
+    Created { id: u32, title: String },
+    Updated(u32),
+    Skipped(u32),
+}
+
+impl ImportAction {
+    /// The project ID the imported task ends up as
+    fn id(&self) -> u32 {
+        match self {
+            ImportAction::Created { id, .. } | ImportAction::Updated(id) | ImportAction::Skipped(id) => *id,
+        }
+    }
 }
 
 struct TaskMetadata {
@@ -49,7 +49,7 @@ pub fn execute_action_interactive(actions: Vec<(String, String, Option<String>)>
             if let Some(command) = cmd {
                 context.push_str(&format!("   Command: {}\n", command));
             }
-            context.push_str("\n");
+            context.push('\n');
         }
         
         context.push_str("I'd like to discuss these suggestions and potentially refine them. What do you think about these recommendations?");
@@ -0,0 +1,267 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::graph::TaskGraph;
+use crate::history;
+use crate::storage::{atomic_write, LockGuard, TaskStore};
+use crate::task::{Task, TaskRef};
+use crate::trash::Trash;
+
+const JOURNAL_FILE: &str = "journal";
+
+/// Oldest operations are dropped beyond this many
+const MAX_OPERATIONS: usize = 500;
+
+/// One task's state before and after an operation. `None` means the task
+/// didn't exist, so a change with no `before` is a creation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Change {
+    pub id: u32,
+    pub before: Option<Task>,
+    pub after: Option<Task>,
+}
+
+/// Everything one `trusty` command changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub seq: u64,
+    pub at: DateTime<Utc>,
+    pub command: String,
+    pub changes: Vec<Change>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub undone: bool,
+}
+
+/// Undo history in `.trusty/journal`, one JSON operation per line
+pub struct Journal {
+    path: PathBuf,
+    trusty_dir: PathBuf,
+}
+
+impl Journal {
+    pub fn new(trusty_dir: &Path) -> Self {
+        Self {
+            path: trusty_dir.join(JOURNAL_FILE),
+            trusty_dir: trusty_dir.to_path_buf(),
+        }
+    }
+
+    /// All recorded operations, oldest first
+    pub fn load(&self) -> Result<Vec<Operation>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read journal: {:?}", self.path))?;
+        content.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).with_context(|| format!("Corrupt journal entry in {:?}", self.path)))
+            .collect()
+    }
+
+    fn write(&self, operations: &[Operation]) -> Result<()> {
+        let mut content = String::new();
+        for operation in operations {
+            content.push_str(&serde_json::to_string(operation)?);
+            content.push('\n');
+        }
+        atomic_write(&self.path, content.as_bytes())
+    }
+
+    /// Append an operation. Anything previously undone can no longer be redone.
+    pub fn record(&self, storage: &dyn TaskStore, command: &str, changes: Vec<Change>) -> Result<Option<u64>> {
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        let _lock = storage.lock()?;
+        let mut operations = self.load()?;
+        let seq = operations.iter().map(|op| op.seq).max().unwrap_or(0) + 1;
+        operations.retain(|op| !op.undone);
+
+        operations.push(Operation {
+            seq,
+            at: Utc::now(),
+            command: command.to_string(),
+            changes,
+            undone: false,
+        });
+
+        let excess = operations.len().saturating_sub(MAX_OPERATIONS);
+        operations.drain(..excess);
+
+        self.write(&operations)?;
+        Ok(Some(seq))
+    }
+
+    /// Revert the last `count` operations that haven't been undone, newest first
+    pub fn undo(&self, storage: &dyn TaskStore, count: usize, force: bool) -> Result<Vec<Operation>> {
+        let _lock = storage.lock()?;
+        let mut operations = self.load()?;
+        let pending: Vec<usize> = (0..operations.len()).rev().filter(|&i| !operations[i].undone).take(count).collect();
+
+        self.step(storage, &mut operations, &pending, false, force)
+    }
+
+    /// Reapply the last `count` undone operations, in the order they first ran
+    pub fn redo(&self, storage: &dyn TaskStore, count: usize, force: bool) -> Result<Vec<Operation>> {
+        let _lock = storage.lock()?;
+        let mut operations = self.load()?;
+        let pending: Vec<usize> = (0..operations.len()).filter(|&i| operations[i].undone).take(count).collect();
+
+        self.step(storage, &mut operations, &pending, true, force)
+    }
+
+    /// Undo or redo the operations at `indices` in order. Progress is saved
+    /// even when one of them fails, so the journal matches the tasks.
+    fn step(&self, storage: &dyn TaskStore, operations: &mut [Operation], indices: &[usize], forward: bool, force: bool) -> Result<Vec<Operation>> {
+        let mut applied = Vec::new();
+
+        for &i in indices {
+            if let Err(e) = self.apply(storage, &operations[i], forward, force) {
+                self.write(operations)?;
+                return Err(e);
+            }
+            operations[i].undone = !forward;
+            applied.push(operations[i].clone());
+        }
+
+        self.write(operations)?;
+        Ok(applied)
+    }
+
+    /// Move every task in `op` from one side of its changes to the other.
+    ///
+    /// Refuses when a task no longer matches the state the operation left it
+    /// in, since reverting would silently throw away the later edit.
+    fn apply(&self, storage: &dyn TaskStore, op: &Operation, forward: bool, force: bool) -> Result<()> {
+        let side = |change: &Change, forward: bool| if forward { change.after.clone() } else { change.before.clone() };
+
+        if !force {
+            for change in &op.changes {
+                let current = storage.load_task(change.id).ok();
+                if !same_state(current.as_ref(), side(change, !forward).as_ref()) {
+                    anyhow::bail!(
+                        "Task #{} has changed since operation {} ({}). Use --force to {} it anyway",
+                        change.id, op.seq, op.command, if forward { "redo" } else { "undo" }
+                    );
+                }
+            }
+        }
+
+        let trash = Trash::new(&self.trusty_dir);
+        let _actor = history::act_as(if forward { "redo" } else { "undo" });
+        for change in &op.changes {
+            match side(change, forward) {
+                Some(task) => {
+                    storage.save_task(&task)?;
+                    // A task brought back by undoing a delete shouldn't also linger in the trash
+                    trash.forget(&task)?;
+                }
+                None => {
+                    if storage.load_task(change.id).is_ok() {
+                        storage.delete_task(change.id)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether two snapshots are the same apart from history, which grows with
+/// every save, including the ones made by undo and redo themselves
+fn same_state(a: Option<&Task>, b: Option<&Task>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Task { history: Vec::new(), ..a.clone() } == Task { history: Vec::new(), ..b.clone() },
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
+/// Wraps a store and records the before/after snapshots of every write
+pub struct RecordingStore<'a> {
+    inner: &'a dyn TaskStore,
+    changes: RefCell<Vec<Change>>,
+}
+
+impl<'a> RecordingStore<'a> {
+    pub fn new(inner: &'a dyn TaskStore) -> Self {
+        Self { inner, changes: RefCell::new(Vec::new()) }
+    }
+
+    /// The recorded changes, one per task: the state before its first write
+    /// and after its last one
+    pub fn into_changes(self) -> Vec<Change> {
+        let mut merged: Vec<Change> = Vec::new();
+
+        for change in self.changes.into_inner() {
+            match merged.iter_mut().find(|c| c.id == change.id) {
+                Some(existing) => existing.after = change.after,
+                None => merged.push(change),
+            }
+        }
+
+        merged.retain(|c| c.before != c.after);
+        merged
+    }
+
+    fn record(&self, id: u32, before: Option<Task>) {
+        // Read back what was stored, which may differ from what was passed in (e.g. an assigned uid)
+        let after = self.inner.load_task(id).ok();
+        self.changes.borrow_mut().push(Change { id, before, after });
+    }
+}
+
+impl TaskStore for RecordingStore<'_> {
+    fn load_task(&self, id: u32) -> Result<Task> {
+        self.inner.load_task(id)
+    }
+
+    fn save_task(&self, task: &Task) -> Result<()> {
+        let before = self.inner.load_task(task.id).ok();
+        self.inner.save_task(task)?;
+        self.record(task.id, before);
+        Ok(())
+    }
+
+    fn delete_task(&self, id: u32) -> Result<()> {
+        let before = self.inner.load_task(id).ok();
+        self.inner.delete_task(id)?;
+        self.record(id, before);
+        Ok(())
+    }
+
+    fn list_all_tasks(&self) -> Result<Vec<Task>> {
+        self.inner.list_all_tasks()
+    }
+
+    fn load_graph(&self) -> Result<TaskGraph> {
+        self.inner.load_graph()
+    }
+
+    fn unreadable(&self) -> Result<Vec<(String, String)>> {
+        self.inner.unreadable()
+    }
+
+    fn next_id(&self) -> Result<u32> {
+        self.inner.next_id()
+    }
+
+    fn allocate_id(&self) -> Result<u32> {
+        self.inner.allocate_id()
+    }
+
+    fn lock(&self) -> Result<LockGuard<'_>> {
+        self.inner.lock()
+    }
+
+    fn resolve(&self, task: &TaskRef) -> Result<u32> {
+        self.inner.resolve(task)
+    }
+}
@@ -1,2 +1,21 @@
+pub mod checklist;
+pub mod config;
+pub mod dates;
+pub mod defer;
+pub mod estimate;
+pub mod fields;
+pub mod graph;
+pub mod hierarchy;
+pub mod history;
+pub mod journal;
+pub mod milestone;
+pub mod notes;
+pub mod project;
+pub mod query;
+pub mod recur;
+pub mod relation;
 pub mod storage;
-pub mod task;
\ No newline at end of file
+pub mod task;
+pub mod timelog;
+pub mod trash;
+pub mod workflow;
@@ -1,62 +1,161 @@
 mod advice;
 mod agent;
+pub mod checklist;
 mod cli;
 mod claude_integration;
+pub mod config;
+pub mod dates;
+pub mod defer;
 mod display;
+pub mod estimate;
+pub mod fields;
+pub mod graph;
+pub mod hierarchy;
+pub mod history;
 mod focus;
+mod git;
 mod import;
 mod interactive;
+pub mod journal;
+pub mod milestone;
+pub mod notes;
+pub mod project;
+pub mod query;
 mod prune;
+pub mod recur;
+pub mod relation;
 pub mod storage;
 pub mod task;
+pub mod timelog;
+pub mod trash;
+pub mod workflow;
 
 use anyhow::Result;
 use clap::Parser;
 use colored::*;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::{thread, time::Duration};
 use std::io::{self, Write};
 use std::process::Command;
 use std::env;
 
-use crate::cli::{Cli, Commands, TaskCommands};
+use crate::cli::{Cli, Commands, MilestoneCommands, RecurCommands, TaskCommands, TrashCommands};
 use crate::display::TaskDisplay;
-use crate::storage::TaskStorage;
-use crate::task::{Priority, Task, TaskStatus};
+use crate::graph::TaskGraph;
+use crate::relation::{Relation, RelationKind};
+use crate::config::{IdScheme, ProjectConfig, StorageBackend};
+use crate::storage::TaskStore;
+use crate::task::{Priority, Task, TaskRef, TaskStatus};
+
+/// The global `--project` flag, set once at startup
+static PROJECT: OnceLock<Option<PathBuf>> = OnceLock::new();
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    PROJECT.get_or_init(|| cli.project.clone());
     
     match cli.command {
-        Commands::Init => init_trusty(),
+        Commands::Init { storage, id_scheme, git } => init_trusty(storage, id_scheme, git),
+        Commands::MergeDriver { base, ours, theirs } => {
+            if !git::run_merge_driver(&base, &ours, &theirs)? {
+                eprintln!("{} Conflicting description edits in {}", "⚠️".yellow(), ours.display());
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Commands::AddAgent { scope, global, local: _, name, model, color } => add_agent(scope, global, name, model, color),
+        Commands::Demo { skip_confirm, delay, keep } => run_demo(skip_confirm, delay, keep),
+        Commands::Undo { .. } | Commands::Redo { .. } | Commands::Journal { .. } => {
+            let storage = get_storage()?;
+            handle_journal_command(cli.command, storage.as_ref())
+        }
         _ => {
+            let config = ProjectConfig::load(&get_trusty_dir()?)?;
             let storage = get_storage()?;
-            handle_command(cli.command, storage)
+            let journal = journal::Journal::new(&get_trusty_dir()?);
+            
+            let project_root = project_root()?;
+            
+            // Every write the command makes is recorded as one undoable operation, including
+            // waking up deferred tasks whose date has come or whose condition is met
+            let recorder = journal::RecordingStore::new(storage.as_ref());
+            let result = defer::wake_up(&recorder, &project_root, dates::today())
+                .and_then(|_| handle_command(cli.command, &recorder, &config));
+            
+            // A command that fails partway is recorded too, so what it did write can be undone
+            journal.record(storage.as_ref(), &command_line(), recorder.into_changes())?;
+            
+            result
         }
     }
 }
 
-fn init_trusty() -> Result<()> {
-    let tasks_dir = get_tasks_dir()?;
-    std::fs::create_dir_all(&tasks_dir)?;
+fn init_trusty(backend: Option<String>, id_scheme: Option<String>, git: bool) -> Result<()> {
+    let trusty_dir = project::init_trusty_dir(project_arg())?;
+    std::fs::create_dir_all(&trusty_dir)?;
+    
+    let mut config = ProjectConfig::load(&trusty_dir)?;
+    if let Some(backend) = backend {
+        config.storage = backend.parse::<StorageBackend>()?;
+    }
+    if let Some(id_scheme) = id_scheme {
+        config.id_scheme = id_scheme.parse::<IdScheme>()?;
+    }
+    config.save(&trusty_dir)?;
+    
+    // Opening the store creates its directory or database
+    storage::open_store(&trusty_dir, &config)?;
     
     println!("{}", "✅ Trusty initialized successfully!".green());
-    println!("Tasks will be stored in: {}", tasks_dir.display());
+    println!("Tasks will be stored in: {} ({} backend)", config.storage.location(&trusty_dir).display(), config.storage);
+    
+    if git {
+        if config.storage != StorageBackend::Markdown {
+            println!("{} The merge driver only handles markdown task files", "⚠️".yellow());
+        }
+        
+        let attributes = git::install_merge_driver(&trusty_dir, &config.storage.location(&trusty_dir))?;
+        println!("{} Registered the trusty merge driver in {}", "✅".green(), attributes.display());
+    }
     
     Ok(())
 }
 
-fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
+fn handle_command(command: Commands, storage: &dyn TaskStore, config: &ProjectConfig) -> Result<()> {
     match command {
-        Commands::List { all, completed, recent } => {
-            let mut tasks = storage.list_all_tasks()?;
+        Commands::List { query, all, completed, recent, fields, overdue, due_soon, milestone } => {
+            let graph = storage.load_graph()?.with_checklists(config.checklists_complete_tasks);
+            let mut tasks = graph.tasks().to_vec();
+            
+            let query = query::Query::parse(&query.join(" "), config, dates::today())?;
+            tasks.retain(|t| query.matches(t, &graph));
+            
+            let field_filters = fields.iter()
+                .map(|f| crate::fields::FieldFilter::parse(&config.custom_fields, f))
+                .collect::<Result<Vec<_>>>()?;
+            tasks.retain(|t| field_filters.iter().all(|f| f.matches(&t.extra)));
+            
+            let today = dates::today();
+            if overdue {
+                tasks.retain(|t| t.is_overdue(today));
+            }
+            if let Some(days) = due_soon {
+                tasks.retain(|t| t.is_due_within(days, today));
+            }
+            if let Some(name) = milestone {
+                let milestone = milestone::Milestones::new(&get_trusty_dir()?).get(&name)?;
+                tasks.retain(|t| t.milestone.as_ref() == Some(&milestone.name));
+            }
             
             // Filter tasks based on flags
             if completed {
                 // Show only completed tasks
                 tasks.retain(|t| t.status == TaskStatus::Done);
-            } else if !all {
-                // Default behavior: hide completed tasks older than 5 minutes (or custom recent value)
+            } else if !all && !query.mentions_status() {
+                // Default behavior: hide completed tasks older than 5 minutes (or custom recent value),
+                // unless the query already says which statuses to show
                 let cutoff_minutes = recent.unwrap_or(5);
                 let cutoff_time = chrono::Utc::now() - chrono::Duration::minutes(cutoff_minutes as i64);
                 
@@ -64,7 +163,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                     match t.status {
                         TaskStatus::Done => {
                             // Keep if completed within the cutoff time
-                            t.completed_at.map_or(false, |completed| completed > cutoff_time)
+                            t.completed_at.is_some_and(|completed| completed > cutoff_time)
                         }
                         _ => true, // Keep all non-completed tasks
                     }
@@ -72,13 +171,23 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
             }
             // If --all is set, show everything (no filtering)
             
-            let project_path = get_tasks_dir()?.display().to_string();
-            TaskDisplay::display_task_list(&tasks, &project_path);
+            let project_path = get_tasks_location()?.display().to_string();
+            TaskDisplay::display_task_list(&tasks, &graph, &project_path);
         }
         
-        Commands::Add { title, description, priority, dependencies, tags, prompt } => {
-            let tasks = storage.list_all_tasks()?;
-            let next_id = tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+        Commands::Add { title, description, priority, dependencies, tags, prompt, fields, due, scheduled, deferred_until, estimate, recur, recur_until, recur_count, milestone } => {
+            // Validate custom fields and dates before doing anything expensive like calling Claude
+            let field_values = fields.iter()
+                .map(|f| crate::fields::parse_assignment(&config.custom_fields, f))
+                .collect::<Result<Vec<_>>>()?;
+            let today = dates::today();
+            let parse = |date: Option<String>| date.map(|d| dates::parse_date(&d, today)).transpose();
+            let (due, scheduled, deferred_until) = (parse(due)?, parse(scheduled)?, parse(deferred_until)?);
+            let estimate = estimate.map(|e| e.parse::<estimate::Estimate>()).transpose()?;
+            let recurrence = recur.map(|rule| -> Result<recur::Recurrence> {
+                Ok(recur::Recurrence { rule: rule.parse()?, until: parse(recur_until)?, remaining: recur_count })
+            }).transpose()?;
+            let milestone = milestone.map(|name| milestone::Milestones::new(&get_trusty_dir()?).get(&name)).transpose()?;
             
             let (final_title, final_description, final_priority, final_tags) = if let Some(prompt_text) = prompt {
                 // Generate task from prompt
@@ -106,6 +215,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                 (title, description.unwrap_or_default(), priority, tags_vec)
             };
             
+            let next_id = storage.allocate_id()?;
             let mut task = Task::new(
                 next_id,
                 final_title.clone(),
@@ -114,11 +224,25 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
             );
             
             task.tags = final_tags;
+            task.extra.extend(field_values);
+            task.due = due;
+            task.scheduled = scheduled;
+            task.deferred_until = deferred_until;
+            task.estimate = estimate;
+            task.milestone = milestone.map(|m| m.name);
+            if let Some(recurrence) = recurrence {
+                // Occurrences are scheduled from the due date, so a series always has one
+                task.due = Some(due.unwrap_or_else(|| recurrence.first_due(today)));
+                task.recurrence = Some(recurrence);
+            }
             
             if let Some(deps) = dependencies {
+                let graph = storage.load_graph()?;
                 for dep in deps.split(',') {
-                    if let Ok(dep_id) = dep.trim().parse::<u32>() {
-                        task.add_dependency(dep_id);
+                    if let Ok(dep) = dep.parse::<TaskRef>() {
+                        let dep = storage.resolve(&dep)?;
+                        graph.check_dependency(next_id, dep)?;
+                        task.add_dependency(dep);
                     }
                 }
             }
@@ -128,17 +252,18 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
         }
         
         Commands::Show { id, with_subtasks } => {
+            let id = storage.resolve(&id)?;
             let task = storage.load_task(id)?;
-            let all_tasks = storage.list_all_tasks()?;
-            display_task_details(&task, Some(&all_tasks));
+            let graph = storage.load_graph()?.with_checklists(config.checklists_complete_tasks);
+            display_task_details(&task, Some(&graph), config);
             
             if with_subtasks && !task.subtasks.is_empty() {
                 println!("\n{}", "Subtasks:".bold());
                 println!("{}", "─".repeat(50));
                 
                 for (i, &subtask_id) in task.subtasks.iter().enumerate() {
-                    match storage.load_task(subtask_id) {
-                        Ok(subtask) => {
+                    match graph.get(subtask_id) {
+                        Some(subtask) => {
                             println!("  {}. [#{}] {} - {}", 
                                 i + 1, 
                                 subtask.id, 
@@ -146,7 +271,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                                 subtask.status
                             );
                         }
-                        Err(_) => {
+                        None => {
                             println!("  {}. [#{}] (Task not found)", i + 1, subtask_id);
                         }
                     }
@@ -154,76 +279,195 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
             }
         }
         
-        Commands::SetStatus { id, status, cascade } => {
+        Commands::Note { id, text } => {
+            if text.trim().is_empty() {
+                anyhow::bail!("A note needs some text");
+            }
+            let id = storage.resolve(&id)?;
+            let _lock = storage.lock()?;
             let mut task = storage.load_task(id)?;
-            let new_status = parse_status(&status)?;
-            task.set_status(new_status.clone());
+            task.notes.push(notes::Note::new(&text));
             storage.save_task(&task)?;
+            println!("{} Added note to task #{} ({} in total)", "✅".green(), id, task.notes.len());
+        }
+        
+        Commands::Check { id, items } => set_checklist_items(storage, &id, &items, true)?,
+        
+        Commands::Uncheck { id, items } => set_checklist_items(storage, &id, &items, false)?,
+        
+        Commands::History { id } => {
+            let id = storage.resolve(&id)?;
+            let task = storage.load_task(id)?;
             
-            let mut updated_count = 1;
-            
-            if cascade && !task.subtasks.is_empty() {
-                // Recursively update all subtasks
-                fn update_subtasks_status(storage: &TaskStorage, subtask_ids: &[u32], status: &TaskStatus) -> Result<usize> {
-                    let mut count = 0;
-                    for &subtask_id in subtask_ids {
-                        if let Ok(mut subtask) = storage.load_task(subtask_id) {
-                            subtask.set_status(status.clone());
-                            storage.save_task(&subtask)?;
-                            count += 1;
-                            
-                            // Recursively update this subtask's subtasks
-                            if !subtask.subtasks.is_empty() {
-                                count += update_subtasks_status(storage, &subtask.subtasks, status)?;
-                            }
+            println!("{} #{}: {}", "History of".bold(), task.id, task.title);
+            println!("{}", "─".repeat(50));
+            
+            if task.history.is_empty() {
+                println!("{} No history recorded for this task yet.", "ℹ️".blue());
+            }
+            for event in &task.history {
+                let when = event.at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+                println!("  {}  {:<10} {}", when, event.actor.cyan(), event.change);
+            }
+            
+            let days = (chrono::Utc::now() - task.status_since()).num_days();
+            println!("\n{} {} for {} day(s)", "Status:".bold(), task.status, days);
+        }
+        
+        Commands::SetStatus { id, filter, status, cascade, resolution, force } => {
+            let new_status = config.workflow.parse_status(&status)?;
+            let _lock = storage.lock()?;
+            let graph = storage.load_graph()?.with_checklists(config.checklists_complete_tasks);
+            
+            let mut ids = selected_tasks(storage, config, id, filter.as_deref())?;
+            if ids.is_empty() {
+                println!("{} No tasks match", "ℹ️".blue());
+                return Ok(());
+            }
+            if cascade {
+                // The whole subtask trees, each task once even if a cycle or shared subtask reaches it twice
+                for id in ids.clone() {
+                    for subtask in graph.descendants(id) {
+                        if !ids.contains(&subtask.id) {
+                            ids.push(subtask.id);
                         }
                     }
-                    Ok(count)
                 }
-                
-                updated_count += update_subtasks_status(&storage, &task.subtasks, &new_status)?;
             }
+            let updated = workflow::set_status(storage, &config.workflow, &ids, &new_status, resolution.as_deref(), force)?;
             
             println!("{} Updated {} task{} to status: {}", 
                 "✅".green(), 
-                updated_count,
-                if updated_count > 1 { "s" } else { "" },
-                task.status
+                updated.len(),
+                if updated.len() > 1 { "s" } else { "" },
+                new_status
             );
-        }
-        
-        Commands::Edit { id, title, description, priority, complexity } => {
-            let mut task = storage.load_task(id)?;
             
-            if let Some(title) = title {
-                task.title = title;
+            // Completing an occurrence of a recurring task schedules the next one
+            if new_status == TaskStatus::Done {
+                for task in &updated {
+                    if let Some(next) = recur::create_next(storage, task, dates::today())? {
+                        println!("{} Next occurrence: task #{} due {}", "🔁".cyan(), next.id, next.due.map(|d| d.to_string()).unwrap_or_default());
+                    }
+                }
             }
-            
-            if let Some(description) = description {
-                task.description = description;
+        }
+        
+        Commands::Edit { id, filter, title, description, priority, complexity, estimate, fields, unset_fields, tags, untags, due, scheduled, deferred_until, milestone } => {
+            let ids = selected_tasks(storage, config, id, filter.as_deref())?;
+            if ids.is_empty() {
+                println!("{} No tasks match", "ℹ️".blue());
+                return Ok(());
             }
             
-            if let Some(priority) = priority {
-                task.priority = parse_priority(&priority)?;
-            }
+            // Parsed up front, so a bad value fails before any task is changed
+            let priority = priority.as_deref().map(parse_priority).transpose()?;
+            let complexity = complexity.as_deref().map(parse_complexity).transpose()?;
+            let estimate = match estimate.as_deref().map(str::trim) {
+                Some("none") => Some(None),
+                Some(estimate) => Some(Some(estimate.parse()?)),
+                None => None,
+            };
+            let fields = fields.iter()
+                .map(|field| crate::fields::parse_assignment(&config.custom_fields, field))
+                .collect::<Result<Vec<_>>>()?;
+            let today = dates::today();
+            let due = due.map(|due| dates::parse_optional_date(&due, today)).transpose()?;
+            let scheduled = scheduled.map(|scheduled| dates::parse_optional_date(&scheduled, today)).transpose()?;
+            let deferred_until = deferred_until.map(|deferred_until| dates::parse_optional_date(&deferred_until, today)).transpose()?;
+            let milestone = match milestone {
+                Some(name) if name.trim().eq_ignore_ascii_case("none") => Some(None),
+                Some(name) => Some(Some(milestone::Milestones::new(&get_trusty_dir()?).get(&name)?.name)),
+                None => None,
+            };
             
-            if let Some(complexity) = complexity {
-                task.complexity = Some(parse_complexity(&complexity)?);
+            let _lock = storage.lock()?;
+            for id in ids {
+                let mut task = storage.load_task(id)?;
+                
+                if let Some(title) = &title {
+                    task.title = title.clone();
+                }
+                
+                if let Some(description) = &description {
+                    task.description = description.clone();
+                }
+                
+                if let Some(priority) = &priority {
+                    task.priority = priority.clone();
+                }
+                
+                if let Some(complexity) = &complexity {
+                    task.complexity = Some(complexity.clone());
+                }
+                
+                if let Some(estimate) = &estimate {
+                    task.estimate = estimate.clone();
+                }
+                
+                for (name, value) in &fields {
+                    task.extra.insert(name.clone(), value.clone());
+                }
+                
+                for name in &unset_fields {
+                    if task.extra.remove(name).is_none() {
+                        println!("{} Task #{} has no field '{}'", "⚠️".yellow(), id, name);
+                    }
+                }
+                
+                for tag in &tags {
+                    if !task.tags.contains(tag) {
+                        task.tags.push(tag.clone());
+                    }
+                }
+                task.tags.retain(|tag| !untags.contains(tag));
+                
+                if let Some(due) = due {
+                    task.due = due;
+                }
+                if let Some(scheduled) = scheduled {
+                    task.scheduled = scheduled;
+                }
+                if let Some(deferred_until) = deferred_until {
+                    task.deferred_until = deferred_until;
+                }
+                if let Some(milestone) = &milestone {
+                    task.milestone = milestone.clone();
+                }
+                
+                task.updated_at = chrono::Utc::now();
+                storage.save_task(&task)?;
+                
+                println!("{} Updated task #{}", "✅".green(), id);
             }
-            
-            task.updated_at = chrono::Utc::now();
-            storage.save_task(&task)?;
-            
-            println!("{} Updated task #{}", "✅".green(), id);
         }
         
-        Commands::Delete { id } => {
-            storage.delete_task(id)?;
-            println!("{} Deleted task #{}", "✅".green(), id);
+        Commands::Delete { id, filter } => {
+            let ids = selected_tasks(storage, config, id, filter.as_deref())?;
+            if ids.is_empty() {
+                println!("{} No tasks match", "ℹ️".blue());
+                return Ok(());
+            }
+            // Trashed as one batch so references between the tasks are kept as they are
+            let entries = trash::Trash::new(&get_trusty_dir()?).delete(storage, &ids)?;
+            
+            let restore = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(" ");
+            match ids.as_slice() {
+                [id] => println!("{} Moved task #{} to the trash", "✅".green(), id),
+                _ => println!("{} Moved {} tasks to the trash", "✅".green(), ids.len()),
+            }
+            let references = entries.iter().map(|e| e.referenced_by.len()).sum::<usize>();
+            if references > 0 {
+                println!("   Removed {} reference(s) from other tasks; they come back on restore", references);
+            }
+            println!("   Undo with: trusty trash restore {}", restore);
         }
         
         Commands::AddDep { task, dep } => {
+            let (task, dep) = (storage.resolve(&task)?, storage.resolve(&dep)?);
+            let _lock = storage.lock()?;
             let mut t = storage.load_task(task)?;
+            storage.load_graph()?.check_dependency(task, dep)?;
             t.add_dependency(dep);
             storage.save_task(&t)?;
             
@@ -231,6 +475,8 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
         }
         
         Commands::RemoveDep { task, dep } => {
+            let (task, dep) = (storage.resolve(&task)?, storage.resolve(&dep)?);
+            let _lock = storage.lock()?;
             let mut t = storage.load_task(task)?;
             t.remove_dependency(dep);
             storage.save_task(&t)?;
@@ -238,10 +484,62 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
             println!("{} Removed dependency #{} from task #{}", "✅".green(), dep, task);
         }
         
-        Commands::AddSubtask { task, title, description, priority, tags, prompt } => {
+        Commands::Relate { task, kind, other } => {
+            let (task, other) = (storage.resolve(&task)?, storage.resolve(&other)?);
+            let _lock = storage.lock()?;
+            let mut t = storage.load_task(task)?;
+            let graph = storage.load_graph()?;
+            graph.check_relation(task, Relation { kind, task: other })?;
+            
+            let symmetric = kind == RelationKind::RelatesTo
+                && graph.get(other).is_some_and(|o| o.relations.contains(&Relation { kind, task }));
+            if symmetric || !t.add_relation(kind, other) {
+                println!("{} Task #{} already {} #{}", "⚠️".yellow(), task, kind, other);
+                return Ok(());
+            }
+            storage.save_task(&t)?;
+            
+            println!("{} Task #{} {} #{}", "✅".green(), task, kind, other);
+        }
+        
+        Commands::Unrelate { task, kind, other } => {
+            let (task, other) = (storage.resolve(&task)?, storage.resolve(&other)?);
+            let _lock = storage.lock()?;
+            let mut t = storage.load_task(task)?;
+            if t.remove_relation(kind, other) {
+                storage.save_task(&t)?;
+            } else {
+                // relates-to reads the same from both sides, so it may be held by the other task
+                let mut o = storage.load_task(other)?;
+                if kind != RelationKind::RelatesTo || !o.remove_relation(kind, task) {
+                    anyhow::bail!("Task #{} doesn't have a {} link to #{}", task, kind, other);
+                }
+                storage.save_task(&o)?;
+            }
+            
+            println!("{} Removed {} link between #{} and #{}", "✅".green(), kind, task, other);
+        }
+        
+        Commands::AddSubtask { task, title, description, priority, tags, prompt, existing } => {
+            let task = storage.resolve(&task)?;
+            
+            if let Some(existing) = existing {
+                let subtask = storage.resolve(&existing)?;
+                let _lock = storage.lock()?;
+                let mut parent = storage.load_task(task)?;
+                if parent.subtasks.contains(&subtask) {
+                    println!("{} Task #{} is already a subtask of #{}", "⚠️".yellow(), subtask, task);
+                    return Ok(());
+                }
+                storage.load_graph()?.check_subtask(task, subtask)?;
+                parent.add_subtask(subtask);
+                storage.save_task(&parent)?;
+                
+                println!("{} Added task #{} as a subtask of #{}", "✅".green(), subtask, task);
+                return Ok(());
+            }
+            
             let parent_task = storage.load_task(task)?;
-            let tasks = storage.list_all_tasks()?;
-            let next_id = tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
             
             let (final_title, final_description, final_priority, final_tags) = if let Some(prompt_text) = prompt {
                 // Generate subtask from prompt
@@ -278,6 +576,8 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                 (title, description.unwrap_or_default(), priority, tags_vec)
             };
             
+            let _lock = storage.lock()?;
+            let next_id = storage.allocate_id()?;
             let mut subtask = Task::new(
                 next_id,
                 final_title.clone(),
@@ -298,6 +598,8 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
         }
         
         Commands::RemoveSubtask { task, subtask } => {
+            let (task, subtask) = (storage.resolve(&task)?, storage.resolve(&subtask)?);
+            let _lock = storage.lock()?;
             let mut parent = storage.load_task(task)?;
             let initial_count = parent.subtasks.len();
             parent.subtasks.retain(|&id| id != subtask);
@@ -310,39 +612,198 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
             }
         }
         
-        Commands::Complete { id, all } => {
+        Commands::Move { id, under, top_level } => {
+            let id = storage.resolve(&id)?;
+            let parent = match (under, top_level) {
+                (Some(parent), false) => Some(storage.resolve(&parent)?),
+                (None, true) => None,
+                _ => anyhow::bail!("Use --under or --top-level"),
+            };
+            let destination = parent.map_or("the top level".to_string(), |parent| format!("task #{}", parent));
+            
+            match hierarchy::move_task(storage, id, parent)? {
+                None => println!("{} Task #{} is already at {}", "⚠️".yellow(), id, destination),
+                Some(from) if from.is_empty() => println!("{} Moved task #{} to {}", "✅".green(), id, destination),
+                Some(from) => println!("{} Moved task #{} from {} to {}", "✅".green(), id,
+                    from.iter().map(|p| format!("#{}", p)).collect::<Vec<_>>().join(" and "), destination),
+            }
+        }
+        
+        Commands::Reorder { parent, id, before, after } => {
+            let (parent, id) = (storage.resolve(&parent)?, storage.resolve(&id)?);
+            let placement = match (before, after) {
+                (Some(before), _) => hierarchy::Placement::Before(storage.resolve(&before)?),
+                (None, Some(after)) => hierarchy::Placement::After(storage.resolve(&after)?),
+                (None, None) => anyhow::bail!("Use --before or --after"),
+            };
+            hierarchy::reorder(storage, parent, id, placement)?;
+            
+            let subtasks = storage.load_task(parent)?.subtasks;
+            println!("{} Subtasks of #{}: {}", "✅".green(), parent,
+                subtasks.iter().map(|s| format!("#{}", s)).collect::<Vec<_>>().join(", "));
+        }
+        
+        Commands::Complete { id, filter, all, resolution, force } => {
             let command = Commands::SetStatus {
                 id,
+                filter,
                 status: "done".to_string(),
                 cascade: all,
+                resolution,
+                force,
             };
-            return handle_command(command, storage);
+            return handle_command(command, storage, config);
         }
         
-        Commands::Init => unreachable!(),
-        
-        Commands::AddAgent { scope, global, local: _, name, model, color } => {
-            let is_global = global || scope.as_deref() == Some("global");
-            let agent_config = agent::AgentConfig::new(name, model, color, is_global);
-            
-            match agent::install_agent(&agent_config) {
-                Ok(path) => {
-                    println!("{} Successfully installed agent '{}' to:", "✅".green(), agent_config.name);
-                    println!("   {}", path.display());
-                    println!("\n{} To use this agent in Claude:", "💡".yellow());
-                    println!("   1. Start a new Claude conversation");
-                    println!("   2. Type: {} {}", "@".cyan(), agent_config.name.cyan());
-                    println!("   3. Ask for help managing your project!");
+        Commands::Defer { id, until, force } => {
+            let id = storage.resolve(&id)?;
+            let today = dates::today();
+            let until = defer::parse_until(&until, today)?;
+            let _lock = storage.lock()?;
+
+            let graph = storage.load_graph()?;
+            let mut deferred = graph.get(id).cloned().ok_or_else(|| anyhow::anyhow!("Task #{} not found", id))?;
+            deferred.deferred_until = None;
+            deferred.wake_when = None;
+            match until {
+                defer::Until::Date(date) if date <= today => {
+                    anyhow::bail!("{} isn't in the future, so task #{} would wake up right away", date, id);
                 }
-                Err(e) => {
-                    eprintln!("{} Failed to install agent: {}", "❌".red(), e);
-                    return Err(e);
+                defer::Until::Date(date) => deferred.deferred_until = Some(date),
+                defer::Until::Task(dep) => {
+                    let dep = storage.resolve(&dep)?;
+                    graph.check_dependency(id, dep)?;
+                    deferred.add_dependency(dep);
+                    deferred.wake_when = Some(defer::WakeCondition::DependenciesDone);
+                }
+                defer::Until::Condition(condition) => deferred.wake_when = Some(condition),
+            }
+
+            let project_root = project_root()?;
+            if let Some(condition) = deferred.wake_when.as_ref().filter(|c| c.is_met(&deferred, &graph, &project_root)) {
+                anyhow::bail!("'{}' is already met, so task #{} would wake up right away", condition, id);
+            }
+
+            // The workflow checks the move from the task's current status, then the wake-up is recorded
+            let mut task = workflow::set_status(storage, &config.workflow, &[id], &TaskStatus::Deferred, None, force)?.remove(0);
+            task.dependencies = deferred.dependencies;
+            task.deferred_until = deferred.deferred_until;
+            task.wake_when = deferred.wake_when;
+            storage.save_task(&task)?;
+
+            let when = match (task.deferred_until, &task.wake_when) {
+                (Some(date), _) => format!("{} ({})", date, dates::describe(date, today)),
+                (None, Some(condition)) => condition.describe(),
+                (None, None) => unreachable!(),
+            };
+            println!("{} Deferred task #{} until {}", "✅".green(), id, when);
+        }
+
+        Commands::Start { id } => {
+            let id = storage.resolve(&id)?;
+            start_timer(storage, id)?;
+        }
+
+        Commands::Stop => {
+            match timelog::Timers::new(&get_trusty_dir()?).stop(storage)? {
+                Some((task, entry)) => println!("{} Logged {} on task #{}: {}", "⏱️".cyan(), timelog::format_minutes(entry.minutes), task.id, task.title),
+                None => println!("{} No timer is running", "ℹ️".blue()),
+            }
+        }
+
+        Commands::LogTime { id, duration, note, date } => {
+            let id = storage.resolve(&id)?;
+            let minutes = timelog::parse_duration(&duration)?;
+            let started_at = match date {
+                // Noon keeps the entry on that day in any time zone close to the one it was logged in
+                Some(date) => dates::parse_date(&date, dates::today())?
+                    .and_hms_opt(12, 0, 0)
+                    .and_then(|noon| noon.and_local_timezone(chrono::Local).single())
+                    .map(|noon| noon.with_timezone(&chrono::Utc))
+                    .ok_or_else(|| anyhow::anyhow!("Invalid date: {}", date))?,
+                None => chrono::Utc::now() - chrono::Duration::minutes(minutes.into()),
+            };
+            
+            let _lock = storage.lock()?;
+            let mut task = storage.load_task(id)?;
+            task.time_log.push(timelog::TimeEntry { started_at, minutes, actor: history::current_actor(), note });
+            storage.save_task(&task)?;
+            println!("{} Logged {} on task #{} ({} in total)", "✅".green(), timelog::format_minutes(minutes), id,
+                timelog::format_minutes(task.logged_minutes()));
+        }
+
+        Commands::Estimates => {
+            let tasks = storage.list_all_tasks()?;
+            let calibration = estimate::Calibration::from_tasks(&tasks);
+            
+            for (unit, label, per) in [(estimate::EstimateUnit::Hours, "Hour estimates", "h per estimated h"), (estimate::EstimateUnit::Points, "Point estimates", "h per point")] {
+                let overall = estimate::accuracy_by(&tasks, unit, |_| vec![String::new()]).remove("").unwrap_or_default();
+                println!("{}", format!("📏 {}: {} done task(s)", label, overall.tasks).bold());
+                if overall.tasks == 0 {
+                    println!();
+                    continue;
+                }
+                println!("  Overall: {}", format_accuracy(&overall, per));
+                for (heading, groups) in [
+                    ("By tag", estimate::accuracy_by(&tasks, unit, estimate::tag_keys)),
+                    ("By complexity", estimate::accuracy_by(&tasks, unit, estimate::complexity_key)),
+                ] {
+                    println!("  {}:", heading);
+                    for (group, accuracy) in &groups {
+                        println!("    {:<16} {}", group, format_accuracy(accuracy, per));
+                    }
+                }
+                println!();
+            }
+            
+            if !calibration.by_complexity.is_empty() {
+                println!("{}", "⏱️  Typical actual time by complexity".bold());
+                for (complexity, hours) in &calibration.by_complexity {
+                    println!("    {:<16} {:.1}h", complexity, hours);
                 }
             }
         }
+
+        Commands::Timesheet { week, from, to } => {
+            let today = dates::today();
+            let monday = dates::week_start(today);
+            let to = to.map(|d| dates::parse_date(&d, today)).transpose()?;
+            let (from, to) = match (week, from) {
+                (false, Some(from)) => (dates::parse_date(&from, today)?, to.unwrap_or(today)),
+                // --week, or a range that only gives --to, starts on this week's Monday
+                (true, _) | (false, None) => (monday, to.unwrap_or(monday + chrono::Days::new(6))),
+            };
+            
+            let sheet = timelog::timesheet(&storage.list_all_tasks()?, from, to);
+            println!("{}", format!("⏱️  Time logged {} to {}", from, to).bold());
+            println!("{}", "─".repeat(50));
+            for day in from.iter_days().take_while(|day| *day <= to) {
+                let minutes = sheet.by_day.get(&day).copied().unwrap_or(0);
+                let line = format!("  {} {}  {:>8}", day.format("%a"), day, timelog::format_minutes(minutes));
+                println!("{}", if minutes == 0 { line.dimmed().to_string() } else { line });
+            }
+            
+            if !sheet.by_tag.is_empty() {
+                println!("\n{}", "By tag:".bold());
+                let mut tags: Vec<_> = sheet.by_tag.iter().collect();
+                tags.sort_by_key(|(tag, minutes)| (std::cmp::Reverse(**minutes), tag.to_string()));
+                for (tag, minutes) in tags {
+                    println!("  {:<20} {:>8}", tag, timelog::format_minutes(*minutes));
+                }
+            }
+            println!("\n{} {}", "Total:".bold(), timelog::format_minutes(sheet.total));
+        }
+
+        Commands::Init { .. } | Commands::MergeDriver { .. } => unreachable!(),
+        Commands::AddAgent { .. } | Commands::Demo { .. } => unreachable!(),
+        Commands::Undo { .. } | Commands::Redo { .. } | Commands::Journal { .. } => unreachable!(),
         
-        Commands::Demo { skip_confirm, delay, keep } => {
-            run_demo(storage, skip_confirm, delay, keep)?;
+        Commands::MigrateIds => {
+            migrate_ids(storage, config)?;
+        }
+        
+        Commands::Doctor { fix } => {
+            run_doctor(storage, config, fix)?;
         }
         
         Commands::Nuke { force } => {
@@ -378,48 +839,38 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                 }
             }
             
-            // Delete all tasks
-            let mut deleted = 0;
-            let mut errors = 0;
-            
-            for task in tasks {
-                match storage.delete_task(task.id) {
-                    Ok(_) => deleted += 1,
-                    Err(e) => {
-                        eprintln!("{} Failed to delete task #{}: {}", "❌".red(), task.id, e);
-                        errors += 1;
-                    }
-                }
-            }
+            // Trashed as one batch so references between the tasks are kept as they are
+            let ids: Vec<u32> = tasks.iter().map(|t| t.id).collect();
+            let entries = trash::Trash::new(&get_trusty_dir()?).delete(storage, &ids)?;
             
-            if errors > 0 {
-                println!("{} Deleted {} task(s) with {} error(s).", "⚠️".yellow(), deleted, errors);
-            } else {
-                println!("{} Successfully deleted {} task(s)!", "💥".bright_red(), deleted);
-            }
+            println!("{} Moved {} task(s) to the trash!", "💥".bright_red(), entries.len());
+            println!("   Undo with: trusty trash restore --all");
         }
         
-        Commands::Next { start, details } => {
-            let mut tasks = storage.list_all_tasks()?;
+        Commands::Next { query, start, details } => {
+            let graph = storage.load_graph()?.with_checklists(config.checklists_complete_tasks);
+            let mut tasks = graph.tasks().to_vec();
+            let query = query::Query::parse(&query.join(" "), config, dates::today())?;
+            tasks.retain(|t| query.matches(t, &graph));
             
             // Apply default filtering (hide completed tasks older than 5 minutes)
             let cutoff_time = chrono::Utc::now() - chrono::Duration::minutes(5);
             tasks.retain(|t| {
                 match t.status {
                     TaskStatus::Done => {
-                        t.completed_at.map_or(false, |completed| completed > cutoff_time)
+                        t.completed_at.is_some_and(|completed| completed > cutoff_time)
                     }
                     _ => true,
                 }
             });
             
-            let next_task = display::TaskDisplay::get_next_task(&tasks);
+            let next_task = display::TaskDisplay::get_next_task(&tasks, &graph);
             
             if let Some(task) = next_task {
                 println!("\n{}", format!("🔥 Next Task to Work On: #{} - {}", task.id, task.title).bright_cyan().bold());
                 
                 if details || start {
-                    display_task_details(task, Some(&tasks));
+                    display_task_details(task, Some(&graph), config);
                 } else {
                     println!("\nPriority: {}   Status: {}", task.priority, task.status);
                     if !task.dependencies.is_empty() {
@@ -432,10 +883,10 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                 
                 if start {
                     println!("\n{} Setting task to in-progress...", "⚡".yellow());
-                    let mut task_mut = storage.load_task(task.id)?;
-                    task_mut.set_status(TaskStatus::InProgress);
-                    storage.save_task(&task_mut)?;
+                    let _lock = storage.lock()?;
+                    workflow::set_status(storage, &config.workflow, &[task.id], &TaskStatus::InProgress, None, false)?;
                     println!("{} Task #{} is now in progress!", "✅".green(), task.id);
+                    start_timer(storage, task.id)?;
                 }
                 
                 println!("\n{}: trusty set-status --id={} --status=in-progress", "Start working".bold(), task.id);
@@ -447,12 +898,17 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
             }
         }
         
-        Commands::Prune { dry_run, auto, limit } => {
+        Commands::Prune { query, dry_run, auto, limit, force } => {
             let tasks = storage.list_all_tasks()?;
-            let tasks_dir = get_tasks_dir()?;
-            let mut analyzer = prune::PruneAnalyzer::new(tasks_dir)?;
+            let mut analyzer = prune::PruneAnalyzer::new(get_trusty_dir()?)?;
             
-            let suggestions = analyzer.analyze_tasks(&tasks);
+            let mut suggestions = analyzer.analyze_tasks(&tasks);
+            let query = query::Query::parse(&query.join(" "), config, dates::today())?;
+            if !query.is_empty() {
+                // Analyzed as a whole, since rules look at related tasks, then narrowed down
+                let graph = TaskGraph::new(tasks.clone());
+                suggestions.retain(|s| query.matches(&s.task, &graph));
+            }
             let suggestions_to_show: Vec<_> = suggestions.into_iter().take(limit).collect();
             
             if suggestions_to_show.is_empty() {
@@ -508,10 +964,10 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                     "a" => {
                         // Apply all suggestions
                         for suggestion in &suggestions_to_show {
-                            apply_prune_action(&storage, &mut analyzer, suggestion)?;
+                            apply_prune_action(storage, &config.workflow, &mut analyzer, suggestion, force)?;
                         }
                     }
-                    "s" | _ => {
+                    _ => {
                         // Interactive selection
                         for suggestion in &suggestions_to_show {
                             println!("\n{}", "─".repeat(50));
@@ -532,7 +988,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                             
                             match input.trim().to_lowercase().as_str() {
                                 "y" => {
-                                    apply_prune_action(&storage, &mut analyzer, suggestion)?;
+                                    apply_prune_action(storage, &config.workflow, &mut analyzer, suggestion, force)?;
                                 }
                                 "q" => {
                                     println!("{} Stopped processing.", "✗".yellow());
@@ -549,7 +1005,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
             } else {
                 // Auto mode - apply all suggestions
                 for suggestion in &suggestions_to_show {
-                    apply_prune_action(&storage, &mut analyzer, suggestion)?;
+                    apply_prune_action(storage, &config.workflow, &mut analyzer, suggestion, force)?;
                 }
             }
             
@@ -559,6 +1015,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
         }
         
         Commands::Decompose { id, count, preview } => {
+            let id = storage.resolve(&id)?;
             let task = storage.load_task(id)?;
             
             // Check if task already has subtasks
@@ -609,11 +1066,11 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                         println!("\n{} This is a preview. Run without --preview to create these subtasks.", "ℹ️".blue());
                     } else {
                         // Create the subtasks
-                        let tasks = storage.list_all_tasks()?;
-                        let mut next_id = tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+                        let _lock = storage.lock()?;
                         let mut created_count = 0;
                         
                         for subtask in decomposed.subtasks {
+                            let next_id = storage.allocate_id()?;
                             let priority = parse_priority(&subtask.priority)?;
                             let mut new_task = Task::new(
                                 next_id,
@@ -632,7 +1089,6 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                             
                             println!("{} Created subtask #{}: {}", "✅".green(), next_id, subtask.title);
                             
-                            next_id += 1;
                             created_count += 1;
                         }
                         
@@ -646,7 +1102,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
             }
         }
         
-        Commands::Import { file, format, duplicates, preview } => {
+        Commands::Import { file, format, duplicates, preview, force } => {
             use std::path::Path;
             use crate::import::{ImportFormat, DuplicateHandling, TaskImporter};
             
@@ -703,7 +1159,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                         println!("Found {} task(s) to import:", lines.len());
                         for (i, line) in lines.iter().enumerate() {
                             if let Some(title) = line.strip_prefix("## Task:") {
-                                let title = title.trim().split_whitespace()
+                                let title = title.split_whitespace()
                                     .take_while(|w| !w.starts_with('[') && !w.starts_with('#'))
                                     .collect::<Vec<_>>()
                                     .join(" ");
@@ -715,7 +1171,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                 
                 println!("\n{} Run without --preview to import these tasks", "ℹ️".blue());
             } else {
-                let mut importer = TaskImporter::new(storage);
+                let mut importer = TaskImporter::new(storage, &config.workflow, force);
                 match importer.import_from_file(file_path, format, duplicate_handling) {
                     Ok(result) => {
                         println!("\n{} {}", "✅".green(), result.summary());
@@ -731,9 +1187,8 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
         Commands::Task { command } => {
             match command {
                 TaskCommands::Advice { id, detailed, ask_claude, interactive, history } => {
+                    let id = storage.resolve(&id)?;
                     let task = storage.load_task(id)?;
-                    let tasks_dir = get_tasks_dir()?;
-                    
                     let actions = if ask_claude {
                         // Use Claude integration for more intelligent advice
                         let all_tasks = storage.list_all_tasks()?;
@@ -748,7 +1203,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                                 println!("Falling back to local analysis...\n");
                                 
                                 // Fall back to local analysis
-                                let mut advisor = advice::TaskAdvisor::new(task.clone(), &storage, tasks_dir)?;
+                                let mut advisor = advice::TaskAdvisor::new(task.clone(), storage)?;
                                 let task_advice = advisor.analyze();
                                 let suggested_actions = advice::display_advice(&task_advice, &task, detailed);
                                 // Convert to tuple format
@@ -759,7 +1214,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                         }
                     } else {
                         // Use local analysis
-                        let mut advisor = advice::TaskAdvisor::new(task.clone(), &storage, tasks_dir)?;
+                        let mut advisor = advice::TaskAdvisor::new(task.clone(), storage)?;
                         let task_advice = advisor.analyze();
                         let suggested_actions = advice::display_advice(&task_advice, &task, detailed);
                         // Convert to tuple format
@@ -779,7 +1234,19 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
             }
         }
         
-        Commands::Focus { prd, preview, ai } => {
+        Commands::Trash { command } => {
+            handle_trash_command(command, storage)?;
+        }
+        
+        Commands::Recur { command } => {
+            handle_recur_command(command, storage)?;
+        }
+        
+        Commands::Milestone { command } => {
+            handle_milestone_command(command, storage, config)?;
+        }
+        
+        Commands::Focus { prd, preview } => {
             let mut analyzer = focus::FocusAnalyzer::new(storage);
             
             // Load PRD if provided
@@ -808,6 +1275,7 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
                         io::stdin().read_line(&mut input)?;
                         
                         if input.trim().to_lowercase() == "y" {
+                            let _lock = storage.lock()?;
                             for suggestion in &report.reprioritization_suggestions {
                                 if let Ok(mut task) = storage.load_task(suggestion.task_id) {
                                     task.priority = suggestion.suggested_priority.clone();
@@ -830,96 +1298,644 @@ fn handle_command(command: Commands, storage: TaskStorage) -> Result<()> {
     Ok(())
 }
 
-fn get_storage() -> Result<TaskStorage> {
-    let tasks_dir = get_tasks_dir()?;
-    TaskStorage::new(tasks_dir)
+/// Give every task a uid and switch the project to the `ulid` ID scheme.
+///
+/// Runs in two passes: the first assigns uids, the second rewrites every
+/// task so that its references to other tasks are stored as uids too.
+fn migrate_ids(storage: &dyn TaskStore, config: &ProjectConfig) -> Result<()> {
+    let trusty_dir = get_trusty_dir()?;
+    let _lock = storage.lock()?;
+    
+    let mut migrated = 0;
+    for mut task in storage.list_all_tasks()? {
+        if task.uid.is_none() {
+            task.uid = Some(task::generate_uid());
+            storage.save_task(&task)?;
+            migrated += 1;
+        }
+    }
+    
+    for task in storage.list_all_tasks()? {
+        storage.save_task(&task)?;
+    }
+    
+    let config = ProjectConfig { id_scheme: IdScheme::Ulid, ..config.clone() };
+    config.save(&trusty_dir)?;
+    
+    println!("{} Assigned uids to {} task(s); new tasks will get one automatically", "✅".green(), migrated);
+    println!("Numeric IDs keep working as local aliases, e.g. trusty show 3");
+    
+    Ok(())
+}
+
+fn run_doctor(storage: &dyn TaskStore, config: &ProjectConfig, fix: bool) -> Result<()> {
+    let _lock = storage.lock()?;
+    
+    // Problems that need a human: unreadable files and statuses missing from the workflow
+    let mut manual = 0;
+    for (file, reason) in storage.unreadable()? {
+        println!("{} Can't read {}: {}", "❌".red(), file, reason);
+        manual += 1;
+    }
+    
+    let original = storage.list_all_tasks()?;
+    for task in original.iter().filter(|t| !config.workflow.defines(&t.status)) {
+        println!("{} Task #{} has status '{}', which isn't in the workflow", "❌".red(), task.id, task.status.name());
+        manual += 1;
+    }
+    let mut problems = TaskGraph::new(original.clone()).problems();
+    for problem in &problems {
+        println!("{} {}", "⚠️".yellow(), problem);
+    }
+    
+    if problems.is_empty() && manual == 0 {
+        println!("{} No problems found in {} task(s)", "✅".green(), original.len());
+        return Ok(());
+    }
+    if !fix {
+        let hint = if problems.is_empty() { "" } else { "; run `trusty doctor --fix` to remove the offending links" };
+        anyhow::bail!("Found {} problem(s){}", problems.len() + manual, hint);
+    }
+    
+    // One fix at a time, since removing one link often resolves other problems too
+    let mut tasks: HashMap<u32, Task> = original.iter().map(|t| (t.id, t.clone())).collect();
+    while let Some(problem) = problems.first() {
+        let fixed = problem.fix(&mut tasks)
+            .ok_or_else(|| anyhow::anyhow!("Couldn't fix: {}", problem))?;
+        println!("{} {}", "🔧".green(), fixed);
+        problems = TaskGraph::new(tasks.values().cloned().collect()).problems();
+    }
+    
+    for task in &original {
+        if tasks[&task.id] != *task {
+            storage.save_task(&tasks[&task.id])?;
+        }
+    }
+    
+    if manual > 0 {
+        anyhow::bail!("{} problem(s) need fixing by hand", manual);
+    }
+    println!("{} All links fixed", "✅".green());
+    Ok(())
+}
+
+/// The command as typed, for the journal
+fn command_line() -> String {
+    env::args()
+        .skip(1)
+        .map(|arg| if arg.contains(char::is_whitespace) { format!("{:?}", arg) } else { arg })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn handle_journal_command(command: Commands, storage: &dyn TaskStore) -> Result<()> {
+    let journal = journal::Journal::new(&get_trusty_dir()?);
+    
+    let describe = |op: &journal::Operation| {
+        let ids: Vec<String> = op.changes.iter().map(|c| format!("#{}", c.id)).collect();
+        format!("{} {} ({})", op.seq.to_string().dimmed(), op.command, ids.join(", "))
+    };
+    
+    match command {
+        Commands::Undo { count, force } => {
+            let reverted = journal.undo(storage, count, force)?;
+            if reverted.is_empty() {
+                println!("{} Nothing to undo.", "ℹ️".blue());
+            }
+            for op in &reverted {
+                println!("{} Undid {}", "↩️".green(), describe(op));
+            }
+        }
+        
+        Commands::Redo { count, force } => {
+            let reapplied = journal.redo(storage, count, force)?;
+            if reapplied.is_empty() {
+                println!("{} Nothing to redo.", "ℹ️".blue());
+            }
+            for op in &reapplied {
+                println!("{} Redid {}", "↪️".green(), describe(op));
+            }
+        }
+        
+        Commands::Journal { limit } => {
+            let operations = journal.load()?;
+            if operations.is_empty() {
+                println!("{} No changes recorded yet.", "ℹ️".blue());
+                return Ok(());
+            }
+            
+            let skip = operations.len().saturating_sub(limit);
+            for op in operations.iter().skip(skip).rev() {
+                let when = op.at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+                let line = format!("{}  {}", when, describe(op));
+                if op.undone {
+                    println!("  {} {}", line.dimmed(), "(undone)".yellow());
+                } else {
+                    println!("  {}", line);
+                }
+            }
+        }
+        
+        _ => unreachable!(),
+    }
+    
+    Ok(())
+}
+
+/// `3 task(s)  est 10.0  actual 14.5h  1.45 h per estimated h`
+fn format_accuracy(accuracy: &estimate::Accuracy, per: &str) -> String {
+    format!("{} task(s)  est {:.1}  actual {:.1}h  {}", accuracy.tasks, accuracy.estimated, accuracy.actual_hours,
+        accuracy.ratio().map(|r| format!("{:.2} {}", r, per)).unwrap_or_default())
 }
 
-fn get_tasks_dir() -> Result<PathBuf> {
-    let current_dir = std::env::current_dir()?;
-    Ok(current_dir.join(".trusty").join("tasks"))
+/// Start the current actor's timer on `id`, reporting any timer it replaced
+fn start_timer(storage: &dyn TaskStore, id: u32) -> Result<()> {
+    let timers = timelog::Timers::new(&get_trusty_dir()?);
+    if let Some((task, entry)) = timers.start(storage, id)? {
+        println!("{} Stopped the timer on task #{} and logged {}", "⏱️".cyan(), task.id, timelog::format_minutes(entry.minutes));
+    }
+    println!("{} Timer running on task #{}", "⏱️".cyan(), id);
+    Ok(())
 }
 
-fn apply_prune_action(storage: &TaskStorage, analyzer: &mut prune::PruneAnalyzer, suggestion: &prune::PruneSuggestion) -> Result<()> {
-    match suggestion.action {
-        prune::PruneAction::Complete => {
-            let mut task = storage.load_task(suggestion.task.id)?;
-            task.set_status(TaskStatus::Done);
-            storage.save_task(&task)?;
-            println!("{} Completed task #{}: {}", "✅".green(), task.id, task.title);
+fn handle_recur_command(command: RecurCommands, storage: &dyn TaskStore) -> Result<()> {
+    match command {
+        RecurCommands::List => {
+            let tasks = storage.list_all_tasks()?;
+            let mut current: Vec<&Task> = tasks.iter().filter(|t| t.recurrence.is_some()).collect();
+            if current.is_empty() {
+                println!("{} No recurring tasks.", "ℹ️".blue());
+                return Ok(());
+            }
+            current.sort_by_key(|t| (t.due, t.id));
+            
+            println!("{}", format!("🔁 {} recurring series", current.len()).bold());
+            println!("{}", "─".repeat(50));
+            for task in current {
+                let series = recur::series_of(task);
+                let done = tasks.iter()
+                    .filter(|t| recur::series_of(t) == series && t.status == TaskStatus::Done)
+                    .count();
+                let due = task.due.map(|d| format!("next due {}", d)).unwrap_or_default();
+                println!("  #{} - {} {}", task.id, task.title, format!("({}, {} done)", due, done).dimmed());
+                println!("      {}", task.recurrence.as_ref().map(|r| r.describe()).unwrap_or_default());
+            }
         }
-        prune::PruneAction::Cancel => {
-            let mut task = storage.load_task(suggestion.task.id)?;
-            task.set_status(TaskStatus::Cancelled);
-            storage.save_task(&task)?;
-            println!("{} Cancelled task #{}: {}", "❌".red(), task.id, task.title);
+        
+        RecurCommands::Stop { id } => {
+            let id = storage.resolve(&id)?;
+            let _lock = storage.lock()?;
+            let tasks = storage.list_all_tasks()?;
+            let task = tasks.iter().find(|t| t.id == id).ok_or_else(|| anyhow::anyhow!("Task #{} not found", id))?;
+            let series = recur::series_of(task);
+            
+            let mut stopped = Vec::new();
+            for task in tasks.iter().filter(|t| recur::series_of(t) == series && t.recurrence.is_some()) {
+                let mut task = task.clone();
+                task.recurrence = None;
+                storage.save_task(&task)?;
+                stopped.push(format!("#{}", task.id));
+            }
+            
+            if stopped.is_empty() {
+                println!("{} Task #{} isn't part of a recurring series", "ℹ️".blue(), id);
+            } else {
+                println!("{} Stopped the series; {} won't create another occurrence", "✅".green(), stopped.join(", "));
+            }
         }
-        prune::PruneAction::Skip => {
-            println!("{} Skipped task #{}", "→".blue(), suggestion.task.id);
+    }
+    
+    Ok(())
+}
+
+fn handle_milestone_command(command: MilestoneCommands, storage: &dyn TaskStore, config: &ProjectConfig) -> Result<()> {
+    let milestones = milestone::Milestones::new(&get_trusty_dir()?);
+    let today = dates::today();
+    
+    match command {
+        MilestoneCommands::Add { name, due, description } => {
+            let _lock = storage.lock()?;
+            let mut all = milestones.load()?;
+            let mut milestone = milestone::Milestone::new(&name)?;
+            if all.iter().any(|m| m.name == milestone.name) {
+                anyhow::bail!("Milestone '{}' already exists", milestone.name);
+            }
+            milestone.due = due.map(|d| dates::parse_date(&d, today)).transpose()?;
+            milestone.description = description.unwrap_or_default();
+            println!("{} Created milestone {}", "✅".green(), milestone.name.cyan());
+            all.push(milestone);
+            milestones.save(&all)?;
+        }
+        
+        MilestoneCommands::Edit { name, due, description } => {
+            let _lock = storage.lock()?;
+            let mut all = milestones.load()?;
+            let name = milestones.get(&name)?.name;
+            let milestone = all.iter_mut().find(|m| m.name == name).expect("milestone was just found");
+            if let Some(due) = due {
+                milestone.due = dates::parse_optional_date(&due, today)?;
+            }
+            if let Some(description) = description {
+                milestone.description = description;
+            }
+            milestones.save(&all)?;
+            println!("{} Updated milestone {}", "✅".green(), name.cyan());
+        }
+        
+        MilestoneCommands::List => {
+            let mut all = milestones.load()?;
+            if all.is_empty() {
+                println!("{} No milestones. Create one with `trusty milestone add <name> --due <date>`", "ℹ️".blue());
+                return Ok(());
+            }
+            // Dated milestones first, soonest first
+            all.sort_by(|a, b| (a.due.is_none(), a.due, &a.name).cmp(&(b.due.is_none(), b.due, &b.name)));
+            
+            let graph = storage.load_graph()?.with_checklists(config.checklists_complete_tasks);
+            println!("{}", format!("🏁 {} milestone(s)", all.len()).bold());
+            println!("{}", "─".repeat(50));
+            for milestone in &all {
+                let progress = milestone::progress(&graph, milestone, today);
+                let due = milestone.due.map(|d| format!("due {}, ", d)).unwrap_or_default();
+                println!(
+                    "  {} {} {}",
+                    milestone.name.cyan(),
+                    TaskDisplay::create_progress_bar(progress.percent()),
+                    format!("({}{}/{} done)", due, progress.done, progress.total - progress.cancelled).dimmed()
+                );
+            }
+        }
+        
+        MilestoneCommands::Show { name } => {
+            let milestone = milestones.get(&name)?;
+            let graph = storage.load_graph()?.with_checklists(config.checklists_complete_tasks);
+            let progress = milestone::progress(&graph, &milestone, today);
+            let title = |id: u32| graph.get(id).map(|t| format!("#{} - {}", id, t.title)).unwrap_or_else(|| format!("#{} (not found)", id));
+            
+            println!("{} {}", "🏁 Milestone".bold(), milestone.name.cyan().bold());
+            if let Some(due) = milestone.due {
+                println!("{}: {} ({})", "Target".bold(), due, dates::describe(due, today));
+            }
+            if !milestone.description.is_empty() {
+                println!("{}", milestone.description);
+            }
+            println!(
+                "{}: {} {:.0}%  ({}/{} done, {} cancelled)",
+                "Progress".bold(),
+                TaskDisplay::create_progress_bar(progress.percent()),
+                progress.percent(),
+                progress.done,
+                progress.total - progress.cancelled,
+                progress.cancelled
+            );
+            
+            if progress.total == 0 {
+                println!("No tasks yet. Add some with `trusty milestone assign {} <id>...`", milestone.name);
+                return Ok(());
+            }
+            
+            if progress.blockers.is_empty() {
+                println!("{}: none", "Blockers".bold());
+            } else {
+                println!("{}:", "Blockers".bold());
+                for blocker in &progress.blockers {
+                    let reason = if blocker.waiting_on.is_empty() {
+                        "blocked".to_string()
+                    } else {
+                        format!("waiting on {}", blocker.waiting_on.iter().map(|&id| title(id)).collect::<Vec<_>>().join(", "))
+                    };
+                    println!("  {} {}", title(blocker.task), format!("({})", reason).red());
+                }
+            }
+            
+            match progress.projected {
+                _ if progress.remaining() == 0 => println!("{}: all tasks are closed", "Finish".bold()),
+                None => println!("{}: nothing finished recently to project from ({} open)", "Projected finish".bold(), progress.remaining()),
+                Some(projected) => {
+                    let verdict = match milestone.due {
+                        Some(due) if projected > due => format!(" — {} days after the target", (projected - due).num_days()).red().to_string(),
+                        Some(_) => " — on track".green().to_string(),
+                        None => String::new(),
+                    };
+                    println!("{}: {} at the recent pace ({} open){}", "Projected finish".bold(), projected, progress.remaining(), verdict);
+                }
+            }
+        }
+        
+        MilestoneCommands::Assign { name, ids } => {
+            let milestone = milestones.get(&name)?;
+            let _lock = storage.lock()?;
+            let mut assigned = Vec::new();
+            for id in &ids {
+                let mut task = storage.load_task(storage.resolve(id)?)?;
+                if task.milestone.as_ref() != Some(&milestone.name) {
+                    task.milestone = Some(milestone.name.clone());
+                    task.updated_at = chrono::Utc::now();
+                    storage.save_task(&task)?;
+                }
+                assigned.push(format!("#{}", task.id));
+            }
+            println!("{} Planned {} for {}", "✅".green(), assigned.join(", "), milestone.name.cyan());
+        }
+        
+        MilestoneCommands::Unassign { ids } => {
+            let _lock = storage.lock()?;
+            for id in &ids {
+                let mut task = storage.load_task(storage.resolve(id)?)?;
+                match task.milestone.take() {
+                    Some(name) => {
+                        task.updated_at = chrono::Utc::now();
+                        storage.save_task(&task)?;
+                        println!("{} Took task #{} out of {}", "✅".green(), task.id, name.cyan());
+                    }
+                    None => println!("{} Task #{} isn't in a milestone", "ℹ️".blue(), task.id),
+                }
+            }
+        }
+        
+        MilestoneCommands::Remove { name } => {
+            let _lock = storage.lock()?;
+            let name = milestones.get(&name)?.name;
+            let mut unassigned = 0;
+            for mut task in storage.list_all_tasks()?.into_iter().filter(|t| t.milestone.as_ref() == Some(&name)) {
+                task.milestone = None;
+                task.updated_at = chrono::Utc::now();
+                storage.save_task(&task)?;
+                unassigned += 1;
+            }
+            let mut all = milestones.load()?;
+            all.retain(|m| m.name != name);
+            milestones.save(&all)?;
+            println!("{} Removed milestone {}; {} task(s) unassigned", "✅".green(), name.cyan(), unassigned);
         }
     }
     
-    analyzer.record_suggestion(suggestion.task.id, suggestion.action.clone());
     Ok(())
 }
 
-fn parse_priority(s: &str) -> Result<Priority> {
-    match s.to_lowercase().as_str() {
-        "high" => Ok(Priority::High),
-        "medium" => Ok(Priority::Medium),
-        "low" => Ok(Priority::Low),
-        _ => anyhow::bail!("Invalid priority: {}. Use high, medium, or low", s),
+fn handle_trash_command(command: TrashCommands, storage: &dyn TaskStore) -> Result<()> {
+    let trash = trash::Trash::new(&get_trusty_dir()?);
+    
+    match command {
+        TrashCommands::List => {
+            let entries = trash.list()?;
+            if entries.is_empty() {
+                println!("{} The trash is empty.", "ℹ️".blue());
+                return Ok(());
+            }
+            
+            println!("{}", format!("🗑️  {} deleted task(s)", entries.len()).bold());
+            println!("{}", "─".repeat(50));
+            for entry in &entries {
+                println!("  #{} - {} {}", entry.task.id, entry.task.title,
+                    format!("(deleted {})", entry.deleted_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")).dimmed());
+                if !entry.referenced_by.is_empty() {
+                    let refs: Vec<String> = entry.referenced_by.iter().map(|r| format!("#{}", r.task_id)).collect();
+                    println!("      referenced by {}", refs.join(", "));
+                }
+            }
+        }
+        
+        TrashCommands::Restore { ids, all } => {
+            let entries = if all {
+                trash.list()?
+            } else {
+                ids.iter().map(|id| trash.find(id)).collect::<Result<Vec<_>>>()?
+            };
+            
+            for entry in entries {
+                let old_id = entry.task.id;
+                let title = entry.task.title.clone();
+                let (id, missing) = trash.restore(storage, entry)?;
+                
+                if id == old_id {
+                    println!("{} Restored task #{}: {}", "✅".green(), id, title);
+                } else {
+                    println!("{} Restored task #{} as #{} (ID was taken): {}", "✅".green(), old_id, id, title);
+                }
+                for reference in missing {
+                    println!("   {} Task #{} no longer exists; its reference was not restored", "⚠️".yellow(), reference.task_id);
+                }
+            }
+        }
+        
+        TrashCommands::Purge { ids, all } => {
+            let entries = if all {
+                trash.list()?
+            } else {
+                ids.iter().map(|id| trash.find(id)).collect::<Result<Vec<_>>>()?
+            };
+            
+            for entry in &entries {
+                trash.purge(entry)?;
+            }
+            println!("{} Permanently deleted {} task(s)", "✅".green(), entries.len());
+        }
     }
+    
+    Ok(())
 }
 
-fn parse_status(s: &str) -> Result<TaskStatus> {
-    match s.to_lowercase().as_str() {
-        "pending" => Ok(TaskStatus::Pending),
-        "in-progress" => Ok(TaskStatus::InProgress),
-        "done" => Ok(TaskStatus::Done),
-        "blocked" => Ok(TaskStatus::Blocked),
-        "deferred" => Ok(TaskStatus::Deferred),
-        "cancelled" => Ok(TaskStatus::Cancelled),
-        _ => anyhow::bail!("Invalid status: {}. Use pending, in-progress, done, blocked, deferred, or cancelled", s),
+fn add_agent(scope: Option<String>, global: bool, name: String, model: String, color: String) -> Result<()> {
+    let is_global = global || scope.as_deref() == Some("global");
+    let agent_config = agent::AgentConfig::new(name, model, color, is_global);
+    
+    match agent::install_agent(&agent_config) {
+        Ok(path) => {
+            println!("{} Successfully installed agent '{}' to:", "✅".green(), agent_config.name);
+            println!("   {}", path.display());
+            println!("\n{} To use this agent in Claude:", "💡".yellow());
+            println!("   1. Start a new Claude conversation");
+            println!("   2. Type: {} {}", "@".cyan(), agent_config.name.cyan());
+            println!("   3. Ask for help managing your project!");
+        }
+        Err(e) => {
+            eprintln!("{} Failed to install agent: {}", "❌".red(), e);
+            return Err(e);
+        }
+    }
+    
+    Ok(())
+}
+
+fn get_storage() -> Result<Box<dyn TaskStore>> {
+    let trusty_dir = get_trusty_dir()?;
+    let config = ProjectConfig::load(&trusty_dir)?;
+    storage::open_store(&trusty_dir, &config)
+}
+
+fn get_trusty_dir() -> Result<PathBuf> {
+    project::find_trusty_dir(project_arg())
+}
+
+/// The directory holding `.trusty`, which `defer --until file:PATH` paths are relative to
+fn project_root() -> Result<PathBuf> {
+    let trusty_dir = get_trusty_dir()?;
+    Ok(trusty_dir.parent().map(Path::to_path_buf).unwrap_or(trusty_dir))
+}
+
+fn project_arg() -> Option<&'static Path> {
+    PROJECT.get().and_then(|project| project.as_deref())
+}
+
+/// Where the configured backend keeps its task data
+fn get_tasks_location() -> Result<PathBuf> {
+    let trusty_dir = get_trusty_dir()?;
+    let config = ProjectConfig::load(&trusty_dir)?;
+    Ok(config.storage.location(&trusty_dir))
+}
+
+fn apply_prune_action(
+    storage: &dyn TaskStore,
+    workflow: &workflow::Workflow,
+    analyzer: &mut prune::PruneAnalyzer,
+    suggestion: &prune::PruneSuggestion,
+    force: bool,
+) -> Result<()> {
+    let _lock = storage.lock()?;
+    let _actor = history::act_as("prune");
+    let id = suggestion.task.id;
+    let status = match suggestion.action {
+        prune::PruneAction::Complete => TaskStatus::Done,
+        prune::PruneAction::Cancel => TaskStatus::Cancelled,
+        prune::PruneAction::Skip => {
+            println!("{} Skipped task #{}", "→".blue(), id);
+            analyzer.record_suggestion(id, suggestion.action.clone());
+            return Ok(());
+        }
+    };
+    
+    // The prune reason doubles as the resolution for workflows that ask for one
+    match workflow::set_status(storage, workflow, &[id], &status, Some(&suggestion.reason), force) {
+        Ok(_) if status == TaskStatus::Done => {
+            println!("{} Completed task #{}: {}", "✅".green(), id, suggestion.task.title);
+            analyzer.record_suggestion(id, suggestion.action.clone());
+        }
+        Ok(_) => {
+            println!("{} Cancelled task #{}: {}", "❌".red(), id, suggestion.task.title);
+            analyzer.record_suggestion(id, suggestion.action.clone());
+        }
+        Err(e) => {
+            println!("{} Left task #{} alone: {}", "⚠️".yellow(), id, e);
+            analyzer.record_suggestion(id, prune::PruneAction::Skip);
+        }
     }
+    Ok(())
+}
+
+fn parse_priority(s: &str) -> Result<Priority> {
+    s.parse()
 }
 
 fn parse_complexity(s: &str) -> Result<crate::task::Complexity> {
-    match s.to_lowercase().as_str() {
-        "simple" => Ok(crate::task::Complexity::Simple),
-        "medium" => Ok(crate::task::Complexity::Medium),
-        "complex" => Ok(crate::task::Complexity::Complex),
-        _ => anyhow::bail!("Invalid complexity: {}. Use simple, medium, or complex", s),
+    s.parse()
+}
+
+/// Check or uncheck checklist items, all or none of them
+/// The task named on the command line, or every task matching a `--where` query
+fn selected_tasks(storage: &dyn TaskStore, config: &ProjectConfig, id: Option<TaskRef>, filter: Option<&str>) -> Result<Vec<u32>> {
+    match (id, filter) {
+        (Some(id), _) => Ok(vec![storage.resolve(&id)?]),
+        (None, Some(filter)) => {
+            let query = query::Query::parse(filter, config, dates::today())?;
+            let graph = storage.load_graph()?.with_checklists(config.checklists_complete_tasks);
+            Ok(graph.tasks().iter().filter(|t| query.matches(t, &graph)).map(|t| t.id).collect())
+        }
+        (None, None) => anyhow::bail!("Name a task or use --where"),
     }
 }
 
-fn display_task_details(task: &Task, all_tasks: Option<&[Task]>) {
+fn set_checklist_items(storage: &dyn TaskStore, id: &TaskRef, items: &[usize], checked: bool) -> Result<()> {
+    let id = storage.resolve(id)?;
+    let _lock = storage.lock()?;
+    let mut task = storage.load_task(id)?;
+    
+    for &number in items {
+        task.description = checklist::set_checked(&task.description, number, checked)
+            .map_err(|e| anyhow::anyhow!("Task #{}: {}", id, e))?;
+    }
+    task.updated_at = chrono::Utc::now();
+    storage.save_task(&task)?;
+    
+    let (done, total) = task.checklist_progress().unwrap_or_default();
+    println!("{} Task #{}: {}/{} checklist items checked", "✅".green(), id, done, total);
+    if done == total {
+        println!("{} Every item is checked. Mark the task done with: trusty complete {}", "💡".yellow(), id);
+    }
+    Ok(())
+}
+
+fn display_task_details(task: &Task, graph: Option<&TaskGraph>, config: &ProjectConfig) {
     println!("\n{}", format!("Task #{}", task.id).cyan().bold());
     println!("{}", "─".repeat(50));
     println!("{}: {}", "Title".bold(), task.title);
+    if let Some(uid) = &task.uid {
+        println!("{}: {}", "Uid".bold(), uid);
+    }
     // Show effective status if different from stored status
-    if let Some(tasks) = all_tasks {
-        let effective_status = task.compute_effective_status(tasks);
+    if let Some(graph) = graph {
+        let effective_status = graph.effective_status(task);
         if !task.subtasks.is_empty() {
-            let (completed, total) = task.subtask_progress(tasks);
+            let (completed, total) = graph.progress(task);
             if effective_status != task.status {
                 println!("{}: {} (effective: {})", "Status".bold(), task.status, effective_status);
             } else {
                 println!("{}: {}", "Status".bold(), task.status);
             }
             println!("{}: {}/{} subtasks complete", "Progress".bold(), completed, total);
+        } else if effective_status != task.status {
+            println!("{}: {} (effective: {})", "Status".bold(), task.status, effective_status);
         } else {
             println!("{}: {}", "Status".bold(), task.status);
         }
     } else {
         println!("{}: {}", "Status".bold(), task.status);
     }
+    if let Some(resolution) = &task.resolution {
+        println!("{}: {}", "Resolution".bold(), resolution);
+    }
     println!("{}: {}", "Priority".bold(), task.priority);
+    if let Some(milestone) = &task.milestone {
+        println!("{}: {}", "Milestone".bold(), milestone);
+    }
+    
+    let today = dates::today();
+    if let Some(due) = task.due {
+        let line = format!("{} ({})", due, dates::describe(due, today));
+        if task.is_overdue(today) {
+            println!("{}: {} {}", "Due".bold(), line.red(), "overdue".red().bold());
+        } else {
+            println!("{}: {}", "Due".bold(), line);
+        }
+    }
+    if let Some(scheduled) = task.scheduled {
+        println!("{}: {} ({})", "Scheduled".bold(), scheduled, dates::describe(scheduled, today));
+    }
+    if let Some(deferred_until) = task.deferred_until {
+        println!("{}: {} ({})", "Deferred until".bold(), deferred_until, dates::describe(deferred_until, today));
+    }
+    if let Some(wake_when) = &task.wake_when {
+        println!("{}: {}", "Deferred until".bold(), wake_when.describe());
+    }
+    if let Some(recurrence) = &task.recurrence {
+        println!("{}: {}", "Repeats".bold(), recurrence.describe());
+    }
+    if let Some(series) = task.series {
+        println!("{}: occurrence of #{}", "Series".bold(), series);
+    }
     
     if let Some(complexity) = &task.complexity {
         println!("{}: {}", "Complexity".bold(), complexity);
     }
     
+    let estimated = graph.map(|g| g.estimate_total(task)).unwrap_or_default();
+    match &task.estimate {
+        Some(estimate) if task.subtasks.is_empty() => println!("{}: {}", "Estimate".bold(), estimate),
+        Some(estimate) => println!("{}: {} ({} with subtasks)", "Estimate".bold(), estimate, estimated),
+        None if !estimated.is_empty() => println!("{}: {} from subtasks", "Estimate".bold(), estimated),
+        None => {}
+    }
+    
     if !task.dependencies.is_empty() {
         println!("{}: {:?}", "Dependencies".bold(), task.dependencies.iter().collect::<Vec<_>>());
     }
@@ -928,10 +1944,37 @@ fn display_task_details(task: &Task, all_tasks: Option<&[Task]>) {
         println!("{}: {} subtask(s) - IDs: {:?}", "Subtasks".bold(), task.subtasks.len(), task.subtasks);
     }
     
+    let links = graph.map(|graph| graph.relations(task)).unwrap_or_default();
+    if !links.is_empty() {
+        println!("{}:", "Related".bold());
+        for (name, id) in links {
+            let title = graph.and_then(|graph| graph.get(id)).map_or("(not found)", |t| t.title.as_str());
+            println!("  {} #{} - {}", name, id, title);
+        }
+    }
+    
     if !task.tags.is_empty() {
         println!("{}: {}", "Tags".bold(), task.tags.join(", "));
     }
     
+    let spent = graph.map(|g| g.time_spent(task)).unwrap_or_else(|| task.logged_minutes());
+    if spent > task.logged_minutes() {
+        println!("{}: {} ({} on this task, the rest on subtasks)", "Time logged".bold(),
+            timelog::format_minutes(spent), timelog::format_minutes(task.logged_minutes()));
+    } else if spent > 0 {
+        println!("{}: {}", "Time logged".bold(), timelog::format_minutes(spent));
+    }
+    
+    // Schema-defined fields first, in config order, then any other keys
+    for (name, def) in &config.custom_fields {
+        if let Some(value) = task.extra.get(name) {
+            println!("{}: {} ({})", name.bold(), crate::fields::format_value(value), def.kind);
+        }
+    }
+    for (name, value) in task.extra.iter().filter(|(name, _)| !config.custom_fields.contains_key(*name)) {
+        println!("{}: {}", name.bold(), crate::fields::format_value(value));
+    }
+    
     println!("{}: {}", "Created".bold(), task.created_at.format("%Y-%m-%d %H:%M:%S"));
     println!("{}: {}", "Updated".bold(), task.updated_at.format("%Y-%m-%d %H:%M:%S"));
     
@@ -943,9 +1986,29 @@ fn display_task_details(task: &Task, all_tasks: Option<&[Task]>) {
         println!("\n{}", "Description:".bold());
         println!("{}", task.description);
     }
+    
+    let checklist = task.checklist();
+    if let Some((checked, total)) = task.checklist_progress() {
+        println!("\n{} {}/{} checked", "Checklist:".bold(), checked, total);
+        for (number, item) in checklist.iter().enumerate() {
+            let mark = if item.checked { "☑".green() } else { "☐".normal() };
+            println!("  {:>2}. {} {}", number + 1, mark, item.text);
+        }
+    }
+    
+    if !task.notes.is_empty() {
+        println!("\n{}", "Notes:".bold());
+        for note in &task.notes {
+            let when = note.at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+            println!("  {}  {}", when, note.author.cyan());
+            for line in note.text.lines() {
+                println!("    {}", line);
+            }
+        }
+    }
 }
 
-fn run_demo(_storage: TaskStorage, skip_confirm: bool, delay_ms: u64, keep: bool) -> Result<()> {
+fn run_demo(skip_confirm: bool, delay_ms: u64, keep: bool) -> Result<()> {
     let delay = Duration::from_millis(delay_ms);
     
     // Welcome message
@@ -996,8 +2059,10 @@ fn run_demo(_storage: TaskStorage, skip_confirm: bool, delay_ms: u64, keep: bool
         println!("\n{} trusty {}", "Running:".bright_green(), args.join(" ").cyan());
         thread::sleep(delay);
         
+        // The demo project must not be redirected to the user's own
         let output = Command::new(&trusty_exe)
             .args(args)
+            .env_remove(project::TRUSTY_DIR_ENV)
             .output()?;
         
         let stdout = String::from_utf8_lossy(&output.stdout);
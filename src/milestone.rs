@@ -0,0 +1,177 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::graph::TaskGraph;
+use crate::storage::atomic_write;
+use crate::task::{Task, TaskStatus};
+
+const MILESTONES_FILE: &str = "milestones";
+
+/// How far back finished tasks count towards a milestone's pace
+const PACE_WINDOW_DAYS: i64 = 28;
+
+/// A release or epic that tasks are assigned to by name
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Milestone {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// The date the milestone should be finished by
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Milestone {
+    pub fn new(name: &str) -> Result<Self> {
+        let name = name.trim();
+        if name.is_empty() || name.eq_ignore_ascii_case("none") {
+            anyhow::bail!("Invalid milestone name '{}'", name);
+        }
+        Ok(Self { name: name.to_string(), description: String::new(), due: None, created_at: Utc::now() })
+    }
+}
+
+/// The project's milestones, kept in `.trusty/milestones` (YAML) next to the config
+pub struct Milestones {
+    path: PathBuf,
+}
+
+impl Milestones {
+    pub fn new(trusty_dir: &Path) -> Self {
+        Self { path: trusty_dir.join(MILESTONES_FILE) }
+    }
+
+    pub fn load(&self) -> Result<Vec<Milestone>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read milestones: {:?}", self.path))?;
+        serde_yaml::from_str(&content).with_context(|| format!("Failed to parse milestones: {:?}", self.path))
+    }
+
+    pub fn save(&self, milestones: &[Milestone]) -> Result<()> {
+        atomic_write(&self.path, serde_yaml::to_string(milestones)?.as_bytes())
+            .with_context(|| format!("Failed to write milestones: {:?}", self.path))
+    }
+
+    /// The milestone called `name`
+    pub fn get(&self, name: &str) -> Result<Milestone> {
+        self.load()?.into_iter().find(|m| m.name == name.trim()).ok_or_else(|| {
+            anyhow::anyhow!("No milestone '{}'. Create it with `trusty milestone add {}`", name.trim(), name.trim())
+        })
+    }
+}
+
+/// An open task in a milestone that can't go ahead yet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blocker {
+    pub task: u32,
+    /// Dependencies that aren't done, which may be outside the milestone
+    pub waiting_on: Vec<u32>,
+}
+
+/// Where a milestone stands, from the tasks assigned to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub total: usize,
+    pub done: usize,
+    pub cancelled: usize,
+    pub blockers: Vec<Blocker>,
+    /// When the open tasks should be done at the recent pace, if there is one
+    pub projected: Option<NaiveDate>,
+}
+
+impl Progress {
+    pub fn remaining(&self) -> usize {
+        self.total - self.done - self.cancelled
+    }
+
+    /// Share of the tasks that still count (cancelled ones don't) that are done
+    pub fn percent(&self) -> f32 {
+        match self.total - self.cancelled {
+            0 => 0.0,
+            counted => self.done as f32 / counted as f32 * 100.0,
+        }
+    }
+}
+
+/// The tasks assigned to `milestone`
+pub fn tasks_in<'a>(graph: &'a TaskGraph, milestone: &str) -> Vec<&'a Task> {
+    graph.tasks().iter().filter(|t| t.milestone.as_deref() == Some(milestone)).collect()
+}
+
+/// Progress of `milestone`, counting effective statuses so parents follow their subtasks
+pub fn progress(graph: &TaskGraph, milestone: &Milestone, today: NaiveDate) -> Progress {
+    let tasks = tasks_in(graph, &milestone.name);
+    let status: Vec<TaskStatus> = tasks.iter().map(|t| graph.effective_status(t)).collect();
+    let done = status.iter().filter(|s| **s == TaskStatus::Done).count();
+    let cancelled = status.iter().filter(|s| **s == TaskStatus::Cancelled).count();
+
+    let mut blockers = Vec::new();
+    for (task, status) in tasks.iter().zip(&status) {
+        if status.is_closed() {
+            continue;
+        }
+        let mut waiting_on: Vec<u32> = task.dependencies.iter().copied()
+            .filter(|&dep| graph.get(dep).is_none_or(|t| graph.effective_status(t) != TaskStatus::Done))
+            .collect();
+        waiting_on.sort_unstable();
+        if !waiting_on.is_empty() || *status == TaskStatus::Blocked {
+            blockers.push(Blocker { task: task.id, waiting_on });
+        }
+    }
+
+    let finished: Vec<NaiveDate> = tasks.iter().zip(&status)
+        .filter(|(_, status)| **status == TaskStatus::Done)
+        .filter_map(|(t, _)| t.completed_at.map(|at| at.date_naive()))
+        .collect();
+    let remaining = tasks.len() - done - cancelled;
+    let projected = project_finish(&finished, remaining, milestone.created_at.date_naive(), today);
+
+    Progress { total: tasks.len(), done, cancelled, blockers, projected }
+}
+
+/// The date `remaining` tasks would be done by, going at the pace tasks were
+/// `finished` over the last four weeks (or since `start`, if later). `None`
+/// when nothing is left or nothing was finished in that time.
+pub fn project_finish(finished: &[NaiveDate], remaining: usize, start: NaiveDate, today: NaiveDate) -> Option<NaiveDate> {
+    if remaining == 0 {
+        return None;
+    }
+    let since = start.max(today - Duration::days(PACE_WINDOW_DAYS));
+    let recent = finished.iter().filter(|&&date| date >= since && date <= today).count();
+    if recent == 0 {
+        return None;
+    }
+    let days = (today - since).num_days().max(1) as f64;
+    let per_day = recent as f64 / days;
+    Some(today + Duration::days((remaining as f64 / per_day).ceil() as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_project_finish_at_recent_pace() {
+        let today = date("2025-03-29");
+        let finished = [date("2025-03-10"), date("2025-03-20"), date("2025-03-27"), date("2025-01-05")];
+
+        // Three in the last four weeks, so six more take eight weeks
+        assert_eq!(project_finish(&finished, 6, date("2025-01-01"), today), Some(date("2025-05-24")));
+        // A milestone started two weeks ago went at one a week
+        assert_eq!(project_finish(&finished, 6, date("2025-03-15"), today), Some(date("2025-05-10")));
+
+        assert_eq!(project_finish(&finished, 0, date("2025-01-01"), today), None);
+        assert_eq!(project_finish(&finished[3..], 6, date("2025-01-01"), today), None);
+    }
+}
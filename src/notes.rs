@@ -0,0 +1,135 @@
+use chrono::{DateTime, SecondsFormat, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::history;
+
+/// Heading of the section task files keep notes under, after the description
+const NOTES_HEADING: &str = "## Notes";
+
+/// A timestamped comment on a task. Notes are only ever appended, so they
+/// outlive edits of the description.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Note {
+    pub at: DateTime<Utc>,
+    pub author: String,
+    pub text: String,
+}
+
+impl Note {
+    /// A note by the current actor, timestamped now
+    pub fn new(text: &str) -> Self {
+        let now = Utc::now();
+        // Whole seconds, as written to the task file
+        let at = now.with_nanosecond(0).unwrap_or(now);
+        Self { at, author: history::current_actor(), text: text.to_string() }
+    }
+
+    fn header(&self) -> String {
+        format!("### {} — {}", self.at.to_rfc3339_opts(SecondsFormat::Secs, true), self.author)
+    }
+}
+
+/// Parse a `### <timestamp> — <author>` line
+fn parse_header(line: &str) -> Option<(DateTime<Utc>, String)> {
+    let (at, author) = line.strip_prefix("### ")?.split_once(" — ")?;
+    let at = DateTime::parse_from_rfc3339(at.trim()).ok()?.with_timezone(&Utc);
+    Some((at, author.trim().to_string()))
+}
+
+/// The description followed by the notes section, as written to a task file
+pub fn render_body(description: &str, notes: &[Note]) -> String {
+    if notes.is_empty() {
+        return description.to_string();
+    }
+
+    // The blank line before the heading is ours, so the description is kept exactly as written
+    let mut body = format!("{}\n\n", description);
+    body.push_str(NOTES_HEADING);
+    body.push('\n');
+    for note in notes {
+        body.push_str(&format!("\n{}\n\n{}\n", note.header(), note.text));
+    }
+    body
+}
+
+/// Split a task file body into the description and the notes under it.
+///
+/// Only a `## Notes` heading followed by a note header starts the section,
+/// so a description with a notes heading of its own is left alone.
+pub fn split_body(body: &str) -> (String, Vec<Note>) {
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        let rest = &body[offset + line.len()..];
+        if line.trim_end() == NOTES_HEADING && rest.lines().find(|l| !l.trim().is_empty()).and_then(parse_header).is_some() {
+            let description = &body[..offset];
+            return (description.strip_suffix("\n\n").unwrap_or(description).to_string(), parse_notes(rest));
+        }
+        offset += line.len();
+    }
+
+    (body.to_string(), Vec::new())
+}
+
+fn parse_notes(section: &str) -> Vec<Note> {
+    // Each note is its header line followed by the text up to the next header
+    let mut headers = Vec::new();
+    let mut offset = 0;
+    for line in section.split_inclusive('\n') {
+        if let Some((at, author)) = parse_header(line.trim_end_matches('\n')) {
+            headers.push((at, author, offset, offset + line.len()));
+        }
+        offset += line.len();
+    }
+
+    let count = headers.len();
+    let ends: Vec<usize> = headers.iter().skip(1).map(|&(_, _, start, _)| start).chain([section.len()]).collect();
+    headers.into_iter().zip(ends).enumerate()
+        .map(|(i, ((at, author, _, text_start), text_end))| {
+            // Strip only the blank line after the header and the line breaks that end the note
+            let raw = &section[text_start..text_end];
+            let raw = raw.strip_prefix('\n').unwrap_or(raw);
+            let raw = if i + 1 < count { raw.strip_suffix('\n').unwrap_or(raw) } else { raw };
+            let text = raw.strip_suffix('\n').unwrap_or(raw).to_string();
+            Note { at, author, text }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(at: &str, author: &str, text: &str) -> Note {
+        Note { at: at.parse().unwrap(), author: author.to_string(), text: text.to_string() }
+    }
+
+    #[test]
+    fn test_notes_round_trip_through_the_body() {
+        let notes = vec![
+            note("2025-03-01T09:30:00Z", "alice", "Tried the v2 endpoint, it times out."),
+            note("2025-03-02T16:05:12Z", "agent-7", "Root cause is the pool size.\n\n### Not a header\n- bump it to 20"),
+        ];
+        let body = render_body("Fix the flaky import.", &notes);
+        assert!(body.starts_with("Fix the flaky import.\n\n## Notes\n\n### 2025-03-01T09:30:00Z — alice\n"), "{}", body);
+
+        let (description, parsed) = split_body(&body);
+        assert_eq!(description, "Fix the flaky import.");
+        assert_eq!(parsed, notes);
+
+        // Line breaks around the description and the notes are part of them
+        let notes = vec![note("2025-03-01T09:30:00Z", "alice", "\n  indented\n\n"), note("2025-03-02T16:05:12Z", "bob", "")];
+        for description in ["Trailing newline\n", "Trailing blank lines\n\n\n", "Windows\r\n", "\n"] {
+            assert_eq!(split_body(&render_body(description, &notes)), (description.to_string(), notes.clone()));
+        }
+
+        let (description, parsed) = split_body(&render_body("", &notes[..1]));
+        assert_eq!((description.as_str(), parsed.len()), ("", 1));
+    }
+
+    #[test]
+    fn test_notes_heading_in_description_is_not_a_section() {
+        let body = "Plan\n\n## Notes\n\nJust a heading the author wrote.\n";
+        assert_eq!(split_body(body), (body.to_string(), Vec::new()));
+        assert_eq!(render_body(body, &[]), body);
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Name of the directory holding a project's tasks and settings
+pub const TRUSTY_DIR_NAME: &str = ".trusty";
+
+/// Environment variable pointing at a `.trusty` directory to use instead of discovery
+pub const TRUSTY_DIR_ENV: &str = "TRUSTY_DIR";
+
+/// Find the `.trusty` directory a command should use.
+///
+/// `--project` wins, then `TRUSTY_DIR`, then the nearest `.trusty` in the
+/// working directory or one of its parents, the way git finds `.git`. It is
+/// an error for the directory not to exist, so that running trusty outside a
+/// project never creates a new one by accident.
+pub fn find_trusty_dir(project: Option<&Path>) -> Result<PathBuf> {
+    let trusty_dir = match explicit_trusty_dir(project) {
+        Some(dir) => dir,
+        None => {
+            let current_dir = env::current_dir()?;
+            discover(&current_dir).ok_or_else(|| anyhow::anyhow!(
+                "No trusty project found in {:?} or any parent directory. Run `trusty init` to create one",
+                current_dir
+            ))?
+        }
+    };
+
+    if !trusty_dir.is_dir() {
+        anyhow::bail!("No trusty project at {:?}. Run `trusty init` there to create one", trusty_dir);
+    }
+
+    Ok(trusty_dir)
+}
+
+/// Where `trusty init` creates a project: the explicit location if one was
+/// given, otherwise the working directory
+pub fn init_trusty_dir(project: Option<&Path>) -> Result<PathBuf> {
+    match explicit_trusty_dir(project) {
+        Some(dir) => Ok(dir),
+        None => Ok(env::current_dir()?.join(TRUSTY_DIR_NAME)),
+    }
+}
+
+/// The nearest `.trusty` directory in `start` or one of its ancestors
+pub fn discover(start: &Path) -> Option<PathBuf> {
+    start.ancestors()
+        .map(|dir| dir.join(TRUSTY_DIR_NAME))
+        .find(|candidate| candidate.is_dir())
+}
+
+/// `--project` names the project root (or its `.trusty` directory), while
+/// `TRUSTY_DIR` names the `.trusty` directory itself
+fn explicit_trusty_dir(project: Option<&Path>) -> Option<PathBuf> {
+    if let Some(project) = project {
+        return Some(if project.ends_with(TRUSTY_DIR_NAME) {
+            project.to_path_buf()
+        } else {
+            project.join(TRUSTY_DIR_NAME)
+        });
+    }
+
+    env::var_os(TRUSTY_DIR_ENV)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
@@ -1,3 +1,5 @@
+use crate::graph::TaskGraph;
+use crate::relation::RelationKind;
 use crate::task::{Task, TaskStatus};
 use chrono::{DateTime, Utc, Duration};
 use serde::{Deserialize, Serialize};
@@ -60,7 +62,7 @@ impl PruneAnalyzer {
     
     pub fn save_history(&self) -> anyhow::Result<()> {
         let content = serde_json::to_string_pretty(&self.history)?;
-        std::fs::write(&self.history_path, content)?;
+        crate::storage::atomic_write(&self.history_path, content.as_bytes())?;
         Ok(())
     }
     
@@ -70,7 +72,7 @@ impl PruneAnalyzer {
         
         // Calculate age percentiles
         let mut task_ages: Vec<i64> = tasks.iter()
-            .filter(|t| matches!(t.status, TaskStatus::Pending | TaskStatus::InProgress))
+            .filter(|t| t.status == TaskStatus::Pending || t.status.is_active())
             .map(|t| (now - t.created_at).num_days())
             .collect();
         task_ages.sort();
@@ -82,7 +84,9 @@ impl PruneAnalyzer {
             30 // Default to 30 days if no tasks
         };
         
-        for task in tasks {
+        let graph = TaskGraph::new(tasks.to_vec());
+        
+        for task in graph.tasks() {
             // Skip if in backoff period
             if let Some(history) = self.history.suggestions.get(&task.id) {
                 if now < history.next_suggestion_after {
@@ -95,7 +99,7 @@ impl PruneAnalyzer {
                 continue;
             }
             
-            if let Some(suggestion) = self.analyze_single_task(task, tasks, p80_age, now) {
+            if let Some(suggestion) = self.analyze_single_task(task, &graph, p80_age, now) {
                 suggestions.push(suggestion);
             }
         }
@@ -105,13 +109,31 @@ impl PruneAnalyzer {
         suggestions
     }
     
-    fn analyze_single_task(&self, task: &Task, all_tasks: &[Task], p80_age: i64, now: DateTime<Utc>) -> Option<PruneSuggestion> {
+    fn analyze_single_task(&self, task: &Task, graph: &TaskGraph, p80_age: i64, now: DateTime<Utc>) -> Option<PruneSuggestion> {
         let age_days = (now - task.created_at).num_days();
-        let last_update_days = (now - task.updated_at).num_days();
+        let days_in_status = (now - task.status_since()).num_days();
+        
+        // A duplicate or superseded task is covered by the other one
+        let covering = task.relations.iter()
+            .filter(|r| r.kind == RelationKind::Duplicates)
+            .filter_map(|r| graph.get(r.task))
+            .map(|original| format!("Duplicate of #{} - {}", original.id, original.title))
+            .chain(graph.inverse_relations(task.id).into_iter()
+                .filter(|(kind, newer)| *kind == RelationKind::Supersedes && newer.status != TaskStatus::Cancelled)
+                .map(|(_, newer)| format!("Superseded by #{} - {}", newer.id, newer.title)))
+            .next();
+        if let Some(reason) = covering {
+            return Some(PruneSuggestion {
+                task: task.clone(),
+                action: PruneAction::Cancel,
+                reason,
+                confidence: 0.95,
+            });
+        }
         
         // Check if all subtasks are complete
         if !task.subtasks.is_empty() {
-            let (completed, total) = task.subtask_progress(all_tasks);
+            let (completed, total) = graph.progress(task);
             if completed == total && total > 0 {
                 return Some(PruneSuggestion {
                     task: task.clone(),
@@ -124,11 +146,9 @@ impl PruneAnalyzer {
         
         // Check if blocked by completed or missing dependencies
         if !task.dependencies.is_empty() {
-            let deps_status: Vec<_> = task.dependencies.iter().map(|&dep_id| {
-                all_tasks.iter()
-                    .find(|t| t.id == dep_id)
-                    .map(|t| t.status.clone())
-            }).collect();
+            let deps_status: Vec<_> = task.dependencies.iter()
+                .map(|&dep_id| graph.get(dep_id).map(|t| t.status.clone()))
+                .collect();
             
             let all_deps_resolved = deps_status.iter().all(|status| {
                 matches!(status, Some(TaskStatus::Done))
@@ -145,11 +165,11 @@ impl PruneAnalyzer {
                 });
             }
             
-            if all_deps_resolved && last_update_days > 3 {
+            if all_deps_resolved && days_in_status > 3 {
                 return Some(PruneSuggestion {
                     task: task.clone(),
                     action: PruneAction::Cancel,
-                    reason: format!("All dependencies completed but task untouched for {} days", last_update_days),
+                    reason: format!("All dependencies completed but task hasn't changed status in {} days", days_in_status),
                     confidence: 0.7,
                 });
             }
@@ -163,20 +183,19 @@ impl PruneAnalyzer {
                 crate::task::Priority::High => 0.4,
             };
             
-            // If it hasn't been updated in a long time, it's probably stale
-            if last_update_days > 30 {
+            // If it hasn't moved in a long time, it's probably stale
+            if days_in_status > 30 {
                 return Some(PruneSuggestion {
                     task: task.clone(),
                     action: PruneAction::Cancel,
-                    reason: format!("Task is {} days old (P80+) with no updates in {} days", age_days, last_update_days),
+                    reason: format!("Task is {} days old (P80+) and hasn't changed status in {} days", age_days, days_in_status),
                     confidence: confidence + 0.1,
                 });
             }
         }
         
         // Check if this is an orphaned subtask (parent is complete)
-        let parent_task = all_tasks.iter().find(|t| t.subtasks.contains(&task.id));
-        if let Some(parent) = parent_task {
+        if let Some(parent) = graph.parent(task.id) {
             if parent.status == TaskStatus::Done {
                 return Some(PruneSuggestion {
                     task: task.clone(),
@@ -228,7 +247,7 @@ impl PruneAnalyzer {
         
         // If action was Skip, increase backoff more aggressively
         if action_taken == PruneAction::Skip {
-            entry.next_suggestion_after = entry.next_suggestion_after + Duration::days(backoff_days / 2);
+            entry.next_suggestion_after += Duration::days(backoff_days / 2);
         }
     }
 }
@@ -261,6 +280,23 @@ mod tests {
         assert!(suggestions[0].confidence > 0.8);
     }
     
+    #[test]
+    fn test_prune_duplicates_and_superseded_tasks() {
+        let analyzer = PruneAnalyzer::new(std::env::temp_dir()).unwrap();
+        
+        let original = Task::new(1, "Login page".to_string(), "Details".to_string(), Priority::High);
+        let mut duplicate = Task::new(2, "Build login".to_string(), "Details".to_string(), Priority::High);
+        duplicate.add_relation(RelationKind::Duplicates, 1);
+        let old = Task::new(3, "Use v1 API".to_string(), "Details".to_string(), Priority::High);
+        let mut new = Task::new(4, "Use v2 API".to_string(), "Details".to_string(), Priority::High);
+        new.add_relation(RelationKind::Supersedes, 3);
+        
+        let suggestions = analyzer.analyze_tasks(&[original, duplicate, old, new]);
+        let reasons: Vec<(u32, &str)> = suggestions.iter().map(|s| (s.task.id, s.reason.as_str())).collect();
+        assert_eq!(reasons, vec![(2, "Duplicate of #1 - Login page"), (3, "Superseded by #4 - Use v2 API")]);
+        assert!(suggestions.iter().all(|s| s.action == PruneAction::Cancel));
+    }
+    
     #[test]
     fn test_prune_old_task_no_description() {
         let analyzer = PruneAnalyzer::new(std::env::temp_dir()).unwrap();
@@ -276,6 +312,27 @@ mod tests {
         assert_eq!(suggestions[0].action, PruneAction::Cancel);
     }
     
+    #[test]
+    fn test_prune_uses_time_in_status() {
+        let analyzer = PruneAnalyzer::new(std::env::temp_dir()).unwrap();
+        
+        let mut task = Task::new(1, "Stuck task".to_string(), "Details".to_string(), Priority::Low);
+        task.created_at = Utc::now() - Duration::days(60);
+        task.history.push(crate::history::HistoryEvent {
+            at: Utc::now() - Duration::days(45),
+            actor: "human".to_string(),
+            change: crate::history::HistoryChange::Status { from: TaskStatus::Pending, to: TaskStatus::Blocked },
+        });
+        task.status = TaskStatus::Blocked;
+        
+        // A recent edit doesn't hide that the task hasn't moved in 45 days
+        task.updated_at = Utc::now();
+        
+        let suggestions = analyzer.analyze_tasks(&[task]);
+        assert_eq!(suggestions.len(), 1);
+        assert!(suggestions[0].reason.contains("hasn't changed status in 45 days"));
+    }
+    
     #[test]
     fn test_exponential_backoff() {
         let mut analyzer = PruneAnalyzer::new(std::env::temp_dir()).unwrap();
@@ -0,0 +1,646 @@
+use anyhow::Result;
+use chrono::{DateTime, Days, Local, Months, NaiveDate, Utc};
+use std::fmt;
+
+use crate::config::ProjectConfig;
+use crate::dates;
+use crate::fields::FieldFilter;
+use crate::graph::TaskGraph;
+use crate::task::{Complexity, Priority, Task, TaskStatus};
+
+const KEYS: &[&str] = &[
+    "status", "priority", "complexity", "tag", "id", "parent", "milestone", "has", "is",
+    "due", "scheduled", "deferred", "created", "updated", "completed",
+];
+
+/// A task filter such as `status:pending,blocked priority:high -tag:spike due<7d "oauth"`.
+///
+/// Terms separated by spaces must all match; `OR` between terms and
+/// parentheses group them, and a leading `-` negates a term or group.
+/// Anything that isn't a `key:value` term is searched for in the title and
+/// description.
+#[derive(Debug, Clone)]
+pub struct Query {
+    expr: Expr,
+    today: NaiveDate,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    All(Vec<Expr>),
+    Any(Vec<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    /// Any of these, compared with the effective status
+    Status(Vec<TaskStatus>),
+    Priority(Op, Vec<Priority>),
+    Complexity(Vec<Complexity>),
+    Tag(Vec<String>),
+    Id(Vec<u32>),
+    Parent(Vec<u32>),
+    Milestone(Vec<String>),
+    Has(Has),
+    Is(Is),
+    /// Compared with the period from the first to the second date
+    Date(DateField, Op, NaiveDate, NaiveDate),
+    /// Lowercased text to find in the title or description
+    Text(String),
+    Field(FieldFilter),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Has {
+    Deps,
+    Subtasks,
+    Parent,
+    Due,
+    Estimate,
+    Notes,
+    Checklist,
+    Milestone,
+    Relations,
+    Tags,
+    Time,
+}
+
+const HAS: &[(&str, Has)] = &[
+    ("deps", Has::Deps),
+    ("dependencies", Has::Deps),
+    ("subtasks", Has::Subtasks),
+    ("parent", Has::Parent),
+    ("due", Has::Due),
+    ("estimate", Has::Estimate),
+    ("notes", Has::Notes),
+    ("checklist", Has::Checklist),
+    ("milestone", Has::Milestone),
+    ("relations", Has::Relations),
+    ("tags", Has::Tags),
+    ("time", Has::Time),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Is {
+    Ready,
+    Blocked,
+    Overdue,
+    Waiting,
+    Open,
+    Closed,
+    Recurring,
+    TopLevel,
+}
+
+const IS: &[(&str, Is)] = &[
+    ("ready", Is::Ready),
+    ("blocked", Is::Blocked),
+    ("overdue", Is::Overdue),
+    ("waiting", Is::Waiting),
+    ("open", Is::Open),
+    ("closed", Is::Closed),
+    ("recurring", Is::Recurring),
+    ("top-level", Is::TopLevel),
+    ("root", Is::TopLevel),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateField {
+    Due,
+    Scheduled,
+    Deferred,
+    Created,
+    Updated,
+    Completed,
+}
+
+impl DateField {
+    fn parse(key: &str) -> Option<Self> {
+        match key {
+            "due" => Some(DateField::Due),
+            "scheduled" => Some(DateField::Scheduled),
+            "deferred" | "deferred_until" => Some(DateField::Deferred),
+            "created" => Some(DateField::Created),
+            "updated" => Some(DateField::Updated),
+            "completed" => Some(DateField::Completed),
+            _ => None,
+        }
+    }
+
+    /// Whether `7d` means a week from now rather than a week ago
+    fn looks_ahead(&self) -> bool {
+        matches!(self, DateField::Due | DateField::Scheduled | DateField::Deferred)
+    }
+
+    fn of(&self, task: &Task) -> Option<NaiveDate> {
+        let local = |at: DateTime<Utc>| at.with_timezone(&Local).date_naive();
+        match self {
+            DateField::Due => task.due,
+            DateField::Scheduled => task.scheduled,
+            DateField::Deferred => task.deferred_until,
+            DateField::Created => Some(local(task.created_at)),
+            DateField::Updated => Some(local(task.updated_at)),
+            DateField::Completed => task.completed_at.map(local),
+        }
+    }
+}
+
+impl Query {
+    /// Parse `input`, resolving statuses and custom fields against the
+    /// project config and relative dates against `today`
+    pub fn parse(input: &str, config: &ProjectConfig, today: NaiveDate) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { input, tokens, position: 0, config, today };
+        if parser.tokens.is_empty() {
+            return Ok(Self { expr: Expr::All(Vec::new()), today });
+        }
+
+        let expr = parser.parse_any()?;
+        if let Some(&(_, column)) = parser.tokens.get(parser.position) {
+            return Err(error(input, column, 1, "unmatched ')'"));
+        }
+        Ok(Self { expr, today })
+    }
+
+    /// Whether the query has no terms and so matches every task
+    pub fn is_empty(&self) -> bool {
+        matches!(&self.expr, Expr::All(terms) if terms.is_empty())
+    }
+
+    /// Whether the query picks tasks by status, so callers shouldn't hide
+    /// finished tasks on top of it
+    pub fn mentions_status(&self) -> bool {
+        fn visit(expr: &Expr) -> bool {
+            match expr {
+                Expr::All(exprs) | Expr::Any(exprs) => exprs.iter().any(visit),
+                Expr::Not(expr) => visit(expr),
+                Expr::Term(term) => matches!(
+                    term,
+                    Term::Status(_) | Term::Is(Is::Open | Is::Closed) | Term::Date(DateField::Completed, ..)
+                ),
+            }
+        }
+        visit(&self.expr)
+    }
+
+    /// Whether `task`, one of the tasks in `graph`, matches
+    pub fn matches(&self, task: &Task, graph: &TaskGraph) -> bool {
+        self.eval(&self.expr, task, graph)
+    }
+
+    fn eval(&self, expr: &Expr, task: &Task, graph: &TaskGraph) -> bool {
+        match expr {
+            Expr::All(exprs) => exprs.iter().all(|e| self.eval(e, task, graph)),
+            Expr::Any(exprs) => exprs.iter().any(|e| self.eval(e, task, graph)),
+            Expr::Not(expr) => !self.eval(expr, task, graph),
+            Expr::Term(term) => self.eval_term(term, task, graph),
+        }
+    }
+
+    fn eval_term(&self, term: &Term, task: &Task, graph: &TaskGraph) -> bool {
+        match term {
+            Term::Status(statuses) => statuses.contains(&graph.effective_status(task)),
+            Term::Priority(op, priorities) => {
+                let rank = |p: &Priority| match p {
+                    Priority::Low => 0,
+                    Priority::Medium => 1,
+                    Priority::High => 2,
+                };
+                let (actual, wanted) = (rank(&task.priority), rank(&priorities[0]));
+                match op {
+                    Op::Eq => priorities.contains(&task.priority),
+                    Op::Lt => actual < wanted,
+                    Op::Le => actual <= wanted,
+                    Op::Gt => actual > wanted,
+                    Op::Ge => actual >= wanted,
+                }
+            }
+            Term::Complexity(levels) => task.complexity.as_ref().is_some_and(|c| levels.contains(c)),
+            Term::Tag(tags) => task.tags.iter().any(|t| tags.contains(&t.to_lowercase())),
+            Term::Id(ids) => ids.contains(&task.id),
+            Term::Parent(ids) => graph.parent(task.id).is_some_and(|p| ids.contains(&p.id)),
+            Term::Milestone(names) => task.milestone.as_ref().is_some_and(|m| names.contains(m)),
+            Term::Has(has) => match has {
+                Has::Deps => !task.dependencies.is_empty(),
+                Has::Subtasks => !task.subtasks.is_empty(),
+                Has::Parent => graph.parent(task.id).is_some(),
+                Has::Due => task.due.is_some(),
+                Has::Estimate => task.estimate.is_some(),
+                Has::Notes => !task.notes.is_empty(),
+                Has::Checklist => task.checklist_progress().is_some(),
+                Has::Milestone => task.milestone.is_some(),
+                Has::Relations => !graph.relations(task).is_empty(),
+                Has::Tags => !task.tags.is_empty(),
+                Has::Time => !task.time_log.is_empty(),
+            },
+            Term::Is(is) => {
+                let status = graph.effective_status(task);
+                match is {
+                    Is::Ready => graph.is_ready(task),
+                    // Marked blocked, or pending on dependencies that aren't done
+                    Is::Blocked => status == TaskStatus::Blocked || (status == TaskStatus::Pending && !graph.is_ready(task)),
+                    Is::Overdue => task.is_overdue(self.today),
+                    Is::Waiting => task.is_waiting(self.today),
+                    Is::Open => !status.is_closed(),
+                    Is::Closed => status.is_closed(),
+                    Is::Recurring => task.recurrence.is_some(),
+                    Is::TopLevel => graph.parent(task.id).is_none(),
+                }
+            }
+            Term::Date(field, op, start, end) => field.of(task).is_some_and(|date| match op {
+                Op::Eq => *start <= date && date <= *end,
+                Op::Lt => date < *start,
+                Op::Le => date <= *end,
+                Op::Gt => date > *end,
+                Op::Ge => date >= *start,
+            }),
+            Term::Text(text) => {
+                task.title.to_lowercase().contains(text) || task.description.to_lowercase().contains(text)
+            }
+            Term::Field(filter) => filter.matches(&task.extra),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Not,
+    Or,
+    Word { text: String, quoted: bool },
+}
+
+/// Split `input` into tokens, each with the column (in characters) it starts at
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push((Token::Open, i));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((Token::Close, i));
+                i += 1;
+            }
+            '-' if chars.get(i + 1).is_some_and(|c| !c.is_whitespace()) => {
+                tokens.push((Token::Not, i));
+                i += 1;
+            }
+            first => {
+                let start = i;
+                let mut text = String::new();
+                let mut in_quotes = false;
+                while let Some(&c) = chars.get(i) {
+                    if !in_quotes && (c.is_whitespace() || c == '(' || c == ')') {
+                        break;
+                    }
+                    if c == '"' {
+                        in_quotes = !in_quotes;
+                    } else {
+                        text.push(c);
+                    }
+                    i += 1;
+                }
+                if in_quotes {
+                    return Err(error(input, start, i - start, "missing closing quote"));
+                }
+                let quoted = first == '"';
+                let token = if !quoted && (text == "OR" || text == "or") { Token::Or } else { Token::Word { text, quoted } };
+                tokens.push((token, start));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// A parse error pointing at `width` characters from `column` of `input`
+fn error(input: &str, column: usize, width: usize, message: impl fmt::Display) -> anyhow::Error {
+    anyhow::anyhow!("Invalid query: {}\n  {}\n  {}{}", message, input, " ".repeat(column), "^".repeat(width.max(1)))
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    config: &'a ProjectConfig,
+    today: NaiveDate,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn error_here(&self, message: &str) -> anyhow::Error {
+        let column = self.tokens.get(self.position).map(|&(_, column)| column).unwrap_or(self.input.chars().count());
+        error(self.input, column, 1, message)
+    }
+
+    /// Terms joined by `OR`
+    fn parse_any(&mut self) -> Result<Expr> {
+        let mut exprs = vec![self.parse_all()?];
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            exprs.push(self.parse_all()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { Expr::Any(exprs) })
+    }
+
+    /// Terms next to each other, which must all match
+    fn parse_all(&mut self) -> Result<Expr> {
+        let mut exprs = Vec::new();
+        while !matches!(self.peek(), None | Some(Token::Close) | Some(Token::Or)) {
+            exprs.push(self.parse_unary()?);
+        }
+        match exprs.len() {
+            0 => Err(self.error_here("expected a filter")),
+            1 => Ok(exprs.remove(0)),
+            _ => Ok(Expr::All(exprs)),
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        let Some((token, column)) = self.tokens.get(self.position).cloned() else {
+            return Err(self.error_here("expected a filter"));
+        };
+        self.position += 1;
+        match token {
+            Token::Not => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Token::Open => {
+                let expr = self.parse_any()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err(error(self.input, column, 1, "unclosed '('"));
+                }
+                self.position += 1;
+                Ok(expr)
+            }
+            Token::Word { text, quoted } => self.parse_term(&text, quoted, column).map(Expr::Term),
+            Token::Close | Token::Or => {
+                self.position -= 1;
+                Err(self.error_here("expected a filter"))
+            }
+        }
+    }
+
+    fn parse_term(&self, text: &str, quoted: bool, column: usize) -> Result<Term> {
+        let split = text.find([':', '<', '>', '=']).filter(|&split| split > 0);
+        let (Some(split), false) = (split, quoted) else {
+            return Ok(Term::Text(text.to_lowercase()));
+        };
+
+        let key = text[..split].to_lowercase();
+        let rest = &text[split..];
+        let (op, value) = [("<=", Op::Le), (">=", Op::Ge), (":", Op::Eq), ("=", Op::Eq), ("<", Op::Lt), (">", Op::Gt)]
+            .into_iter()
+            .find_map(|(token, op)| rest.strip_prefix(token).map(|value| (op, value)))
+            .expect("split is at an operator");
+        let fail = |message: String| error(self.input, column, text.chars().count(), message);
+        let values: Vec<&str> = value.split(',').map(str::trim).filter(|v| !v.is_empty()).collect();
+        if values.is_empty() {
+            return Err(fail(format!("'{}' needs a value", &text[..text.len() - value.len()])));
+        }
+        let only_eq = || match op {
+            Op::Eq => Ok(()),
+            _ => Err(fail(format!("{} only takes {}:VALUE", key, key))),
+        };
+        let ids = || -> Result<Vec<u32>> {
+            values.iter()
+                .map(|v| v.trim_start_matches('#').parse().map_err(|_| fail(format!("'{}' is not a task ID", v))))
+                .collect()
+        };
+
+        let term = match key.as_str() {
+            "status" => {
+                only_eq()?;
+                Term::Status(values.iter().map(|v| self.config.workflow.parse_status(v).map_err(|e| fail(e.to_string()))).collect::<Result<_>>()?)
+            }
+            "priority" => {
+                let priorities = values.iter()
+                    .map(|v| v.parse::<Priority>().map_err(|e| fail(e.to_string())))
+                    .collect::<Result<Vec<_>>>()?;
+                if op != Op::Eq && priorities.len() > 1 {
+                    return Err(fail("compare priority with a single value".to_string()));
+                }
+                Term::Priority(op, priorities)
+            }
+            "complexity" => {
+                only_eq()?;
+                Term::Complexity(values.iter().map(|v| v.parse().map_err(|e: anyhow::Error| fail(e.to_string()))).collect::<Result<_>>()?)
+            }
+            "tag" | "tags" => {
+                only_eq()?;
+                Term::Tag(values.iter().map(|v| v.to_lowercase()).collect())
+            }
+            "id" => {
+                only_eq()?;
+                Term::Id(ids()?)
+            }
+            "parent" => {
+                only_eq()?;
+                Term::Parent(ids()?)
+            }
+            "milestone" => {
+                only_eq()?;
+                Term::Milestone(values.iter().map(|v| v.to_string()).collect())
+            }
+            "has" | "is" => {
+                only_eq()?;
+                let value = value.to_lowercase();
+                if key == "has" {
+                    let has = HAS.iter().find(|(name, _)| *name == value).map(|(_, has)| *has);
+                    Term::Has(has.ok_or_else(|| fail(format!("unknown has:{}. Use {}", value, names(HAS))))?)
+                } else {
+                    let is = IS.iter().find(|(name, _)| *name == value).map(|(_, is)| *is);
+                    Term::Is(is.ok_or_else(|| fail(format!("unknown is:{}. Use {}", value, names(IS))))?)
+                }
+            }
+            key => match DateField::parse(key) {
+                Some(field) => {
+                    let (start, end) = self.parse_period(field, value).map_err(|e| fail(e.to_string()))?;
+                    Term::Date(field, op, start, end)
+                }
+                None if self.config.custom_fields.contains_key(key) => {
+                    let op = match op {
+                        Op::Eq => "=",
+                        Op::Lt => "<",
+                        Op::Le => "<=",
+                        Op::Gt => ">",
+                        Op::Ge => ">=",
+                    };
+                    let filter = FieldFilter::parse(&self.config.custom_fields, &format!("{}{}{}", key, op, value));
+                    Term::Field(filter.map_err(|e| fail(e.to_string()))?)
+                }
+                None => {
+                    let hint = suggest(key).map(|k| format!(" (did you mean {}?)", k)).unwrap_or_default();
+                    return Err(fail(format!(
+                        "unknown filter '{}'{}. Use {}, a custom field, or quotes to search for the text",
+                        key, hint, KEYS.join(", ")
+                    )));
+                }
+            },
+        };
+        Ok(term)
+    }
+
+    /// The days a date value covers: a whole year (`2024`) or month
+    /// (`2024-01`), or a single day. `7d`, `2w` and `1m` count forward from
+    /// today for due dates and back from today for the rest.
+    fn parse_period(&self, field: DateField, value: &str) -> Result<(NaiveDate, NaiveDate)> {
+        let today = self.today;
+        let (count, unit) = value.split_at(value.len() - value.chars().last().map_or(0, char::len_utf8));
+        if !count.is_empty() && count.chars().all(|c| c.is_ascii_digit()) && matches!(unit, "d" | "w" | "m") {
+            let ahead = dates::parse_date(&format!("+{}", value), today)?;
+            let date = if field.looks_ahead() { ahead } else { today - (ahead - today) };
+            return Ok((date, date));
+        }
+
+        if value.len() == 4 {
+            if let Some(start) = value.parse().ok().and_then(|year| NaiveDate::from_ymd_opt(year, 1, 1)) {
+                return Ok((start, last_day(start, 12)));
+            }
+        }
+        if let Ok(start) = NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d") {
+            return Ok((start, last_day(start, 1)));
+        }
+        let date = dates::parse_date(value, today)?;
+        Ok((date, date))
+    }
+}
+
+fn names<T>(table: &[(&str, T)]) -> String {
+    table.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ")
+}
+
+/// The day before `months` months after `start`
+fn last_day(start: NaiveDate, months: u32) -> NaiveDate {
+    start.checked_add_months(Months::new(months))
+        .and_then(|next| next.checked_sub_days(Days::new(1)))
+        .unwrap_or(start)
+}
+
+/// The known key closest to a mistyped one, if any is close
+fn suggest(key: &str) -> Option<&'static str> {
+    fn distance(a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        let mut row: Vec<usize> = (0..=b.len()).collect();
+        for (i, ca) in a.chars().enumerate() {
+            let mut previous = row[0];
+            row[0] = i + 1;
+            for (j, &cb) in b.iter().enumerate() {
+                let substitution = previous + usize::from(ca != cb);
+                previous = row[j + 1];
+                row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+            }
+        }
+        row[b.len()]
+    }
+    KEYS.iter().copied()
+        .map(|k| (distance(key, k), k))
+        .filter(|&(d, _)| d <= 2)
+        .min()
+        .map(|(_, k)| k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Priority;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn task(id: u32, title: &str, priority: Priority, tags: &[&str]) -> Task {
+        let mut task = Task::new(id, title.to_string(), String::new(), priority);
+        task.tags = tags.iter().map(|t| t.to_string()).collect();
+        task
+    }
+
+    /// IDs of the tasks matching `input`
+    fn select(graph: &TaskGraph, input: &str) -> Vec<u32> {
+        let query = Query::parse(input, &ProjectConfig::default(), date("2025-03-10")).unwrap();
+        graph.tasks().iter().filter(|t| query.matches(t, graph)).map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn test_terms_groups_and_negation() {
+        let mut oauth = task(1, "OAuth login", Priority::High, &["backend"]);
+        oauth.due = Some(date("2025-03-14"));
+        oauth.subtasks = vec![3];
+        let mut spike = task(2, "Spike: token storage", Priority::Medium, &["backend", "spike"]);
+        spike.add_dependency(1);
+        let mut docs = task(3, "Document the login flow", Priority::Low, &["Docs"]);
+        docs.due = Some(date("2025-04-02"));
+        let graph = TaskGraph::new(vec![oauth, spike, docs]);
+
+        assert_eq!(select(&graph, ""), vec![1, 2, 3]);
+        assert_eq!(select(&graph, "tag:backend -tag:spike"), vec![1]);
+        assert_eq!(select(&graph, "status:pending,blocked priority>=medium"), vec![1, 2]);
+        assert_eq!(select(&graph, "\"login\" tag:docs"), vec![3]);
+        assert_eq!(select(&graph, "login -(priority:high OR has:deps)"), vec![3]);
+        assert_eq!(select(&graph, "priority:low OR has:deps"), vec![2, 3]);
+        assert_eq!(select(&graph, "is:ready"), vec![1, 3]);
+        assert_eq!(select(&graph, "is:blocked"), vec![2]);
+        assert_eq!(select(&graph, "parent:1"), vec![3]);
+        assert_eq!(select(&graph, "is:top-level has:subtasks"), vec![1]);
+        assert_eq!(select(&graph, "\"spike:\""), vec![2]);
+    }
+
+    #[test]
+    fn test_dates_and_periods() {
+        let mut soon = task(1, "Soon", Priority::Medium, &[]);
+        soon.due = Some(date("2025-03-14"));
+        soon.created_at = "2025-01-20T12:00:00Z".parse().unwrap();
+        let mut later = task(2, "Later", Priority::Medium, &[]);
+        later.due = Some(date("2025-04-02"));
+        later.created_at = "2024-11-02T12:00:00Z".parse().unwrap();
+        let mut undated = task(3, "Undated", Priority::Medium, &[]);
+        undated.created_at = "2025-03-01T12:00:00Z".parse().unwrap();
+        let graph = TaskGraph::new(vec![soon, later, undated]);
+
+        assert_eq!(select(&graph, "due<7d"), vec![1]);
+        assert_eq!(select(&graph, "due>=2025-04"), vec![2]);
+        assert_eq!(select(&graph, "due:2025-03"), vec![1]);
+        assert_eq!(select(&graph, "created>2024"), vec![1, 3]);
+        assert_eq!(select(&graph, "created>14d"), vec![3]);
+        assert_eq!(select(&graph, "created<2025-01 OR -has:due"), vec![2, 3]);
+        assert_eq!(select(&graph, "due>today"), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_the_term() {
+        let parse = |input: &str| Query::parse(input, &ProjectConfig::default(), date("2025-03-10")).unwrap_err().to_string();
+
+        let message = parse("priority:high stauts:pending");
+        assert!(message.contains("unknown filter 'stauts' (did you mean status?)"), "{}", message);
+        assert!(message.ends_with("\n  priority:high stauts:pending\n                ^^^^^^^^^^^^^^"), "{}", message);
+
+        assert!(parse("status:later").contains("Invalid status: later"));
+        assert!(parse("(tag:a OR tag:b").contains("unclosed '('"));
+        assert!(parse("tag:a)").contains("unmatched ')'"));
+        assert!(parse("tag:a OR").contains("expected a filter"));
+        assert!(parse("is:stuck").contains("Use ready, blocked"));
+        assert!(parse("due<soon").contains("Invalid date 'soon'"));
+        assert!(parse("tag>a").contains("tag only takes tag:VALUE"));
+        assert!(parse("\"oauth").contains("missing closing quote"));
+        assert!(parse("priority:,").contains("'priority:' needs a value"));
+        assert!(parse("tag:a -tag:,,").contains("'tag:' needs a value"));
+        assert!(parse("due<").contains("'due<' needs a value"));
+    }
+}
@@ -0,0 +1,340 @@
+use anyhow::Result;
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::storage::TaskStore;
+use crate::task::Task;
+
+/// How far ahead a cron rule is searched for its next date
+const CRON_SEARCH_DAYS: u64 = 366 * 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// How often a recurring task comes back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Rule {
+    /// Every `count` days, weeks, months or years
+    Every { count: u32, unit: Unit },
+    /// Dates matching a cron-style `DAY MONTH WEEKDAY` pattern, where `None`
+    /// is `*` and weekdays run from 0 (Sunday) to 6
+    Cron { days: Option<Vec<u32>>, months: Option<Vec<u32>>, weekdays: Option<Vec<u32>> },
+}
+
+impl Rule {
+    /// The first date after `date` the rule falls on
+    pub fn next_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Rule::Every { count, unit } => match unit {
+                Unit::Day => date.checked_add_days(Days::new((*count).into())),
+                Unit::Week => date.checked_add_days(Days::new(u64::from(*count) * 7)),
+                Unit::Month => date.checked_add_months(Months::new(*count)),
+                Unit::Year => date.checked_add_months(Months::new(count * 12)),
+            },
+            Rule::Cron { .. } => (1..=CRON_SEARCH_DAYS)
+                .filter_map(|days| date.checked_add_days(Days::new(days)))
+                .find(|candidate| self.matches(*candidate)),
+        }
+    }
+
+    /// Whether a cron rule falls on `date`. Like cron, a rule restricting both
+    /// the day of the month and the weekday matches either.
+    fn matches(&self, date: NaiveDate) -> bool {
+        let Rule::Cron { days, months, weekdays } = self else {
+            return false;
+        };
+        let allows = |set: &Option<Vec<u32>>, value: u32| set.as_ref().is_none_or(|set| set.contains(&value));
+        let day = allows(days, date.day());
+        let weekday = allows(weekdays, date.weekday().num_days_from_sunday());
+        let day_matches = if days.is_some() && weekdays.is_some() { day || weekday } else { day && weekday };
+        allows(months, date.month()) && day_matches
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::Every { count: 1, unit } => write!(f, "{}", match unit {
+                Unit::Day => "daily",
+                Unit::Week => "weekly",
+                Unit::Month => "monthly",
+                Unit::Year => "yearly",
+            }),
+            Rule::Every { count, unit } => write!(f, "every {} {}", count, match unit {
+                Unit::Day => "days",
+                Unit::Week => "weeks",
+                Unit::Month => "months",
+                Unit::Year => "years",
+            }),
+            Rule::Cron { days: None, months: None, weekdays: Some(weekdays) } => {
+                let names: Vec<String> = weekdays.iter()
+                    .map(|&d| Weekday::try_from(((d + 6) % 7) as u8).map(|w| w.to_string().to_lowercase()).unwrap_or_default())
+                    .collect();
+                write!(f, "every {}", names.join(","))
+            }
+            Rule::Cron { days, months, weekdays } => {
+                let field = |set: &Option<Vec<u32>>| set.as_ref()
+                    .map(|set| set.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+                    .unwrap_or_else(|| "*".to_string());
+                write!(f, "cron:{} {} {}", field(days), field(months), field(weekdays))
+            }
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    /// `daily`, `weekly`, `monthly`, `yearly`, `every 2 weeks`, `every mon,thu`
+    /// or `cron:DAY MONTH WEEKDAY` (e.g. `cron:1,15 * *`, `cron:* * 1-5`)
+    fn from_str(s: &str) -> Result<Self> {
+        let text = s.trim().to_lowercase();
+        let invalid = || anyhow::anyhow!(
+            "Invalid recurrence '{}'. Use daily, weekly, monthly, yearly, 'every 2 weeks', 'every mon,thu' or 'cron:DAY MONTH WEEKDAY'",
+            s
+        );
+
+        let every = |count, unit| Ok(Rule::Every { count, unit });
+        match text.as_str() {
+            "daily" => return every(1, Unit::Day),
+            "weekly" => return every(1, Unit::Week),
+            "monthly" => return every(1, Unit::Month),
+            "yearly" | "annually" => return every(1, Unit::Year),
+            _ => {}
+        }
+
+        if let Some(pattern) = text.strip_prefix("cron:") {
+            let fields: Vec<&str> = pattern.split_whitespace().collect();
+            let [days, months, weekdays] = fields[..] else {
+                return Err(invalid());
+            };
+            // 7 is Sunday as well as 0
+            let weekdays = parse_cron_field(weekdays, 0, 7)?.map(|set| {
+                let mut set: Vec<u32> = set.into_iter().map(|d| d % 7).collect();
+                set.sort_unstable();
+                set.dedup();
+                set
+            });
+            return Ok(Rule::Cron {
+                days: parse_cron_field(days, 1, 31)?,
+                months: parse_cron_field(months, 1, 12)?,
+                weekdays,
+            });
+        }
+
+        let rest = text.strip_prefix("every ").ok_or_else(invalid)?;
+        let weekdays: Option<Vec<u32>> = rest.split(',')
+            .map(|day| day.trim().parse::<Weekday>().ok().map(|w| w.num_days_from_sunday()))
+            .collect();
+        if let Some(weekdays) = weekdays {
+            return Ok(Rule::Cron { days: None, months: None, weekdays: Some(weekdays) });
+        }
+
+        let (count, unit) = match rest.split_once(' ') {
+            Some((count, unit)) => (count.parse::<u32>().map_err(|_| invalid())?, unit),
+            None => (1, rest),
+        };
+        let unit = match unit.trim_end_matches('s') {
+            "day" => Unit::Day,
+            "week" => Unit::Week,
+            "month" => Unit::Month,
+            "year" => Unit::Year,
+            _ => return Err(invalid()),
+        };
+        if count == 0 {
+            return Err(invalid());
+        }
+        every(count, unit)
+    }
+}
+
+impl From<Rule> for String {
+    fn from(rule: Rule) -> Self {
+        rule.to_string()
+    }
+}
+
+impl TryFrom<String> for Rule {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+/// `*`, or a comma-separated list of numbers and ranges between `min` and `max`
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<Option<Vec<u32>>> {
+    if field == "*" {
+        return Ok(None);
+    }
+
+    let number = |s: &str| -> Result<u32> {
+        s.parse::<u32>().ok()
+            .filter(|n| (min..=max).contains(n))
+            .ok_or_else(|| anyhow::anyhow!("Invalid cron value '{}': expected {}-{}", s, min, max))
+    };
+    let mut values = Vec::new();
+    for part in field.split(',') {
+        match part.split_once('-') {
+            Some((start, end)) => values.extend(number(start)?..=number(end)?),
+            None => values.push(number(part)?),
+        }
+    }
+    values.sort_unstable();
+    values.dedup();
+    Ok(Some(values))
+}
+
+/// A task's recurrence rule and when the series ends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub rule: Rule,
+    /// No occurrence is due after this date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<NaiveDate>,
+    /// Occurrences left, counting the current one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<u32>,
+}
+
+impl Recurrence {
+    /// The due date of a new series started on `today`
+    pub fn first_due(&self, today: NaiveDate) -> NaiveDate {
+        match self.rule {
+            Rule::Every { .. } => today,
+            Rule::Cron { .. } => today.pred_opt()
+                .and_then(|yesterday| self.rule.next_after(yesterday))
+                .unwrap_or(today),
+        }
+    }
+
+    /// When the occurrence after one due on `due` is due. Dates that have
+    /// already passed are skipped, so finishing late doesn't leave a backlog
+    /// of overdue copies. `None` once the series is over.
+    pub fn next_due(&self, due: NaiveDate, today: NaiveDate) -> Option<NaiveDate> {
+        if self.remaining.is_some_and(|n| n <= 1) {
+            return None;
+        }
+        let mut next = self.rule.next_after(due)?;
+        while next <= today {
+            next = self.rule.next_after(next)?;
+        }
+        self.until.is_none_or(|until| next <= until).then_some(next)
+    }
+
+    /// Shown after a task's title: `weekly until 2025-12-31`, `daily, 3 left`
+    pub fn describe(&self) -> String {
+        let mut text = self.rule.to_string();
+        if let Some(until) = self.until {
+            text.push_str(&format!(" until {}", until));
+        }
+        if let Some(remaining) = self.remaining {
+            text.push_str(&format!(", {} left", remaining));
+        }
+        text
+    }
+}
+
+/// The first task of the series `task` belongs to
+pub fn series_of(task: &Task) -> u32 {
+    task.series.unwrap_or(task.id)
+}
+
+/// Create the next occurrence of `done`, a recurring task that was just
+/// completed. The rule moves to the new task so completing `done` again
+/// doesn't create a second copy. Returns `None`, and drops the rule, when
+/// the series is over.
+pub fn create_next(storage: &dyn TaskStore, done: &Task, today: NaiveDate) -> Result<Option<Task>> {
+    let Some(recurrence) = &done.recurrence else {
+        return Ok(None);
+    };
+    let anchor = done.due.unwrap_or(today);
+    let mut finished = done.clone();
+    finished.recurrence = None;
+
+    let Some(due) = recurrence.next_due(anchor, today) else {
+        storage.save_task(&finished)?;
+        return Ok(None);
+    };
+
+    let mut next = Task::new(storage.allocate_id()?, done.title.clone(), done.description.clone(), done.priority.clone());
+    next.complexity = done.complexity.clone();
+    next.tags = done.tags.clone();
+    next.extra = done.extra.clone();
+    next.due = Some(due);
+    // Keep the same lead time between start and due date
+    next.scheduled = done.scheduled.and_then(|scheduled| scheduled.checked_add_signed(due - anchor));
+    next.series = Some(series_of(done));
+    next.recurrence = Some(Recurrence {
+        remaining: recurrence.remaining.map(|n| n - 1),
+        ..recurrence.clone()
+    });
+    storage.save_task(&next)?;
+    storage.save_task(&finished)?;
+
+    Ok(Some(next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn rule(s: &str) -> Rule {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display_round_trip() {
+        for text in ["daily", "weekly", "every 2 weeks", "every 3 months", "every mon,thu", "cron:1,15 * *", "cron:* 1,2,3 1,5"] {
+            assert_eq!(rule(text).to_string(), text);
+        }
+        assert_eq!(rule("every month"), rule("monthly"));
+        assert_eq!(rule("cron:* * 1-5").to_string(), "every mon,tue,wed,thu,fri");
+        assert_eq!(rule("cron:* * 0,7"), rule("every sun"));
+
+        assert!("every 0 days".parse::<Rule>().is_err());
+        assert!("cron:32 * *".parse::<Rule>().is_err());
+        assert!("fortnightly".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_next_dates() {
+        // 2025-01-15 is a Wednesday
+        let wednesday = date("2025-01-15");
+        assert_eq!(rule("every 2 weeks").next_after(wednesday), Some(date("2025-01-29")));
+        assert_eq!(rule("monthly").next_after(date("2025-01-31")), Some(date("2025-02-28")));
+        assert_eq!(rule("every mon,thu").next_after(wednesday), Some(date("2025-01-16")));
+        assert_eq!(rule("cron:1,15 * *").next_after(wednesday), Some(date("2025-02-01")));
+        // Day of month or weekday, as in cron
+        assert_eq!(rule("cron:20 * 5").next_after(wednesday), Some(date("2025-01-17")));
+        assert_eq!(rule("cron:30 2 *").next_after(wednesday), None);
+    }
+
+    #[test]
+    fn test_series_end() {
+        let today = date("2025-01-15");
+        let weekly = |until: Option<&str>, remaining| Recurrence { rule: rule("weekly"), until: until.map(date), remaining };
+
+        // Finished late: missed dates are skipped
+        assert_eq!(weekly(None, None).next_due(date("2025-01-01"), today), Some(date("2025-01-22")));
+        assert_eq!(weekly(Some("2025-01-19"), None).next_due(date("2025-01-13"), today), None);
+        assert_eq!(weekly(None, Some(2)).next_due(today, today), Some(date("2025-01-22")));
+        assert_eq!(weekly(None, Some(1)).next_due(today, today), None);
+
+        let mondays = Recurrence { rule: rule("every mon"), until: None, remaining: None };
+        assert_eq!(mondays.first_due(today), date("2025-01-20"));
+        assert_eq!(mondays.first_due(date("2025-01-20")), date("2025-01-20"));
+    }
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A typed link from one task to another. Unlike dependencies, none of
+/// these block a task; `after` only changes what `next` suggests first.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum RelationKind {
+    RelatesTo,
+    Duplicates,
+    FollowUpOf,
+    Supersedes,
+    /// Should come after the other task, without waiting for it
+    After,
+}
+
+impl RelationKind {
+    pub const ALL: [RelationKind; 5] = [
+        RelationKind::RelatesTo,
+        RelationKind::Duplicates,
+        RelationKind::FollowUpOf,
+        RelationKind::Supersedes,
+        RelationKind::After,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RelationKind::RelatesTo => "relates-to",
+            RelationKind::Duplicates => "duplicates",
+            RelationKind::FollowUpOf => "follow-up-of",
+            RelationKind::Supersedes => "supersedes",
+            RelationKind::After => "after",
+        }
+    }
+
+    /// How the link reads from the other task
+    pub fn inverse_name(&self) -> &'static str {
+        match self {
+            RelationKind::RelatesTo => "relates-to",
+            RelationKind::Duplicates => "duplicated-by",
+            RelationKind::FollowUpOf => "followed-up-by",
+            RelationKind::Supersedes => "superseded-by",
+            RelationKind::After => "before",
+        }
+    }
+}
+
+impl fmt::Display for RelationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for RelationKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_lowercase().replace('_', "-").as_str() {
+            "relates-to" | "related" => Ok(RelationKind::RelatesTo),
+            "duplicates" | "duplicate-of" => Ok(RelationKind::Duplicates),
+            "follow-up-of" | "follows-up" => Ok(RelationKind::FollowUpOf),
+            "supersedes" => Ok(RelationKind::Supersedes),
+            "after" | "comes-after" => Ok(RelationKind::After),
+            _ => anyhow::bail!(
+                "Invalid relation: {}. Use {}",
+                s,
+                RelationKind::ALL.iter().map(RelationKind::name).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+/// A link from the task holding it to task `task`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Relation {
+    pub kind: RelationKind,
+    pub task: u32,
+}
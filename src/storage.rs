@@ -1,12 +1,128 @@
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::task::Task;
+use crate::config::{ProjectConfig, StorageBackend};
+use crate::defer::WakeCondition;
+use crate::estimate::Estimate;
+use crate::graph::{ReverseEdges, TaskGraph};
+use crate::recur::Recurrence;
+use crate::relation::{Relation, RelationKind};
+use crate::timelog::TimeEntry;
+use crate::history::{HistoryEvent, HistoryStore};
+use crate::notes::{self, Note};
+use crate::task::{Complexity, Priority, Task, TaskRef, TaskStatus};
 
+mod index;
+mod json;
+mod lock;
+mod merge;
+mod sqlite;
+
+use index::{IndexEntry, TaskIndex};
+pub use json::JsonStore;
+pub use lock::{atomic_write, LockGuard, LockedStore, ProjectLock};
+pub use merge::{merge_task_files, MergeOutcome};
+pub use sqlite::SqliteStore;
+
+/// A persistence backend for tasks.
+///
+/// Every command talks to storage through this trait so that the on-disk
+/// layout can be chosen per project in `.trusty/config`.
+pub trait TaskStore {
+    fn load_task(&self, id: u32) -> Result<Task>;
+
+    fn save_task(&self, task: &Task) -> Result<()>;
+
+    fn delete_task(&self, id: u32) -> Result<()>;
+
+    /// All tasks, sorted by ID
+    fn list_all_tasks(&self) -> Result<Vec<Task>>;
+
+    /// All tasks along with the links between them
+    fn load_graph(&self) -> Result<TaskGraph> {
+        Ok(TaskGraph::new(self.list_all_tasks()?))
+    }
+
+    /// Entries that `list_all_tasks` skips because they can't be read as
+    /// tasks, with the reason for each
+    fn unreadable(&self) -> Result<Vec<(String, String)>> {
+        Ok(Vec::new())
+    }
+
+    /// The ID to use for the next newly created task
+    fn next_id(&self) -> Result<u32> {
+        Ok(self.list_all_tasks()?.iter().map(|t| t.id).max().unwrap_or(0) + 1)
+    }
+
+    /// Reserve the ID for a new task. Stores shared between processes never
+    /// hand out the same ID twice, even to concurrent `trusty add`s.
+    fn allocate_id(&self) -> Result<u32> {
+        self.next_id()
+    }
+
+    /// Hold the store's write lock until the guard is dropped, so that a
+    /// load-modify-save sequence can't interleave with another process
+    fn lock(&self) -> Result<LockGuard<'_>> {
+        Ok(LockGuard::unlocked())
+    }
+
+    /// Turn a command-line reference into a task ID. Numeric IDs are taken
+    /// as-is; uids may be abbreviated to any unique prefix.
+    fn resolve(&self, task: &TaskRef) -> Result<u32> {
+        let prefix = match task {
+            TaskRef::Id(id) => return Ok(*id),
+            TaskRef::Uid(prefix) => prefix,
+        };
+        
+        let matches: Vec<Task> = self.list_all_tasks()?
+            .into_iter()
+            .filter(|t| t.uid.as_deref().is_some_and(|uid| uid.starts_with(prefix.as_str())))
+            .collect();
+        
+        match matches.as_slice() {
+            [task] => Ok(task.id),
+            [] => anyhow::bail!("No task with uid '{}'", prefix),
+            _ => anyhow::bail!(
+                "Uid prefix '{}' is ambiguous: {}",
+                prefix,
+                matches.iter().map(|t| format!("#{} ({})", t.id, t.uid.as_deref().unwrap_or_default())).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+/// Open the backend selected by `config` for the project in `trusty_dir`
+pub fn open_store(trusty_dir: &Path, config: &ProjectConfig) -> Result<Box<dyn TaskStore>> {
+    let location = config.storage.location(trusty_dir);
+
+    let inner: Box<dyn TaskStore> = match config.storage {
+        StorageBackend::Markdown => Box::new(TaskStorage::new(location)?.with_index(trusty_dir.join("index"))),
+        StorageBackend::Json => Box::new(JsonStore::new(location)?),
+        StorageBackend::Sqlite => Box::new(SqliteStore::new(location)?),
+    };
+    
+    // History is stamped inside the lock, see `HistoryStore`
+    let inner = Box::new(HistoryStore::new(inner));
+    Ok(Box::new(LockedStore::new(inner, trusty_dir, config.id_scheme)?))
+}
+
+/// Markdown backend: one file per task, named `<id>.md`, or `<uid>.md` for
+/// tasks that have a uid.
 pub struct TaskStorage {
     base_path: PathBuf,
+    index: Option<TaskIndex>,
+}
+
+/// A parsed task file whose references have not been resolved yet
+struct TaskFile {
+    path: PathBuf,
+    frontmatter: Frontmatter,
+    description: String,
+    notes: Vec<Note>,
 }
 
 impl TaskStorage {
@@ -14,197 +130,497 @@ impl TaskStorage {
         fs::create_dir_all(&base_path)
             .with_context(|| format!("Failed to create task directory: {:?}", base_path))?;
         
-        Ok(Self { base_path })
+        Ok(Self { base_path, index: None })
     }
 
+    /// Cache parsed task files in `index_path`, so reads only parse files
+    /// that changed since the last one
+    pub fn with_index(mut self, index_path: PathBuf) -> Self {
+        self.index = Some(TaskIndex::new(index_path));
+        self
+    }
+
+    /// Path of the file for a task without a uid
     pub fn get_task_path(&self, id: u32) -> PathBuf {
         self.base_path.join(format!("{}.md", id))
     }
 
-    pub fn save_task(&self, task: &Task) -> Result<()> {
-        let content = self.task_to_markdown(task);
-        let path = self.get_task_path(task.id);
-        
-        fs::write(&path, content)
-            .with_context(|| format!("Failed to write task file: {:?}", path))?;
-        
-        Ok(())
+    fn task_path(&self, task: &Task) -> PathBuf {
+        match &task.uid {
+            Some(uid) => self.base_path.join(format!("{}.md", uid)),
+            None => self.get_task_path(task.id),
+        }
     }
 
-    pub fn load_task(&self, id: u32) -> Result<Task> {
-        let path = self.get_task_path(id);
-        let content = fs::read_to_string(&path)
-            .with_context(|| format!("Failed to read task file: {:?}", path))?;
+    /// Parse every task file, skipping files that aren't valid tasks.
+    /// Files whose index entry is still fresh aren't read at all.
+    fn read_files(&self) -> Result<Vec<TaskFile>> {
+        let mut cached = self.index.as_ref().map(TaskIndex::take).unwrap_or_default();
+        let mut entries = BTreeMap::new();
+        let mut changed = false;
+        let mut reparsed = false;
+        let mut files = Vec::new();
         
-        self.markdown_to_task(&content)
-    }
-
-    pub fn delete_task(&self, id: u32) -> Result<()> {
-        let path = self.get_task_path(id);
-        fs::remove_file(&path)
-            .with_context(|| format!("Failed to delete task file: {:?}", path))?;
+        for entry in fs::read_dir(&self.base_path)? {
+            let path = entry?.path();
+            
+            if path.extension().and_then(|s| s.to_str()) != Some("md") {
+                continue;
+            }
+            let (Some(name), Ok(metadata)) = (path.file_name().and_then(|s| s.to_str()), fs::metadata(&path)) else {
+                continue;
+            };
+            let name = name.to_string();
+            
+            let entry = match cached.remove(&name).filter(|e| e.is_fresh(&metadata)) {
+                Some(entry) => entry,
+                None => {
+                    let Ok(content) = fs::read_to_string(&path) else {
+                        continue;
+                    };
+                    let Ok((frontmatter, description, notes)) = self.parse_markdown(&content) else {
+                        continue;
+                    };
+                    let entry = IndexEntry::new(&metadata, frontmatter, description, notes)?;
+                    changed |= !entry.is_racy();
+                    reparsed = true;
+                    entry
+                }
+            };
+            
+            files.push(TaskFile {
+                path,
+                frontmatter: entry.frontmatter.clone(),
+                description: entry.description.clone(),
+                notes: entry.notes.clone(),
+            });
+            entries.insert(name, entry);
+        }
         
-        Ok(())
+        // Anything left over belongs to a file that has been deleted
+        changed |= !cached.is_empty();
+        reparsed |= !cached.is_empty();
+        if let Some(index) = &self.index {
+            index.store(entries, changed, reparsed)?;
+        }
+        
+        Ok(files)
     }
 
-    pub fn list_all_tasks(&self) -> Result<Vec<Task>> {
-        let mut tasks = Vec::new();
+    /// Load all tasks, turning uid references back into numeric IDs.
+    ///
+    /// Two branches can each create a task with the same numeric alias. Files
+    /// without a uid keep theirs; the newer uid task gets the next free ID,
+    /// which is written back so the alias stays stable.
+    fn load_all(&self) -> Result<Vec<Task>> {
+        let mut files = self.read_files()?;
+        files.sort_by(|a, b| {
+            let key = |f: &TaskFile| (f.frontmatter.id, f.frontmatter.uid.is_some(), f.frontmatter.created_at);
+            key(a).cmp(&key(b))
+        });
         
-        for entry in fs::read_dir(&self.base_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if path.extension().and_then(|s| s.to_str()) == Some("md") {
-                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                    if let Ok(id) = stem.parse::<u32>() {
-                        if let Ok(task) = self.load_task(id) {
-                            tasks.push(task);
-                        }
-                    }
-                }
+        let mut next_free = files.iter().map(|f| f.frontmatter.id).max().unwrap_or(0) + 1;
+        let mut seen = HashSet::new();
+        let mut realiased = Vec::new();
+        
+        for (idx, file) in files.iter_mut().enumerate() {
+            if !seen.insert(file.frontmatter.id) && file.frontmatter.uid.is_some() {
+                file.frontmatter.id = next_free;
+                next_free += 1;
+                realiased.push(idx);
             }
         }
         
+        let aliases: HashMap<String, u32> = files.iter()
+            .filter_map(|f| f.frontmatter.uid.clone().map(|uid| (uid, f.frontmatter.id)))
+            .collect();
+        
+        let paths: Vec<PathBuf> = files.iter().map(|f| f.path.clone()).collect();
+        let mut tasks: Vec<Task> = files.into_iter()
+            .map(|f| f.frontmatter.into_task(f.description, f.notes, &aliases))
+            .collect();
+        
+        for idx in realiased {
+            let content = self.task_to_markdown(&tasks[idx], &tasks)?;
+            atomic_write(&paths[idx], content.as_bytes())
+                .with_context(|| format!("Failed to write task file: {:?}", paths[idx]))?;
+        }
+        
         tasks.sort_by_key(|t| t.id);
         Ok(tasks)
     }
 
-    fn task_to_markdown(&self, task: &Task) -> String {
-        let mut content = String::new();
+    fn task_to_markdown(&self, task: &Task, all_tasks: &[Task]) -> Result<String> {
+        let uids: HashMap<u32, &str> = all_tasks.iter()
+            .filter_map(|t| t.uid.as_deref().map(|uid| (t.id, uid)))
+            .collect();
         
-        // Frontmatter
+        let frontmatter = serde_yaml::to_string(&Frontmatter::new(task, &uids))
+            .with_context(|| format!("Failed to serialize frontmatter for task #{}", task.id))?;
+        
+        let mut content = String::new();
         content.push_str("---\n");
-        content.push_str(&format!("id: {}\n", task.id));
-        content.push_str(&format!("title: \"{}\"\n", task.title.replace("\"", "\\\"")));
-        content.push_str(&format!("status: {}\n", serde_json::to_string(&task.status).unwrap().trim_matches('"')));
-        content.push_str(&format!("priority: {}\n", serde_json::to_string(&task.priority).unwrap().trim_matches('"')));
+        content.push_str(&frontmatter);
+        content.push_str("---\n\n");
         
-        if let Some(complexity) = &task.complexity {
-            content.push_str(&format!("complexity: {}\n", serde_json::to_string(complexity).unwrap().trim_matches('"')));
-        }
+        // Heading is for human readers only; the title is read back from the frontmatter
+        content.push_str(&format!("# {}\n\n", task.title.replace(['\r', '\n'], " ")));
+        content.push_str(&notes::render_body(&task.description, &task.notes));
         
-        if !task.dependencies.is_empty() {
-            let deps: Vec<_> = task.dependencies.iter().collect();
-            content.push_str(&format!("dependencies: {:?}\n", deps));
-        }
+        Ok(content)
+    }
+
+    fn parse_markdown(&self, content: &str) -> Result<(Frontmatter, String, Vec<Note>)> {
+        let (frontmatter, body) = split_frontmatter(content)
+            .ok_or_else(|| anyhow::anyhow!("Invalid markdown format: missing frontmatter"))?;
         
-        if !task.subtasks.is_empty() {
-            content.push_str(&format!("subtasks: {:?}\n", task.subtasks));
-        }
+        let frontmatter: Frontmatter = match serde_yaml::from_str(frontmatter) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                // Files written before frontmatter was proper YAML may carry titles with unescaped backslashes
+                let fixed = legacy_title_fixup(frontmatter).ok_or(e).context("Invalid task frontmatter")?;
+                serde_yaml::from_str(&fixed).context("Invalid task frontmatter")?
+            }
+        };
         
-        if !task.tags.is_empty() {
-            content.push_str(&format!("tags: {:?}\n", task.tags));
+        let (description, notes) = notes::split_body(&extract_description(body));
+        Ok((frontmatter, description, notes))
+    }
+}
+
+impl TaskStore for TaskStorage {
+    fn load_task(&self, id: u32) -> Result<Task> {
+        // A numeric file that only references other numeric tasks can be read on its own
+        let path = self.get_task_path(id);
+        if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read task file: {:?}", path))?;
+            let (frontmatter, description, notes) = self.parse_markdown(&content)?;
+            
+            if frontmatter.uid.is_none() && !frontmatter.has_uid_refs() {
+                return Ok(frontmatter.into_task(description, notes, &HashMap::new()));
+            }
         }
         
-        content.push_str(&format!("created_at: {}\n", task.created_at.to_rfc3339()));
-        content.push_str(&format!("updated_at: {}\n", task.updated_at.to_rfc3339()));
+        self.load_all()?
+            .into_iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Task #{} not found", id))
+    }
+
+    fn save_task(&self, task: &Task) -> Result<()> {
+        // Uids are only needed when a referenced task isn't a plain `<id>.md` file
+        let needs_uids = task.dependencies.iter()
+            .chain(&task.subtasks)
+            .chain(&task.series)
+            .chain(task.relations.iter().map(|r| &r.task))
+            .any(|&id| !self.get_task_path(id).exists());
+        let all_tasks = if needs_uids { self.load_all()? } else { Vec::new() };
+        let content = self.task_to_markdown(task, &all_tasks)?;
+        let path = self.task_path(task);
         
-        if let Some(completed_at) = task.completed_at {
-            content.push_str(&format!("completed_at: {}\n", completed_at.to_rfc3339()));
+        atomic_write(&path, content.as_bytes())
+            .with_context(|| format!("Failed to write task file: {:?}", path))?;
+        
+        // A task that just got a uid moves from `<id>.md` to `<uid>.md`
+        let legacy_path = self.get_task_path(task.id);
+        if task.uid.is_some() && legacy_path.exists() {
+            fs::remove_file(&legacy_path)
+                .with_context(|| format!("Failed to delete task file: {:?}", legacy_path))?;
         }
         
-        content.push_str("---\n\n");
+        Ok(())
+    }
+
+    fn delete_task(&self, id: u32) -> Result<()> {
+        let task = self.load_task(id)?;
+        let path = self.task_path(&task);
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to delete task file: {:?}", path))?;
         
-        // Main content
-        content.push_str(&format!("# {}\n\n", task.title));
-        content.push_str(&task.description);
+        Ok(())
+    }
+
+    fn list_all_tasks(&self) -> Result<Vec<Task>> {
+        self.load_all()
+    }
+
+    fn load_graph(&self) -> Result<TaskGraph> {
+        let tasks = self.load_all()?;
+        let Some(index) = &self.index else {
+            return Ok(TaskGraph::new(tasks));
+        };
         
-        content
+        // Stored edges are only kept while no task file has changed
+        let edges = match index.edges() {
+            Some(edges) => edges,
+            None => {
+                let edges = ReverseEdges::new(&tasks);
+                index.store_edges(edges.clone())?;
+                edges
+            }
+        };
+        Ok(TaskGraph::with_edges(tasks, edges))
     }
 
-    fn markdown_to_task(&self, content: &str) -> Result<Task> {
-        let parts: Vec<&str> = content.splitn(3, "---").collect();
-        if parts.len() < 3 {
-            anyhow::bail!("Invalid markdown format: missing frontmatter");
-        }
+    fn unreadable(&self) -> Result<Vec<(String, String)>> {
+        let mut unreadable = Vec::new();
         
-        let frontmatter = parts[1].trim();
-        let body = parts[2].trim();
+        for entry in fs::read_dir(&self.base_path)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("md") {
+                continue;
+            }
+            
+            let parsed = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| self.parse_markdown(&content));
+            if let Err(err) = parsed {
+                unreadable.push((path.display().to_string(), format!("{:#}", err)));
+            }
+        }
         
-        // Parse frontmatter
-        let mut id = None;
-        let mut title = String::new();
-        let mut status = crate::task::TaskStatus::Pending;
-        let mut priority = crate::task::Priority::Medium;
-        let mut complexity = None;
-        let mut dependencies = std::collections::HashSet::new();
-        let mut subtasks = Vec::new();
-        let mut tags = Vec::new();
-        let mut created_at = Utc::now();
-        let mut updated_at = Utc::now();
-        let mut completed_at = None;
+        unreadable.sort();
+        Ok(unreadable)
+    }
+
+    fn next_id(&self) -> Result<u32> {
+        let mut max_id = 0;
         
-        for line in frontmatter.lines() {
-            let parts: Vec<&str> = line.splitn(2, ':').collect();
-            if parts.len() != 2 {
+        for entry in fs::read_dir(&self.base_path)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("md") {
                 continue;
             }
             
-            let key = parts[0].trim();
-            let value = parts[1].trim();
-            
-            match key {
-                "id" => id = Some(value.parse::<u32>()?),
-                "title" => title = value.trim_matches('"').to_string(),
-                "status" => status = serde_json::from_str(&format!("\"{}\"", value))?,
-                "priority" => priority = serde_json::from_str(&format!("\"{}\"", value))?,
-                "complexity" => complexity = Some(serde_json::from_str(&format!("\"{}\"", value))?),
-                "dependencies" => {
-                    if !value.is_empty() && value != "[]" {
-                        let deps_str = value.trim_start_matches('[').trim_end_matches(']');
-                        for dep in deps_str.split(',') {
-                            if let Ok(dep_id) = dep.trim().parse::<u32>() {
-                                dependencies.insert(dep_id);
-                            }
-                        }
-                    }
-                },
-                "subtasks" => {
-                    if !value.is_empty() && value != "[]" {
-                        let subtasks_str = value.trim_start_matches('[').trim_end_matches(']');
-                        for subtask in subtasks_str.split(',') {
-                            if let Ok(subtask_id) = subtask.trim().parse::<u32>() {
-                                subtasks.push(subtask_id);
-                            }
-                        }
-                    }
-                },
-                "tags" => {
-                    if !value.is_empty() && value != "[]" {
-                        let tags_str = value.trim_start_matches('[').trim_end_matches(']');
-                        for tag in tags_str.split(',') {
-                            tags.push(tag.trim().trim_matches('"').to_string());
-                        }
-                    }
-                },
-                "created_at" => created_at = value.parse()?,
-                "updated_at" => updated_at = value.parse()?,
-                "completed_at" => completed_at = Some(value.parse()?),
-                _ => {}
+            match path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse::<u32>().ok()) {
+                Some(id) => max_id = max_id.max(id),
+                // Uid-named files carry their alias in the frontmatter
+                None => return Ok(self.load_all()?.iter().map(|t| t.id).max().unwrap_or(0) + 1),
             }
         }
         
-        let id = id.ok_or_else(|| anyhow::anyhow!("Missing task ID"))?;
+        Ok(max_id + 1)
+    }
+}
+
+/// The YAML frontmatter of a markdown task file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Frontmatter {
+    id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uid: Option<String>,
+    title: String,
+    status: TaskStatus,
+    priority: Priority,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    complexity: Option<Complexity>,
+    /// Stored as `sizing` so that `estimate` stays free for custom fields
+    #[serde(default, rename = "sizing", skip_serializing_if = "Option::is_none")]
+    estimate: Option<Estimate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<StoredRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    subtasks: Vec<StoredRef>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    relations: BTreeMap<RelationKind, Vec<StoredRef>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completed_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scheduled: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deferred_until: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wake_when: Option<WakeCondition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recurrence: Option<Recurrence>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    series: Option<StoredRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    time_log: Vec<TimeEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resolution: Option<String>,
+    /// Stored as `in_milestone` so that `milestone` stays free for custom fields
+    #[serde(default, rename = "in_milestone", skip_serializing_if = "Option::is_none")]
+    milestone: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<HistoryEvent>,
+    /// Any other keys, such as custom fields or keys added by other tools
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// A reference to another task as written in frontmatter: the target's uid
+/// when it has one, so references survive re-aliasing after a merge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredRef {
+    Id(u32),
+    Uid(String),
+}
+
+impl StoredRef {
+    fn new(id: u32, uids: &HashMap<u32, &str>) -> Self {
+        match uids.get(&id) {
+            Some(uid) => StoredRef::Uid(uid.to_string()),
+            None => StoredRef::Id(id),
+        }
+    }
+
+    /// The numeric ID this reference points at, if the target still exists
+    fn resolve(&self, aliases: &HashMap<String, u32>) -> Option<u32> {
+        match self {
+            StoredRef::Id(id) => Some(*id),
+            StoredRef::Uid(uid) => aliases.get(uid).copied(),
+        }
+    }
+}
+
+impl Frontmatter {
+    fn has_uid_refs(&self) -> bool {
+        self.dependencies.iter()
+            .chain(&self.subtasks)
+            .chain(&self.series)
+            .chain(self.relations.values().flatten())
+            .any(|r| matches!(r, StoredRef::Uid(_)))
+    }
+
+    fn new(task: &Task, uids: &HashMap<u32, &str>) -> Self {
+        // Sorted so that files are stable across saves and diff cleanly
+        let mut dependencies: Vec<u32> = task.dependencies.iter().copied().collect();
+        dependencies.sort_unstable();
+        let mut relations: BTreeMap<RelationKind, Vec<StoredRef>> = BTreeMap::new();
+        for relation in &task.relations {
+            relations.entry(relation.kind).or_default().push(StoredRef::new(relation.task, uids));
+        }
         
-        // Extract description from body (skip the title line)
-        let description = body.lines()
-            .skip_while(|line| line.starts_with('#') || line.is_empty())
-            .collect::<Vec<_>>()
-            .join("\n");
+        Self {
+            id: task.id,
+            uid: task.uid.clone(),
+            title: task.title.clone(),
+            status: task.status.clone(),
+            priority: task.priority.clone(),
+            complexity: task.complexity.clone(),
+            estimate: task.estimate.clone(),
+            dependencies: dependencies.into_iter().map(|id| StoredRef::new(id, uids)).collect(),
+            subtasks: task.subtasks.iter().map(|&id| StoredRef::new(id, uids)).collect(),
+            relations,
+            tags: task.tags.clone(),
+            created_at: task.created_at,
+            updated_at: task.updated_at,
+            completed_at: task.completed_at,
+            due: task.due,
+            scheduled: task.scheduled,
+            deferred_until: task.deferred_until,
+            wake_when: task.wake_when.clone(),
+            recurrence: task.recurrence.clone(),
+            series: task.series.map(|id| StoredRef::new(id, uids)),
+            time_log: task.time_log.clone(),
+            resolution: task.resolution.clone(),
+            milestone: task.milestone.clone(),
+            history: task.history.clone(),
+            extra: task.extra.clone(),
+        }
+    }
+
+    fn into_task(self, description: String, notes: Vec<Note>, aliases: &HashMap<String, u32>) -> Task {
+        // Sorted like `Task::add_relation` keeps them, in case the file was edited by hand
+        let mut relations: Vec<Relation> = self.relations.iter()
+            .flat_map(|(&kind, refs)| refs.iter().filter_map(|r| r.resolve(aliases)).map(move |task| Relation { kind, task }))
+            .collect();
+        relations.sort();
+        relations.dedup();
         
-        Ok(Task {
-            id,
-            title,
+        Task {
+            id: self.id,
+            uid: self.uid,
+            title: self.title,
             description,
-            status,
-            priority,
-            complexity,
-            dependencies,
-            subtasks,
-            created_at,
-            updated_at,
-            completed_at,
-            tags,
-        })
-    }
-}
\ No newline at end of file
+            notes,
+            status: self.status,
+            priority: self.priority,
+            complexity: self.complexity,
+            estimate: self.estimate,
+            dependencies: self.dependencies.iter().filter_map(|r| r.resolve(aliases)).collect::<HashSet<_>>(),
+            subtasks: self.subtasks.iter().filter_map(|r| r.resolve(aliases)).collect(),
+            relations,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            completed_at: self.completed_at,
+            due: self.due,
+            scheduled: self.scheduled,
+            deferred_until: self.deferred_until,
+            wake_when: self.wake_when,
+            recurrence: self.recurrence,
+            series: self.series.and_then(|r| r.resolve(aliases)),
+            time_log: self.time_log,
+            resolution: self.resolution,
+            milestone: self.milestone,
+            tags: self.tags,
+            extra: self.extra,
+            history: self.history,
+        }
+    }
+}
+
+/// Split a task file into its frontmatter and body.
+///
+/// Only the first `---` line after the opening delimiter closes the
+/// frontmatter, so separators inside the description are left alone.
+fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+    let rest = content.strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+    
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end_matches(['\r', '\n']) == "---" {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    
+    None
+}
+
+/// Re-quote a `title: "..."` line from the old hand-rolled writer, which only
+/// escaped double quotes. Returns `None` when there is nothing to fix.
+fn legacy_title_fixup(frontmatter: &str) -> Option<String> {
+    let mut fixed = false;
+    let lines: Vec<String> = frontmatter.lines().map(|line| {
+        let quoted = line.strip_prefix("title: \"").and_then(|v| v.strip_suffix('"'));
+        match quoted.and_then(|v| serde_yaml::to_string(&v.replace("\\\"", "\"")).ok()) {
+            Some(title) => {
+                fixed = true;
+                format!("title: {}", title.trim_end())
+            }
+            None => line.to_string(),
+        }
+    }).collect();
+    
+    fixed.then(|| lines.join("\n"))
+}
+
+/// Recover the description from the body written by `task_to_markdown`.
+///
+/// The body is a blank line, the `# title` heading, another blank line and
+/// then the description verbatim. Files edited by hand without the heading
+/// fall back to the body with leading blank lines removed.
+fn extract_description(body: &str) -> String {
+    let body = body.strip_prefix('\n').unwrap_or(body);
+    
+    if let Some(after_heading) = body.strip_prefix("# ") {
+        let after_heading = match after_heading.find('\n') {
+            Some(idx) => &after_heading[idx + 1..],
+            None => "",
+        };
+        return after_heading.strip_prefix('\n').unwrap_or(after_heading).to_string();
+    }
+    
+    body.trim_start_matches(['\r', '\n']).to_string()
+}
@@ -0,0 +1,130 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::{self, Metadata};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use super::{atomic_write, Frontmatter};
+use crate::graph::ReverseEdges;
+use crate::notes::Note;
+
+/// Bumped whenever the cached format changes, which discards older indexes
+const INDEX_VERSION: u32 = 4;
+
+/// Files modified this recently aren't cached: a second write within the
+/// filesystem's timestamp granularity could leave both size and mtime unchanged
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Parsed markdown task files, cached in `.trusty/index` so that reading a
+/// large project only parses the files that changed since the last run.
+///
+/// Entries are keyed by file name and dropped when the file's size or
+/// modification time changes, which catches edits made outside trusty (an
+/// editor, a `git checkout`) as well as trusty's own writes.
+///
+/// The reverse edges of the whole project (dependents, parents and inverse
+/// relations) are stored alongside, and dropped as soon as any file is parsed
+/// again or goes away.
+pub struct TaskIndex {
+    path: PathBuf,
+    /// Kept in memory after the first read, since one command often lists tasks several times
+    entries: RefCell<Option<BTreeMap<String, IndexEntry>>>,
+    edges: RefCell<Option<ReverseEdges>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    entries: BTreeMap<String, IndexEntry>,
+    #[serde(default)]
+    edges: Option<ReverseEdges>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    modified: SystemTime,
+    size: u64,
+    pub frontmatter: Frontmatter,
+    pub description: String,
+    pub notes: Vec<Note>,
+}
+
+impl IndexEntry {
+    pub fn new(metadata: &Metadata, frontmatter: Frontmatter, description: String, notes: Vec<Note>) -> Result<Self> {
+        Ok(Self { modified: metadata.modified()?, size: metadata.len(), frontmatter, description, notes })
+    }
+
+    /// Whether this entry still describes the file with `metadata`
+    pub fn is_fresh(&self, metadata: &Metadata) -> bool {
+        metadata.modified().ok() == Some(self.modified) && metadata.len() == self.size
+    }
+
+    /// Too recently modified to be trusted on the next read
+    pub fn is_racy(&self) -> bool {
+        SystemTime::now().duration_since(self.modified).map_or(true, |age| age < RACY_WINDOW)
+    }
+}
+
+impl TaskIndex {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, entries: RefCell::new(None), edges: RefCell::new(None) }
+    }
+
+    /// Take the cached entries. A missing, outdated or unreadable index is
+    /// just empty; it is only ever a cache of the task files.
+    pub fn take(&self) -> BTreeMap<String, IndexEntry> {
+        if let Some(entries) = self.entries.borrow_mut().take() {
+            return entries;
+        }
+
+        let index = fs::read(&self.path).ok()
+            .and_then(|content| serde_json::from_slice::<IndexFile>(&content).ok())
+            .filter(|index| index.version == INDEX_VERSION);
+        let Some(index) = index else {
+            *self.edges.borrow_mut() = None;
+            return BTreeMap::new();
+        };
+        *self.edges.borrow_mut() = index.edges;
+        index.entries
+    }
+
+    /// Put entries back after a read, writing them to disk if any changed.
+    /// `reparsed` says whether any file was read again or deleted, which
+    /// makes the stored reverse edges stale.
+    pub fn store(&self, mut entries: BTreeMap<String, IndexEntry>, changed: bool, reparsed: bool) -> Result<()> {
+        entries.retain(|_, entry| !entry.is_racy());
+        *self.entries.borrow_mut() = Some(entries);
+
+        if reparsed {
+            *self.edges.borrow_mut() = None;
+        }
+        if changed {
+            self.write()?;
+        }
+        Ok(())
+    }
+
+    /// The reverse edges of the tasks last read, unless a file changed since
+    pub fn edges(&self) -> Option<ReverseEdges> {
+        self.edges.borrow().clone()
+    }
+
+    /// Remember the reverse edges of the tasks last read
+    pub fn store_edges(&self, edges: ReverseEdges) -> Result<()> {
+        *self.edges.borrow_mut() = Some(edges);
+        self.write()
+    }
+
+    fn write(&self) -> Result<()> {
+        let index = IndexFile {
+            version: INDEX_VERSION,
+            entries: self.entries.borrow_mut().take().unwrap_or_default(),
+            edges: self.edges.borrow().clone(),
+        };
+        let written = atomic_write(&self.path, &serde_json::to_vec(&index)?);
+        *self.entries.borrow_mut() = Some(index.entries);
+        written
+    }
+}
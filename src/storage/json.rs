@@ -0,0 +1,87 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+use super::{atomic_write, TaskStore};
+use crate::task::Task;
+
+/// JSON backend: every task in a single `tasks.json` array, sorted by ID.
+///
+/// Listing reads one file instead of one file per task, which keeps large
+/// projects fast at the cost of noisier diffs.
+pub struct JsonStore {
+    path: PathBuf,
+}
+
+impl JsonStore {
+    pub fn new(path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create task directory: {:?}", parent))?;
+        }
+        
+        Ok(Self { path })
+    }
+
+    fn read_tasks(&self) -> Result<Vec<Task>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read task file: {:?}", self.path))?;
+        
+        if content.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse task file: {:?}", self.path))
+    }
+
+    fn write_tasks(&self, tasks: &mut [Task]) -> Result<()> {
+        tasks.sort_by_key(|t| t.id);
+        let content = serde_json::to_string_pretty(tasks)?;
+        
+        atomic_write(&self.path, content.as_bytes())
+            .with_context(|| format!("Failed to write task file: {:?}", self.path))
+    }
+}
+
+impl TaskStore for JsonStore {
+    fn load_task(&self, id: u32) -> Result<Task> {
+        self.read_tasks()?
+            .into_iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Task #{} not found in {:?}", id, self.path))
+    }
+
+    fn save_task(&self, task: &Task) -> Result<()> {
+        let mut tasks = self.read_tasks()?;
+        
+        match tasks.iter_mut().find(|t| t.id == task.id) {
+            Some(existing) => *existing = task.clone(),
+            None => tasks.push(task.clone()),
+        }
+        
+        self.write_tasks(&mut tasks)
+    }
+
+    fn delete_task(&self, id: u32) -> Result<()> {
+        let mut tasks = self.read_tasks()?;
+        let initial_count = tasks.len();
+        tasks.retain(|t| t.id != id);
+        
+        if tasks.len() == initial_count {
+            anyhow::bail!("Task #{} not found in {:?}", id, self.path);
+        }
+        
+        self.write_tasks(&mut tasks)
+    }
+
+    fn list_all_tasks(&self) -> Result<Vec<Task>> {
+        let mut tasks = self.read_tasks()?;
+        tasks.sort_by_key(|t| t.id);
+        Ok(tasks)
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use std::cell::Cell;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::TaskStore;
+use crate::config::IdScheme;
+use crate::graph::TaskGraph;
+use crate::task::{generate_uid, Task};
+
+const LOCK_FILE: &str = "lock";
+const ID_COUNTER_FILE: &str = "next_id";
+
+/// Replace `path` with `contents` without readers ever seeing a partial file.
+///
+/// The data goes to a temporary file in the same directory, is synced, and
+/// is then renamed over the target, which is atomic on the same filesystem.
+pub fn atomic_write(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path.file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file path: {:?}", path))?;
+    let tmp_path = dir.join(format!(".{}.{}.tmp", file_name.to_string_lossy(), std::process::id()));
+
+    let result = (|| -> Result<()> {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    result.with_context(|| format!("Failed to write file: {:?}", path))
+}
+
+/// Advisory lock on `.trusty/lock`, shared by every `trusty` process in the project.
+///
+/// The lock is reentrant within a process: nested `acquire` calls just bump
+/// a counter, and the file lock is released when the outermost guard drops.
+pub struct ProjectLock {
+    file: File,
+    path: PathBuf,
+    depth: Cell<usize>,
+}
+
+impl ProjectLock {
+    pub fn open(trusty_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(trusty_dir)
+            .with_context(|| format!("Failed to create directory: {:?}", trusty_dir))?;
+
+        let path = trusty_dir.join(LOCK_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file: {:?}", path))?;
+
+        Ok(Self { file, path, depth: Cell::new(0) })
+    }
+
+    /// Block until this process holds the lock
+    pub fn acquire(&self) -> Result<LockGuard<'_>> {
+        if self.depth.get() == 0 {
+            self.file.lock()
+                .with_context(|| format!("Failed to lock {:?}", self.path))?;
+        }
+        self.depth.set(self.depth.get() + 1);
+
+        Ok(LockGuard { lock: Some(self) })
+    }
+}
+
+/// Keeps a [`ProjectLock`] held until dropped
+pub struct LockGuard<'a> {
+    lock: Option<&'a ProjectLock>,
+}
+
+impl LockGuard<'_> {
+    /// A guard for stores that are not shared between processes
+    pub fn unlocked() -> Self {
+        Self { lock: None }
+    }
+}
+
+impl Drop for LockGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(lock) = self.lock {
+            let depth = lock.depth.get() - 1;
+            lock.depth.set(depth);
+            if depth == 0 {
+                let _ = lock.file.unlock();
+            }
+        }
+    }
+}
+
+/// Wraps a backend so that every write holds the project lock, and hands out
+/// IDs from a counter persisted in `.trusty/next_id`. New tasks get a uid
+/// when the project uses the `ulid` ID scheme.
+///
+/// Reads are not locked: backends replace files atomically, so a reader sees
+/// either the old or the new version of a task.
+pub struct LockedStore {
+    inner: Box<dyn TaskStore>,
+    lock: ProjectLock,
+    counter_path: PathBuf,
+    id_scheme: IdScheme,
+}
+
+impl LockedStore {
+    pub fn new(inner: Box<dyn TaskStore>, trusty_dir: &Path, id_scheme: IdScheme) -> Result<Self> {
+        Ok(Self {
+            inner,
+            lock: ProjectLock::open(trusty_dir)?,
+            counter_path: trusty_dir.join(ID_COUNTER_FILE),
+            id_scheme,
+        })
+    }
+
+    fn read_counter(&self) -> Result<u32> {
+        if !self.counter_path.exists() {
+            return Ok(0);
+        }
+
+        let content = fs::read_to_string(&self.counter_path)
+            .with_context(|| format!("Failed to read ID counter: {:?}", self.counter_path))?;
+        content.trim().parse()
+            .with_context(|| format!("Invalid ID counter in {:?}", self.counter_path))
+    }
+}
+
+impl TaskStore for LockedStore {
+    fn load_task(&self, id: u32) -> Result<Task> {
+        self.inner.load_task(id)
+    }
+
+    fn save_task(&self, task: &Task) -> Result<()> {
+        let _lock = self.lock.acquire()?;
+        let mut task = task.clone();
+        
+        if self.id_scheme == IdScheme::Ulid && task.uid.is_none() {
+            task.uid = Some(generate_uid());
+        }
+        
+        self.inner.save_task(&task)
+    }
+
+    fn delete_task(&self, id: u32) -> Result<()> {
+        let _lock = self.lock.acquire()?;
+        self.inner.delete_task(id)
+    }
+
+    fn list_all_tasks(&self) -> Result<Vec<Task>> {
+        self.inner.list_all_tasks()
+    }
+
+    fn load_graph(&self) -> Result<TaskGraph> {
+        self.inner.load_graph()
+    }
+
+    fn unreadable(&self) -> Result<Vec<(String, String)>> {
+        self.inner.unreadable()
+    }
+
+    fn next_id(&self) -> Result<u32> {
+        Ok(self.read_counter()?.max(self.inner.next_id()?))
+    }
+
+    fn allocate_id(&self) -> Result<u32> {
+        let _lock = self.lock.acquire()?;
+
+        // Tasks created before the counter existed still push it forward
+        let id = self.next_id()?;
+        atomic_write(&self.counter_path, format!("{}\n", id + 1).as_bytes())?;
+
+        Ok(id)
+    }
+
+    fn lock(&self) -> Result<LockGuard<'_>> {
+        self.lock.acquire()
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_yaml::{Mapping, Value};
+
+use super::{extract_description, split_frontmatter};
+use crate::notes::{self, Note};
+
+/// Frontmatter keys holding sets of values, merged item by item
+const SET_KEYS: &[&str] = &["dependencies", "subtasks", "tags"];
+
+/// Result of a three-way merge of one task file
+pub struct MergeOutcome {
+    pub content: String,
+    /// True when the descriptions diverged and conflict markers were written
+    pub conflicted: bool,
+}
+
+/// A task file split into its frontmatter mapping, description and notes
+struct Side {
+    frontmatter: Mapping,
+    description: String,
+    notes: Vec<Note>,
+}
+
+impl Side {
+    fn parse(content: &str, label: &str) -> Result<Self> {
+        // Git passes an empty ancestor when both branches added the file
+        if content.trim().is_empty() {
+            return Ok(Self { frontmatter: Mapping::new(), description: String::new(), notes: Vec::new() });
+        }
+
+        let (frontmatter, body) = split_frontmatter(content)
+            .ok_or_else(|| anyhow::anyhow!("Invalid task file ({}): missing frontmatter", label))?;
+        let frontmatter = serde_yaml::from_str(frontmatter)
+            .with_context(|| format!("Invalid task frontmatter ({})", label))?;
+
+        let (description, notes) = notes::split_body(&extract_description(body));
+        Ok(Self { frontmatter, description, notes })
+    }
+
+    fn updated_at(&self) -> Option<DateTime<Utc>> {
+        self.frontmatter.get("updated_at")?.as_str()?.parse().ok()
+    }
+}
+
+/// Three-way merge of a markdown task file, field by field.
+///
+/// - `dependencies`, `subtasks` and `tags`, and each kind of `relations`,
+///   take the union of both sides' additions; an item removed on either
+///   side stays removed.
+/// - Other keys changed on only one side take that side's value. Keys
+///   changed on both sides take the value from the more recently updated
+///   side (by `updated_at`), preferring ours on a tie.
+/// - `history` and notes keep the entries from both sides, ordered by time.
+/// - Descriptions changed on both sides get conflict markers.
+pub fn merge_task_files(base: &str, ours: &str, theirs: &str) -> Result<MergeOutcome> {
+    let base = Side::parse(base, "ancestor")?;
+    let ours = Side::parse(ours, "ours")?;
+    let theirs = Side::parse(theirs, "theirs")?;
+    let theirs_newer = match (ours.updated_at(), theirs.updated_at()) {
+        (Some(ours), Some(theirs)) => theirs > ours,
+        _ => false,
+    };
+
+    // Keep our key order so the merged file diffs cleanly against ours
+    let mut keys: Vec<&Value> = ours.frontmatter.keys().collect();
+    keys.extend(theirs.frontmatter.keys().filter(|k| !ours.frontmatter.contains_key(*k)));
+
+    let mut merged = Mapping::new();
+    for key in keys {
+        let (b, o, t) = (base.frontmatter.get(key), ours.frontmatter.get(key), theirs.frontmatter.get(key));
+
+        let value = match key.as_str() {
+            Some(name) if SET_KEYS.contains(&name) => merge_set(b, o, t),
+            Some("relations") => merge_set_map(b, o, t),
+            Some("history") => merge_history(o, t),
+            Some("updated_at") => if theirs_newer { t.cloned() } else { o.cloned() },
+            _ => merge_scalar(b, o, t, theirs_newer),
+        };
+
+        if let Some(value) = value {
+            merged.insert(key.clone(), value);
+        }
+    }
+
+    let (description, conflicted) = merge_description(&base.description, &ours.description, &theirs.description);
+    let notes = merge_notes(ours.notes, theirs.notes);
+    let title = merged.get("title").and_then(Value::as_str).unwrap_or_default().replace(['\r', '\n'], " ");
+
+    let mut content = String::new();
+    content.push_str("---\n");
+    content.push_str(&serde_yaml::to_string(&merged)?);
+    content.push_str("---\n\n");
+    content.push_str(&format!("# {}\n\n", title));
+    content.push_str(&notes::render_body(&description, &notes));
+
+    Ok(MergeOutcome { content, conflicted })
+}
+
+fn merge_scalar(base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>, theirs_newer: bool) -> Option<Value> {
+    if ours == theirs || theirs == base {
+        ours.cloned()
+    } else if ours == base || theirs_newer {
+        theirs.cloned()
+    } else {
+        ours.cloned()
+    }
+}
+
+fn merge_set(base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>) -> Option<Value> {
+    let items = |v: Option<&Value>| v.and_then(Value::as_sequence).cloned().unwrap_or_default();
+    let (base, ours, theirs) = (items(base), items(ours), items(theirs));
+    // A side that left the list alone takes the other side's, order included
+    if theirs == base {
+        return (!ours.is_empty()).then_some(Value::Sequence(ours));
+    }
+    if ours == base {
+        return (!theirs.is_empty()).then_some(Value::Sequence(theirs));
+    }
+    let removed = |item: &Value| base.contains(item) && !(ours.contains(item) && theirs.contains(item));
+
+    let mut merged: Vec<Value> = Vec::new();
+    for item in ours.iter().chain(&theirs) {
+        if !removed(item) && !merged.contains(item) {
+            merged.push(item.clone());
+        }
+    }
+
+    (!merged.is_empty()).then_some(Value::Sequence(merged))
+}
+
+/// A mapping of sets, such as `relations` by kind, merged set by set
+fn merge_set_map(base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>) -> Option<Value> {
+    let map = |v: Option<&Value>| v.and_then(Value::as_mapping).cloned().unwrap_or_default();
+    let (base, ours, theirs) = (map(base), map(ours), map(theirs));
+
+    let mut merged = Mapping::new();
+    for key in ours.keys().chain(theirs.keys()) {
+        if merged.contains_key(key) {
+            continue;
+        }
+        if let Some(set) = merge_set(base.get(key), ours.get(key), theirs.get(key)) {
+            merged.insert(key.clone(), set);
+        }
+    }
+
+    (!merged.is_empty()).then_some(Value::Mapping(merged))
+}
+
+/// History is append-only, so keep every event from both sides in time order
+fn merge_history(ours: Option<&Value>, theirs: Option<&Value>) -> Option<Value> {
+    let items = |v: Option<&Value>| v.and_then(Value::as_sequence).cloned().unwrap_or_default();
+
+    let mut merged = items(ours);
+    for event in items(theirs) {
+        if !merged.contains(&event) {
+            merged.push(event);
+        }
+    }
+    merged.sort_by_key(|event| event.get("at").and_then(Value::as_str).and_then(|at| at.parse::<DateTime<Utc>>().ok()));
+
+    (!merged.is_empty()).then_some(Value::Sequence(merged))
+}
+
+/// Notes are append-only too
+fn merge_notes(ours: Vec<Note>, theirs: Vec<Note>) -> Vec<Note> {
+    let mut merged = ours;
+    for note in theirs {
+        if !merged.contains(&note) {
+            merged.push(note);
+        }
+    }
+    merged.sort_by_key(|note| note.at);
+    merged
+}
+
+fn merge_description(base: &str, ours: &str, theirs: &str) -> (String, bool) {
+    if ours == theirs || theirs == base {
+        return (ours.to_string(), false);
+    }
+    if ours == base {
+        return (theirs.to_string(), false);
+    }
+
+    let with_newline = |s: &str| if s.is_empty() || s.ends_with('\n') { s.to_string() } else { format!("{}\n", s) };
+    let content = format!(
+        "<<<<<<< ours\n{}=======\n{}>>>>>>> theirs\n",
+        with_newline(ours),
+        with_newline(theirs)
+    );
+
+    (content, true)
+}
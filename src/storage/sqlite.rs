@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::path::PathBuf;
+
+use super::TaskStore;
+use crate::task::Task;
+
+/// SQLite backend: tasks stored as JSON documents keyed by ID in an embedded database
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    pub fn new(path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create task directory: {:?}", parent))?;
+        }
+        
+        let conn = Connection::open(&path)
+            .with_context(|| format!("Failed to open task database: {:?}", path))?;
+        
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS tasks (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL
+            )",
+            [],
+        )?;
+        
+        Ok(Self { conn })
+    }
+}
+
+impl TaskStore for SqliteStore {
+    fn load_task(&self, id: u32) -> Result<Task> {
+        let data: Option<String> = self.conn
+            .query_row("SELECT data FROM tasks WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?;
+        
+        let data = data.ok_or_else(|| anyhow::anyhow!("Task #{} not found", id))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse stored task #{}", id))
+    }
+
+    fn save_task(&self, task: &Task) -> Result<()> {
+        let data = serde_json::to_string(task)?;
+        self.conn.execute(
+            "INSERT INTO tasks (id, data) VALUES (?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET data = excluded.data",
+            params![task.id, data],
+        )?;
+        
+        Ok(())
+    }
+
+    fn delete_task(&self, id: u32) -> Result<()> {
+        let deleted = self.conn.execute("DELETE FROM tasks WHERE id = ?1", params![id])?;
+        if deleted == 0 {
+            anyhow::bail!("Task #{} not found", id);
+        }
+        
+        Ok(())
+    }
+
+    fn list_all_tasks(&self) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare("SELECT data FROM tasks ORDER BY id")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        
+        let mut tasks = Vec::new();
+        for data in rows {
+            tasks.push(serde_json::from_str(&data?)?);
+        }
+        
+        Ok(tasks)
+    }
+
+    fn next_id(&self) -> Result<u32> {
+        let max: Option<u32> = self.conn.query_row("SELECT MAX(id) FROM tasks", [], |row| row.get(0))?;
+        Ok(max.unwrap_or(0) + 1)
+    }
+}
@@ -1,6 +1,18 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::checklist::{self, ChecklistItem};
+use crate::defer::WakeCondition;
+use crate::estimate::Estimate;
+use crate::graph::TaskGraph;
+use crate::history::{HistoryChange, HistoryEvent};
+use crate::notes::Note;
+use crate::recur::Recurrence;
+use crate::relation::{Relation, RelationKind};
+use crate::timelog::TimeEntry;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "kebab-case")]
@@ -11,6 +23,44 @@ pub enum TaskStatus {
     Blocked,
     Deferred,
     Cancelled,
+    /// A status declared in the project's workflow. Counts as work in
+    /// progress wherever trusty groups statuses.
+    #[serde(untagged)]
+    Custom(String),
+}
+
+impl TaskStatus {
+    pub const BUILT_IN: &'static [TaskStatus] = &[
+        TaskStatus::Pending,
+        TaskStatus::InProgress,
+        TaskStatus::Done,
+        TaskStatus::Blocked,
+        TaskStatus::Deferred,
+        TaskStatus::Cancelled,
+    ];
+
+    /// The status as written in task files and on the command line
+    pub fn name(&self) -> &str {
+        match self {
+            TaskStatus::Pending => "pending",
+            TaskStatus::InProgress => "in-progress",
+            TaskStatus::Done => "done",
+            TaskStatus::Blocked => "blocked",
+            TaskStatus::Deferred => "deferred",
+            TaskStatus::Cancelled => "cancelled",
+            TaskStatus::Custom(name) => name,
+        }
+    }
+
+    /// In progress or in one of the workflow's own statuses
+    pub fn is_active(&self) -> bool {
+        matches!(self, TaskStatus::InProgress | TaskStatus::Custom(_))
+    }
+
+    /// Done or cancelled
+    pub fn is_closed(&self) -> bool {
+        matches!(self, TaskStatus::Done | TaskStatus::Cancelled)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -29,20 +79,112 @@ pub enum Complexity {
     Complex,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Task {
+    /// Numeric ID, shown as `#id`. In projects using the `ulid` ID scheme this
+    /// is a local alias and `uid` is the identity stored on disk.
     pub id: u32,
+    /// Branch-safe identifier, assigned when the project uses `id_scheme: ulid`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
     pub title: String,
     pub description: String,
+    /// Comments appended with `trusty note`, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<Note>,
     pub status: TaskStatus,
     pub priority: Priority,
     pub complexity: Option<Complexity>,
+    /// Size in points or hours
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<Estimate>,
     pub dependencies: HashSet<u32>,
     pub subtasks: Vec<u32>,
+    /// Typed links to other tasks, sorted. Only this side is stored; the
+    /// other task sees the inverse through [`TaskGraph::relations`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relations: Vec<Relation>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
+    /// The date the task has to be done by
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+    /// The date work is planned to start; `next` doesn't suggest the task before then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduled: Option<NaiveDate>,
+    /// Set aside until this date; `next` doesn't suggest the task before then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deferred_until: Option<NaiveDate>,
+    /// Brings a deferred task back once met, like `deferred_until` does on its date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wake_when: Option<WakeCondition>,
+    /// Completing the task creates the next occurrence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Recurrence>,
+    /// The first task of the recurring series this task was created from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series: Option<u32>,
+    /// Time spent on the task
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time_log: Vec<TimeEntry>,
+    /// Why the task was closed, when the workflow asks for one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+    /// Name of the milestone the task is planned for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub milestone: Option<String>,
     pub tags: Vec<String>,
+    /// Frontmatter keys trusty doesn't model itself, including custom fields
+    /// from the project schema. Kept verbatim so saving never drops them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, serde_yaml::Value>,
+    /// Append-only audit trail, maintained by the store on every save
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<HistoryEvent>,
+}
+
+/// Generate a short uid: the 10 random characters (50 bits) of a fresh ULID.
+///
+/// Tasks created on different branches get different uids, so merging them
+/// never collides the way two new `7.md` files would.
+pub fn generate_uid() -> String {
+    let ulid = ulid::Ulid::new().to_string().to_lowercase();
+    ulid[ulid.len() - 10..].to_string()
+}
+
+/// A task as named on the command line: `7`, `#7`, or a unique uid prefix
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskRef {
+    Id(u32),
+    Uid(String),
+}
+
+impl FromStr for TaskRef {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        let digits = s.strip_prefix('#').unwrap_or(s);
+
+        if let Ok(id) = digits.parse::<u32>() {
+            return Ok(TaskRef::Id(id));
+        }
+        if s.len() >= 4 && s.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Ok(TaskRef::Uid(s.to_lowercase()));
+        }
+
+        anyhow::bail!("Invalid task reference '{}'. Use a task ID like 7 or at least 4 characters of its uid", s)
+    }
+}
+
+impl fmt::Display for TaskRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskRef::Id(id) => write!(f, "#{}", id),
+            TaskRef::Uid(uid) => write!(f, "{}", uid),
+        }
+    }
 }
 
 impl Task {
@@ -50,17 +192,32 @@ impl Task {
         let now = Utc::now();
         Self {
             id,
+            uid: None,
             title,
             description,
+            notes: Vec::new(),
             status: TaskStatus::Pending,
             priority,
             complexity: None,
+            estimate: None,
             dependencies: HashSet::new(),
             subtasks: Vec::new(),
+            relations: Vec::new(),
             created_at: now,
             updated_at: now,
             completed_at: None,
+            due: None,
+            scheduled: None,
+            deferred_until: None,
+            wake_when: None,
+            recurrence: None,
+            series: None,
+            time_log: Vec::new(),
+            resolution: None,
+            milestone: None,
             tags: Vec::new(),
+            extra: BTreeMap::new(),
+            history: Vec::new(),
         }
     }
 
@@ -71,6 +228,9 @@ impl Task {
         if status == TaskStatus::Done {
             self.completed_at = Some(Utc::now());
         }
+        if status != TaskStatus::Deferred {
+            self.wake_when = None;
+        }
     }
 
     pub fn add_dependency(&mut self, dep_id: u32) {
@@ -88,6 +248,76 @@ impl Task {
         self.updated_at = Utc::now();
     }
 
+    /// Link to task `id`. Returns false if the link already exists.
+    pub fn add_relation(&mut self, kind: RelationKind, id: u32) -> bool {
+        let relation = Relation { kind, task: id };
+        let Err(position) = self.relations.binary_search(&relation) else {
+            return false;
+        };
+        self.relations.insert(position, relation);
+        self.updated_at = Utc::now();
+        true
+    }
+
+    /// Returns false if there was no such link
+    pub fn remove_relation(&mut self, kind: RelationKind, id: u32) -> bool {
+        let before = self.relations.len();
+        self.relations.retain(|r| *r != Relation { kind, task: id });
+        let removed = self.relations.len() != before;
+        if removed {
+            self.updated_at = Utc::now();
+        }
+        removed
+    }
+
+    /// Past its due date and not yet done or cancelled
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.status.is_closed() && self.due.is_some_and(|due| due < today)
+    }
+
+    /// Open and due within `days`, but not overdue
+    pub fn is_due_within(&self, days: i64, today: NaiveDate) -> bool {
+        !self.status.is_closed() && self.due.is_some_and(|due| due >= today && (due - today).num_days() <= days)
+    }
+
+    /// Scheduled to start, or deferred until, a later date
+    pub fn is_waiting(&self, today: NaiveDate) -> bool {
+        self.scheduled.is_some_and(|date| date > today) || self.deferred_until.is_some_and(|date| date > today)
+    }
+
+    /// The `- [ ]` items in the description
+    pub fn checklist(&self) -> Vec<ChecklistItem> {
+        checklist::parse(&self.description)
+    }
+
+    /// Checked items out of all items, or `None` without a checklist
+    pub fn checklist_progress(&self) -> Option<(usize, usize)> {
+        let items = self.checklist();
+        (!items.is_empty()).then(|| (items.iter().filter(|item| item.checked).count(), items.len()))
+    }
+
+    /// Minutes logged on this task alone
+    pub fn logged_minutes(&self) -> u32 {
+        self.time_log.iter().map(|entry| entry.minutes).sum()
+    }
+
+    /// When the task entered its current status.
+    ///
+    /// Falls back to `completed_at` or `updated_at` for tasks saved before
+    /// history was recorded.
+    pub fn status_since(&self) -> DateTime<Utc> {
+        let entered = self.history.iter().rev().find_map(|event| match &event.change {
+            HistoryChange::Status { to, .. } if *to == self.status => Some(event.at),
+            HistoryChange::Created { status } if *status == self.status => Some(event.at),
+            _ => None,
+        });
+
+        entered.unwrap_or_else(|| match self.status {
+            TaskStatus::Done => self.completed_at.unwrap_or(self.updated_at),
+            _ => self.updated_at,
+        })
+    }
+
     pub fn is_ready(&self, completed_tasks: &HashSet<u32>) -> bool {
         match self.status {
             TaskStatus::Pending => self.dependencies.is_subset(completed_tasks),
@@ -95,46 +325,14 @@ impl Task {
         }
     }
 
+    /// Status rolled up from subtasks. When asking for many tasks, build one
+    /// [`TaskGraph`] and use [`TaskGraph::effective_status`] instead.
     pub fn compute_effective_status(&self, all_tasks: &[Task]) -> TaskStatus {
-        if self.subtasks.is_empty() {
-            return self.status.clone();
-        }
-        
-        let subtask_statuses: Vec<_> = self.subtasks.iter()
-            .filter_map(|&id| all_tasks.iter().find(|t| t.id == id))
-            .map(|t| t.compute_effective_status(all_tasks))
-            .collect();
-        
-        if subtask_statuses.is_empty() {
-            return self.status.clone();
-        }
-        
-        // If all subtasks are done, task is effectively done
-        if subtask_statuses.iter().all(|s| matches!(s, TaskStatus::Done)) {
-            return TaskStatus::Done;
-        }
-        
-        // If any subtask is in progress, task is effectively in progress
-        if subtask_statuses.iter().any(|s| matches!(s, TaskStatus::InProgress)) {
-            return TaskStatus::InProgress;
-        }
-        
-        // If any subtask is blocked, task is effectively blocked
-        if subtask_statuses.iter().any(|s| matches!(s, TaskStatus::Blocked)) {
-            return TaskStatus::Blocked;
-        }
-        
-        // Otherwise, use the stored status
-        self.status.clone()
+        TaskGraph::new(all_tasks.to_vec()).effective_status(self)
     }
     
     pub fn subtask_progress(&self, all_tasks: &[Task]) -> (usize, usize) {
-        let total = self.subtasks.len();
-        let completed = self.subtasks.iter()
-            .filter_map(|&id| all_tasks.iter().find(|t| t.id == id))
-            .filter(|t| t.compute_effective_status(all_tasks) == TaskStatus::Done)
-            .count();
-        (completed, total)
+        TaskGraph::new(all_tasks.to_vec()).progress(self)
     }
 }
 
@@ -147,6 +345,33 @@ impl std::fmt::Display for TaskStatus {
             TaskStatus::Blocked => write!(f, "◻ blocked"),
             TaskStatus::Deferred => write!(f, "◇ deferred"),
             TaskStatus::Cancelled => write!(f, "◈ cancelled"),
+            TaskStatus::Custom(name) => write!(f, "◆ {}", name),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "high" => Ok(Priority::High),
+            "medium" => Ok(Priority::Medium),
+            "low" => Ok(Priority::Low),
+            _ => anyhow::bail!("Invalid priority: {}. Use high, medium, or low", s),
+        }
+    }
+}
+
+impl FromStr for Complexity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "simple" => Ok(Complexity::Simple),
+            "medium" => Ok(Complexity::Medium),
+            "complex" => Ok(Complexity::Complex),
+            _ => anyhow::bail!("Invalid complexity: {}. Use simple, medium, or complex", s),
         }
     }
 }
@@ -371,4 +596,17 @@ mod tests {
         assert_eq!(completed, 2);
         assert_eq!(total, 3);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_task_ref_parsing() {
+        assert_eq!("7".parse::<TaskRef>().unwrap(), TaskRef::Id(7));
+        assert_eq!("#7".parse::<TaskRef>().unwrap(), TaskRef::Id(7));
+        assert_eq!("01HZK3".parse::<TaskRef>().unwrap(), TaskRef::Uid("01hzk3".to_string()));
+        assert!("ab".parse::<TaskRef>().is_err());
+        assert!("#x-1".parse::<TaskRef>().is_err());
+        
+        let uid = generate_uid();
+        assert_eq!(uid.len(), 10);
+        assert_ne!(uid, generate_uid());
+    }
+}
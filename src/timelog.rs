@@ -0,0 +1,215 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::history;
+use crate::storage::{atomic_write, TaskStore};
+use crate::task::Task;
+
+const TIMERS_FILE: &str = "timers";
+
+/// Time spent on a task, from a timer or logged by hand
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimeEntry {
+    pub started_at: DateTime<Utc>,
+    pub minutes: u32,
+    pub actor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// A running timer
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Timer {
+    pub task: u32,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Running timers in `.trusty/timers`, at most one per actor
+pub struct Timers {
+    path: PathBuf,
+}
+
+impl Timers {
+    pub fn new(trusty_dir: &Path) -> Self {
+        Self { path: trusty_dir.join(TIMERS_FILE) }
+    }
+
+    fn load(&self) -> Result<BTreeMap<String, Timer>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read timers: {:?}", self.path))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse timers: {:?}", self.path))
+    }
+
+    fn write(&self, timers: &BTreeMap<String, Timer>) -> Result<()> {
+        atomic_write(&self.path, serde_json::to_string_pretty(timers)?.as_bytes())
+    }
+
+    /// The current actor's running timer
+    pub fn current(&self) -> Result<Option<Timer>> {
+        Ok(self.load()?.remove(&history::current_actor()))
+    }
+
+    /// Start the current actor's timer on task `id`. A timer already running
+    /// on another task is stopped and logged first, and returned.
+    pub fn start(&self, storage: &dyn TaskStore, id: u32) -> Result<Option<(Task, TimeEntry)>> {
+        let _lock = storage.lock()?;
+        storage.load_task(id)?;
+
+        let stopped = match self.current()? {
+            Some(timer) if timer.task == id => return Ok(None),
+            Some(_) => self.stop(storage)?,
+            None => None,
+        };
+
+        let mut timers = self.load()?;
+        timers.insert(history::current_actor(), Timer { task: id, started_at: Utc::now() });
+        self.write(&timers)?;
+        Ok(stopped)
+    }
+
+    /// Stop the current actor's timer and log the time on its task
+    pub fn stop(&self, storage: &dyn TaskStore) -> Result<Option<(Task, TimeEntry)>> {
+        let _lock = storage.lock()?;
+        let mut timers = self.load()?;
+        let Some(timer) = timers.remove(&history::current_actor()) else {
+            return Ok(None);
+        };
+        self.write(&timers)?;
+
+        let mut task = storage.load_task(timer.task)
+            .with_context(|| format!("Discarded the timer on task #{}", timer.task))?;
+        // Rounded to the nearest minute, but a stopped timer always logs something
+        let minutes = ((Utc::now() - timer.started_at).num_seconds() + 30) / 60;
+        let entry = TimeEntry {
+            started_at: timer.started_at,
+            minutes: minutes.clamp(1, u32::MAX.into()) as u32,
+            actor: history::current_actor(),
+            note: None,
+        };
+        task.time_log.push(entry.clone());
+        storage.save_task(&task)?;
+        Ok(Some((task, entry)))
+    }
+}
+
+/// Parse a duration such as `45m`, `2h`, `1h30m` or `1.5h` into minutes. A
+/// bare number is minutes.
+pub fn parse_duration(input: &str) -> Result<u32> {
+    let text = input.trim().to_lowercase().replace(' ', "");
+    let invalid = || anyhow::anyhow!("Invalid duration '{}'. Use e.g. 45m, 2h, 1h30m or 1.5h", input);
+
+    if let Ok(minutes) = text.parse::<u32>() {
+        return (minutes > 0).then_some(minutes).ok_or_else(invalid);
+    }
+
+    let mut minutes = 0.0;
+    let mut number = String::new();
+    for c in text.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'h' | 'm' => {
+                let value: f64 = number.parse().map_err(|_| invalid())?;
+                minutes += if c == 'h' { value * 60.0 } else { value };
+                number.clear();
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    if !number.is_empty() || minutes < 0.5 {
+        return Err(invalid());
+    }
+    Ok(minutes.round() as u32)
+}
+
+/// `45m`, `2h`, `1h 30m`
+pub fn format_minutes(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// Logged time between two dates, inclusive
+#[derive(Debug, Default, PartialEq)]
+pub struct Timesheet {
+    pub by_day: BTreeMap<NaiveDate, u32>,
+    /// A task with several tags counts towards each of them
+    pub by_tag: BTreeMap<String, u32>,
+    pub total: u32,
+}
+
+/// Sum the time logged on `tasks` in `from..=to`, by the local date it was spent
+pub fn timesheet(tasks: &[Task], from: NaiveDate, to: NaiveDate) -> Timesheet {
+    let mut sheet = Timesheet::default();
+    for task in tasks {
+        for entry in &task.time_log {
+            let day = entry.started_at.with_timezone(&Local).date_naive();
+            if day < from || day > to {
+                continue;
+            }
+
+            *sheet.by_day.entry(day).or_default() += entry.minutes;
+            sheet.total += entry.minutes;
+            if task.tags.is_empty() {
+                *sheet.by_tag.entry("(untagged)".to_string()).or_default() += entry.minutes;
+            }
+            for tag in &task.tags {
+                *sheet.by_tag.entry(tag.clone()).or_default() += entry.minutes;
+            }
+        }
+    }
+    sheet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Priority;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_and_format_durations() {
+        assert_eq!(parse_duration("45m").unwrap(), 45);
+        assert_eq!(parse_duration("2h").unwrap(), 120);
+        assert_eq!(parse_duration("1h30m").unwrap(), 90);
+        assert_eq!(parse_duration("1h 30m").unwrap(), 90);
+        assert_eq!(parse_duration("1.5h").unwrap(), 90);
+        assert_eq!(parse_duration("20").unwrap(), 20);
+
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("soon").is_err());
+
+        assert_eq!(format_minutes(45), "45m");
+        assert_eq!(format_minutes(120), "2h");
+        assert_eq!(format_minutes(95), "1h 35m");
+    }
+
+    #[test]
+    fn test_timesheet_by_day_and_tag() {
+        let at = |day: u32, hour: u32| Local.with_ymd_and_hms(2025, 1, day, hour, 0, 0).unwrap().with_timezone(&Utc);
+        let entry = |day, minutes| TimeEntry { started_at: at(day, 10), minutes, actor: "human".to_string(), note: None };
+
+        let mut api = Task::new(1, "API".to_string(), String::new(), Priority::High);
+        api.tags = vec!["backend".to_string(), "auth".to_string()];
+        api.time_log = vec![entry(13, 60), entry(14, 30), entry(20, 45)];
+        let mut docs = Task::new(2, "Docs".to_string(), String::new(), Priority::Low);
+        docs.time_log = vec![entry(14, 15)];
+
+        let day = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let sheet = timesheet(&[api, docs], day(13), day(19));
+        assert_eq!(sheet.by_day, BTreeMap::from([(day(13), 60), (day(14), 45)]));
+        assert_eq!(sheet.by_tag["backend"], 90);
+        assert_eq!(sheet.by_tag["auth"], 90);
+        assert_eq!(sheet.by_tag["(untagged)"], 15);
+        assert_eq!(sheet.total, 105);
+    }
+}
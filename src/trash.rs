@@ -0,0 +1,221 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::relation::RelationKind;
+use crate::storage::{atomic_write, TaskStore};
+use crate::task::{Task, TaskRef};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RefKind {
+    Dependency,
+    Subtask,
+    /// A typed link held by the other task
+    Relation(RelationKind),
+}
+
+/// Another task that pointed at a deleted task
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrashRef {
+    pub task_id: u32,
+    pub kind: RefKind,
+    /// Index in the parent's `subtasks` list, so a restore keeps the order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+}
+
+/// A deleted task along with everything needed to put it back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub task: Task,
+    pub deleted_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub referenced_by: Vec<TrashRef>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// Deleted tasks, kept as JSON files in `.trusty/trash` until purged
+pub struct Trash {
+    dir: PathBuf,
+}
+
+impl Trash {
+    pub fn new(trusty_dir: &Path) -> Self {
+        Self { dir: trusty_dir.join("trash") }
+    }
+
+    /// All trashed tasks, oldest deletion first
+    pub fn list(&self) -> Result<Vec<TrashEntry>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read trash entry: {:?}", path))?;
+            let mut entry: TrashEntry = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse trash entry: {:?}", path))?;
+            entry.path = path;
+            entries.push(entry);
+        }
+
+        entries.sort_by_key(|e| (e.deleted_at, e.task.id));
+        Ok(entries)
+    }
+
+    /// Find the most recently trashed task matching an ID or uid prefix
+    pub fn find(&self, task: &TaskRef) -> Result<TrashEntry> {
+        self.list()?
+            .into_iter()
+            .rev()
+            .find(|e| match task {
+                TaskRef::Id(id) => e.task.id == *id,
+                TaskRef::Uid(prefix) => e.task.uid.as_deref().is_some_and(|uid| uid.starts_with(prefix.as_str())),
+            })
+            .ok_or_else(|| anyhow::anyhow!("Task {} is not in the trash", task))
+    }
+
+    /// Move tasks from the store into the trash.
+    ///
+    /// References from tasks outside `ids` are removed from those tasks and
+    /// recorded in the entry. References between the trashed tasks stay in
+    /// place, so trashing and restoring a whole group leaves it unchanged.
+    pub fn delete(&self, storage: &dyn TaskStore, ids: &[u32]) -> Result<Vec<TrashEntry>> {
+        let _lock = storage.lock()?;
+        let deleting: HashSet<u32> = ids.iter().copied().collect();
+        let mut others: Vec<Task> = storage.list_all_tasks()?
+            .into_iter()
+            .filter(|t| !deleting.contains(&t.id))
+            .collect();
+
+        let mut entries = Vec::new();
+        for &id in ids {
+            let task = storage.load_task(id)?;
+            let mut referenced_by = Vec::new();
+
+            for other in others.iter_mut() {
+                let mut changed = false;
+                if other.dependencies.contains(&id) {
+                    other.remove_dependency(id);
+                    referenced_by.push(TrashRef { task_id: other.id, kind: RefKind::Dependency, position: None });
+                    changed = true;
+                }
+                if let Some(position) = other.subtasks.iter().position(|&s| s == id) {
+                    other.subtasks.remove(position);
+                    other.updated_at = Utc::now();
+                    referenced_by.push(TrashRef { task_id: other.id, kind: RefKind::Subtask, position: Some(position) });
+                    changed = true;
+                }
+                for relation in other.relations.clone().into_iter().filter(|r| r.task == id) {
+                    other.remove_relation(relation.kind, id);
+                    referenced_by.push(TrashRef { task_id: other.id, kind: RefKind::Relation(relation.kind), position: None });
+                    changed = true;
+                }
+                if changed {
+                    storage.save_task(other)?;
+                }
+            }
+
+            let mut entry = TrashEntry {
+                task,
+                deleted_at: Utc::now(),
+                referenced_by,
+                path: PathBuf::new(),
+            };
+            self.write(&mut entry)?;
+            storage.delete_task(id)?;
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+
+    /// Put a trashed task back, returning its ID and the references that
+    /// could not be restored because the referencing task is gone.
+    ///
+    /// The task keeps its old ID unless another task has taken it since.
+    /// References from tasks that are themselves in the trash are written
+    /// into their entries, so they come back when those tasks are restored.
+    pub fn restore(&self, storage: &dyn TaskStore, entry: TrashEntry) -> Result<(u32, Vec<TrashRef>)> {
+        let _lock = storage.lock()?;
+
+        let mut task = entry.task.clone();
+        if storage.load_task(task.id).is_ok() {
+            task.id = storage.allocate_id()?;
+        }
+        storage.save_task(&task)?;
+        fs::remove_file(&entry.path)
+            .with_context(|| format!("Failed to remove trash entry: {:?}", entry.path))?;
+
+        let mut trashed = self.list()?;
+        let mut missing = Vec::new();
+        for reference in entry.referenced_by {
+            if let Ok(mut other) = storage.load_task(reference.task_id) {
+                reattach(&mut other, task.id, &reference);
+                storage.save_task(&other)?;
+            } else if let Some(other) = trashed.iter_mut().rev().find(|e| e.task.id == reference.task_id) {
+                reattach(&mut other.task, task.id, &reference);
+                self.write(other)?;
+            } else {
+                missing.push(reference);
+            }
+        }
+
+        Ok((task.id, missing))
+    }
+
+    /// Drop entries for a task that is back in the store by other means, such as an undo
+    pub fn forget(&self, task: &Task) -> Result<()> {
+        for entry in self.list()? {
+            if entry.task.id == task.id && entry.task.created_at == task.created_at {
+                self.purge(&entry)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Permanently remove an entry
+    pub fn purge(&self, entry: &TrashEntry) -> Result<()> {
+        fs::remove_file(&entry.path)
+            .with_context(|| format!("Failed to remove trash entry: {:?}", entry.path))
+    }
+
+    fn write(&self, entry: &mut TrashEntry) -> Result<()> {
+        if entry.path.as_os_str().is_empty() {
+            fs::create_dir_all(&self.dir)
+                .with_context(|| format!("Failed to create trash directory: {:?}", self.dir))?;
+            // Timestamped so the same ID can be trashed more than once
+            entry.path = self.dir.join(format!("{}-{}.json", entry.task.id, entry.deleted_at.timestamp_millis()));
+        }
+
+        let content = serde_json::to_string_pretty(entry)?;
+        atomic_write(&entry.path, content.as_bytes())
+    }
+}
+
+fn reattach(task: &mut Task, id: u32, reference: &TrashRef) {
+    match reference.kind {
+        RefKind::Dependency => task.add_dependency(id),
+        RefKind::Relation(kind) => {
+            task.add_relation(kind, id);
+        }
+        RefKind::Subtask => {
+            if !task.subtasks.contains(&id) {
+                let position = reference.position.unwrap_or(task.subtasks.len()).min(task.subtasks.len());
+                task.subtasks.insert(position, id);
+                task.updated_at = Utc::now();
+            }
+        }
+    }
+}
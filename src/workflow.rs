@@ -0,0 +1,199 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::graph::TaskGraph;
+use crate::storage::TaskStore;
+use crate::task::{Task, TaskStatus};
+
+/// A condition a task has to meet before it may enter a status
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Guard {
+    /// Every dependency exists and is done
+    DependenciesDone,
+    /// Every subtask is done or cancelled
+    SubtasksDone,
+    /// The change comes with a `--resolution` note
+    Resolution,
+}
+
+/// The `workflow` section of `.trusty/config`: which statuses exist, which
+/// status changes are allowed and what a task needs before entering a status.
+///
+/// The default workflow has the six built-in statuses and allows every change.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Workflow {
+    /// Statuses in addition to the built-in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statuses: Vec<String>,
+    /// Status → the statuses a task may move to from it. A status without an
+    /// entry may move to any status.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub transitions: BTreeMap<String, Vec<String>>,
+    /// Status → guards checked before a task enters it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub guards: BTreeMap<String, Vec<Guard>>,
+}
+
+impl Workflow {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check that the workflow only refers to statuses it defines
+    pub fn validate(&self) -> Result<()> {
+        for name in &self.statuses {
+            if TaskStatus::BUILT_IN.iter().any(|s| s.name() == name) {
+                anyhow::bail!("Workflow status '{}' is already a built-in status", name);
+            }
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+                anyhow::bail!("Invalid workflow status '{}'. Use lowercase letters, digits and dashes", name);
+            }
+        }
+
+        let referenced = self.transitions.iter()
+            .flat_map(|(from, to)| std::iter::once(from).chain(to))
+            .chain(self.guards.keys());
+        for name in referenced {
+            self.parse_status(name)?;
+        }
+        Ok(())
+    }
+
+    /// Built-in statuses followed by the workflow's own
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        TaskStatus::BUILT_IN.iter().cloned()
+            .chain(self.statuses.iter().map(|name| TaskStatus::Custom(name.clone())))
+            .collect()
+    }
+
+    pub fn parse_status(&self, s: &str) -> Result<TaskStatus> {
+        let name = s.trim().to_lowercase();
+        self.statuses().into_iter()
+            .find(|status| status.name() == name)
+            .ok_or_else(|| anyhow::anyhow!(
+                "Invalid status: {}. Use {}",
+                s,
+                self.statuses().iter().map(TaskStatus::name).collect::<Vec<_>>().join(", ")
+            ))
+    }
+
+    /// Whether `status` exists in this workflow
+    pub fn defines(&self, status: &TaskStatus) -> bool {
+        match status {
+            TaskStatus::Custom(name) => self.statuses.contains(name),
+            _ => true,
+        }
+    }
+
+    /// Why `task` may not move to `to`, if anything stops it. `graph` shows
+    /// the project as it will be after the change, so tasks that change
+    /// status together see each other's new status.
+    pub fn violations(&self, task: &Task, to: &TaskStatus, graph: &TaskGraph, resolution: Option<&str>) -> Vec<String> {
+        let mut violations = Vec::new();
+        if task.status == *to {
+            return violations;
+        }
+
+        if !self.defines(to) {
+            violations.push(format!("'{}' isn't a status in this project's workflow", to.name()));
+        }
+        if let Some(allowed) = self.transitions.get(task.status.name()) {
+            if allowed.is_empty() {
+                violations.push(format!("{} is a final status", task.status.name()));
+            } else if !allowed.iter().any(|name| name == to.name()) {
+                violations.push(format!("{} tasks can only move to {}", task.status.name(), allowed.join(", ")));
+            }
+        }
+
+        for guard in self.guards.get(to.name()).into_iter().flatten() {
+            match guard {
+                Guard::DependenciesDone => {
+                    let mut deps: Vec<u32> = task.dependencies.iter().copied().collect();
+                    deps.sort();
+                    for dep in deps {
+                        if !graph.get(dep).is_some_and(|t| t.status == TaskStatus::Done) {
+                            violations.push(format!("dependency #{} isn't done", dep));
+                        }
+                    }
+                }
+                Guard::SubtasksDone => {
+                    for &id in &task.subtasks {
+                        let closed = graph.get(id)
+                            .map(|subtask| graph.effective_status(subtask))
+                            .is_some_and(|status| matches!(status, TaskStatus::Done | TaskStatus::Cancelled));
+                        if !closed {
+                            violations.push(format!("subtask #{} isn't done", id));
+                        }
+                    }
+                }
+                Guard::Resolution => {
+                    if resolution.is_none_or(|r| r.trim().is_empty()) {
+                        violations.push("a resolution is required (--resolution)".to_string());
+                    }
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// Fail with every reason `task` may not move to `to`
+    pub fn check(&self, task: &Task, to: &TaskStatus, graph: &TaskGraph, resolution: Option<&str>) -> Result<()> {
+        let violations = self.violations(task, to, graph, resolution);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Can't move task #{} from {} to {}: {}. Use --force to override",
+            task.id, task.status.name(), to.name(), violations.join("; ")
+        )
+    }
+}
+
+/// Move the tasks `ids` to `status` together, enforcing the workflow unless
+/// `force` is set. Nothing is saved if any task may not move. The caller
+/// holds the store's lock.
+pub fn set_status(
+    storage: &dyn TaskStore,
+    workflow: &Workflow,
+    ids: &[u32],
+    status: &TaskStatus,
+    resolution: Option<&str>,
+    force: bool,
+) -> Result<Vec<Task>> {
+    let mut tasks: HashMap<u32, Task> = storage.list_all_tasks()?.into_iter().map(|t| (t.id, t)).collect();
+    let mut changed = Vec::new();
+    for id in ids {
+        let task = tasks.get(id).ok_or_else(|| anyhow::anyhow!("Task #{} not found", id))?;
+        let mut moved = task.clone();
+        if moved.status != *status {
+            moved.set_status(status.clone());
+        }
+        match resolution {
+            Some(resolution) => moved.resolution = Some(resolution.to_string()),
+            // A reopened task is no longer resolved
+            None if !matches!(status, TaskStatus::Done | TaskStatus::Cancelled) => moved.resolution = None,
+            None => {}
+        }
+        changed.push((task.clone(), moved));
+    }
+
+    if !force {
+        for (_, moved) in &changed {
+            tasks.insert(moved.id, moved.clone());
+        }
+        let graph = TaskGraph::new(tasks.into_values().collect());
+        for (before, _) in &changed {
+            workflow.check(before, status, &graph, resolution)?;
+        }
+    }
+
+    let mut saved = Vec::new();
+    for (_, moved) in changed {
+        storage.save_task(&moved)?;
+        saved.push(moved);
+    }
+    Ok(saved)
+}
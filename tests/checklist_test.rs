@@ -0,0 +1,63 @@
+mod common;
+
+use common::{run, trusty};
+use trusty::config::ProjectConfig;
+use trusty::storage;
+
+#[test]
+fn test_check_and_uncheck_items() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Cut the release", "--description", "Steps:\n- [ ] Bump the version\n- [ ] Tag\n- [ ] Publish"]);
+
+    trusty(dir, &["check", "1", "1", "3"]);
+    trusty(dir, &["check", "1", "2"]);
+    trusty(dir, &["uncheck", "1", "3"]);
+    // A bad item number changes nothing
+    assert!(!run(dir, &["check", "1", "3", "4"]).status.success());
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    let task = store.load_task(1).unwrap();
+    assert_eq!(task.description, "Steps:\n- [x] Bump the version\n- [x] Tag\n- [ ] Publish");
+    assert_eq!(task.checklist_progress(), Some((2, 3)));
+
+    let show = trusty(dir, &["show", "1"]);
+    assert!(show.contains("Checklist: 2/3 checked"), "{}", show);
+    assert!(show.contains("3. ☐ Publish"), "{}", show);
+    assert!(trusty(dir, &["list"]).contains("☑ 2/3"));
+}
+
+#[test]
+fn test_all_checked_counts_as_done_when_configured() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Rotate keys", "--description", "- [ ] Staging\n- [ ] Production"]);
+    trusty(dir, &["check", "1", "1", "2"]);
+    assert!(trusty(dir, &["show", "1"]).contains("Status: ○ pending\n"));
+
+    let mut config = ProjectConfig::load(&dir.join(".trusty")).unwrap();
+    config.checklists_complete_tasks = true;
+    config.save(&dir.join(".trusty")).unwrap();
+    let show = trusty(dir, &["show", "1"]);
+    assert!(show.contains("Status: ○ pending (effective: ● done)"), "{}", show);
+}
+
+#[test]
+fn test_next_skips_checked_off_tasks_when_configured() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Rotate keys", "--priority", "high", "--description", "- [ ] Staging\n- [ ] Production"]);
+    trusty(dir, &["add", "Announce rotation", "--priority", "low", "--dependencies", "1"]);
+    trusty(dir, &["check", "1", "1", "2"]);
+    assert!(trusty(dir, &["next"]).contains("#1 - Rotate keys"));
+
+    let mut config = ProjectConfig::load(&dir.join(".trusty")).unwrap();
+    config.checklists_complete_tasks = true;
+    config.save(&dir.join(".trusty")).unwrap();
+    // Checked off counts as done, which also unblocks what depends on it
+    let next = trusty(dir, &["next"]);
+    assert!(next.contains("#2 - Announce rotation"), "{}", next);
+}
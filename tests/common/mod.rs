@@ -0,0 +1,31 @@
+//! Runs the trusty binary for the CLI tests
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Run trusty in `dir` as `actor`, ignoring any project or actor the tests were started with
+pub fn run_as(dir: &Path, actor: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_trusty"))
+        .args(args)
+        .current_dir(dir)
+        .env_remove("TRUSTY_DIR")
+        .env("TRUSTY_ACTOR", actor)
+        .output()
+        .expect("failed to run trusty")
+}
+
+/// Run trusty in `dir`
+pub fn run(dir: &Path, args: &[&str]) -> Output {
+    run_as(dir, "human", args)
+}
+
+/// Run trusty in `dir` and return what it printed, failing the test if the command fails
+pub fn trusty(dir: &Path, args: &[&str]) -> String {
+    succeeded(run(dir, args), args)
+}
+
+/// What `trusty args` printed, failing the test if it didn't succeed
+pub fn succeeded(output: Output, args: &[&str]) -> String {
+    assert!(output.status.success(), "trusty {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
@@ -0,0 +1,94 @@
+//! Runs many `trusty` processes against one project at once and checks that
+//! no task or update is lost and no ID is handed out twice.
+
+mod common;
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::thread;
+
+use common::trusty;
+use trusty::config::ProjectConfig;
+use trusty::storage::{self, TaskStore};
+
+const WORKERS: u32 = 8;
+const ADDS_PER_WORKER: u32 = 6;
+
+fn open(dir: &Path) -> Box<dyn TaskStore> {
+    let trusty_dir = dir.join(".trusty");
+    let config = ProjectConfig::load(&trusty_dir).unwrap();
+    storage::open_store(&trusty_dir, &config).unwrap()
+}
+
+fn stress(backend: &str) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init", "--storage", backend]);
+    trusty(dir, &["add", "Hub"]);
+
+    // Every worker adds tasks and records a dependency on the shared hub task,
+    // so both ID allocation and read-modify-write of one task are contended
+    let workers: Vec<_> = (0..WORKERS).map(|worker| {
+        let dir = dir.to_path_buf();
+        thread::spawn(move || {
+            for n in 0..ADDS_PER_WORKER {
+                trusty(&dir, &["add", &format!("Worker {} task {}", worker, n)]);
+            }
+            // Dependencies have to exist, so depend on one of the worker's own tasks
+            let title = format!("Worker {} task 0", worker);
+            let dep = open(&dir).list_all_tasks().unwrap().into_iter().find(|t| t.title == title).unwrap().id.to_string();
+            trusty(&dir, &["add-dep", "--task", "1", "--dep", &dep]);
+        })
+    }).collect();
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    let store = open(dir);
+    let tasks = store.list_all_tasks().unwrap();
+    let expected = (WORKERS * ADDS_PER_WORKER + 1) as usize;
+    assert_eq!(tasks.len(), expected, "{} backend lost tasks", backend);
+
+    let ids: HashSet<u32> = tasks.iter().map(|t| t.id).collect();
+    assert_eq!(ids.len(), expected, "{} backend reused an ID", backend);
+
+    let titles: HashSet<&str> = tasks.iter().map(|t| t.title.as_str()).collect();
+    for worker in 0..WORKERS {
+        for n in 0..ADDS_PER_WORKER {
+            assert!(titles.contains(format!("Worker {} task {}", worker, n).as_str()));
+        }
+    }
+
+    let hub = store.load_task(1).unwrap();
+    assert_eq!(hub.dependencies.len(), WORKERS as usize, "{} backend lost an update", backend);
+}
+
+#[test]
+fn test_concurrent_processes_markdown() {
+    stress("markdown");
+}
+
+#[test]
+fn test_concurrent_processes_json() {
+    stress("json");
+}
+
+#[test]
+fn test_concurrent_processes_sqlite() {
+    stress("sqlite");
+}
+
+#[test]
+fn test_deleted_ids_are_not_reused() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "First"]);
+    trusty(dir, &["add", "Second"]);
+    trusty(dir, &["delete", "2"]);
+    trusty(dir, &["add", "Third"]);
+
+    let ids: Vec<u32> = open(dir).list_all_tasks().unwrap().iter().map(|t| t.id).collect();
+    assert_eq!(ids, vec![1, 3]);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::{run, trusty};
+use trusty::config::ProjectConfig;
+use trusty::dates;
+use trusty::defer::{self, WakeCondition};
+use trusty::storage;
+use trusty::task::{Priority, Task, TaskStatus};
+
+#[test]
+fn test_deferred_tasks_wake_up_on_the_next_command() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    for title in ["Spec", "Build", "Release", "Later"] {
+        trusty(dir, &["add", title]);
+    }
+
+    trusty(dir, &["defer", "2", "--until", "#1"]);
+    trusty(dir, &["defer", "3", "--until", "file:out/ready"]);
+    trusty(dir, &["defer", "4", "--until", "next week"]);
+    // A condition that already holds would wake the task straight away
+    assert!(!run(dir, &["defer", "1", "--until", "yesterday"]).status.success());
+
+    let list = trusty(dir, &["list"]);
+    assert!(!list.contains("woke up"), "{}", list);
+
+    trusty(dir, &["complete", "1"]);
+    std::fs::create_dir(dir.join("out")).unwrap();
+    std::fs::write(dir.join("out/ready"), "").unwrap();
+
+    let list = trusty(dir, &["list"]);
+    assert!(list.contains("2 deferred task(s) woke up"), "{}", list);
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    let build = store.load_task(2).unwrap();
+    assert_eq!(build.status, TaskStatus::Pending);
+    assert_eq!(build.wake_when, None);
+    assert!(build.dependencies.contains(&1));
+    assert_eq!(store.load_task(4).unwrap().status, TaskStatus::Deferred);
+
+    // Touching a woken task takes it out of the notice
+    trusty(dir, &["set-status", "--id", "2", "--status", "in-progress"]);
+    let list = trusty(dir, &["list"]);
+    assert!(list.contains("1 deferred task(s) woke up"), "{}", list);
+}
+
+#[test]
+fn test_defer_until_task_resolves_uids_and_checks_the_task_exists() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init", "--id-scheme", "ulid"]);
+    trusty(dir, &["add", "Spec"]);
+    trusty(dir, &["add", "Build"]);
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    let uid = store.load_task(1).unwrap().uid.unwrap();
+    trusty(dir, &["defer", "2", "--until", &format!("#{}", &uid[..8])]);
+    let build = store.load_task(2).unwrap();
+    assert!(build.dependencies.contains(&1), "{:?}", build.dependencies);
+    assert_eq!(build.wake_when, Some(WakeCondition::DependenciesDone));
+
+    let output = run(dir, &["defer", "1", "--until", "#99"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("#99, which doesn't exist"), "{}", stderr);
+    assert_eq!(store.load_task(1).unwrap().status, TaskStatus::Pending);
+}
+
+#[test]
+fn test_wake_ups_are_undone_with_the_command_that_triggered_them() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Spec"]);
+    trusty(dir, &["add", "Build"]);
+    trusty(dir, &["defer", "2", "--until", "#1"]);
+    trusty(dir, &["complete", "1"]);
+
+    // The wake-up is recorded even though the command fails
+    assert!(!run(dir, &["complete", "99"]).status.success());
+    let journal = trusty(dir, &["journal"]);
+    assert!(journal.contains("complete 99 (#2)"), "{}", journal);
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    assert_eq!(store.load_task(2).unwrap().status, TaskStatus::Pending);
+    assert!(trusty(dir, &["undo"]).contains("complete 99"));
+    assert_eq!(store.load_task(2).unwrap().status, TaskStatus::Deferred);
+}
+
+#[test]
+fn test_wake_up_by_date() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let store = storage::open_store(temp_dir.path(), &ProjectConfig::default()).unwrap();
+    let today = dates::today();
+
+    for (id, days) in [(1, 0), (2, 1)] {
+        let mut task = Task::new(id, format!("Task {}", id), String::new(), Priority::Medium);
+        task.set_status(TaskStatus::Deferred);
+        task.deferred_until = Some(today + chrono::Days::new(days));
+        store.save_task(&task).unwrap();
+    }
+    let mut forgotten = Task::new(3, "Forgotten".to_string(), String::new(), Priority::Low);
+    forgotten.set_status(TaskStatus::Deferred);
+    forgotten.wake_when = Some(WakeCondition::FileExists("missing".to_string()));
+    store.save_task(&forgotten).unwrap();
+
+    let woken = defer::wake_up(store.as_ref(), temp_dir.path(), today).unwrap();
+    assert_eq!(woken.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1]);
+
+    let task = store.load_task(1).unwrap();
+    assert!(defer::is_newly_woken(&task));
+    assert_eq!(task.deferred_until, None);
+    assert!(!defer::is_newly_woken(&store.load_task(2).unwrap()));
+    assert_eq!(store.load_task(3).unwrap().wake_when, forgotten.wake_when);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use std::process::Output;
+
+use common::{run, trusty};
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn project() -> tempfile::TempDir {
+    let temp_dir = tempfile::tempdir().unwrap();
+    trusty(temp_dir.path(), &["init"]);
+    for title in ["First", "Second", "Third"] {
+        trusty(temp_dir.path(), &["add", title]);
+    }
+    temp_dir
+}
+
+#[test]
+fn test_add_dep_rejects_cycles() {
+    let temp_dir = project();
+    let dir = temp_dir.path();
+
+    assert!(run(dir, &["add-dep", "--task", "1", "--dep", "2"]).status.success());
+    assert!(run(dir, &["add-dep", "--task", "2", "--dep", "3"]).status.success());
+
+    let output = run(dir, &["add-dep", "--task", "3", "--dep", "1"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Dependency cycle: #3 → #1 → #2 → #3"));
+
+    let output = run(dir, &["add-dep", "--task", "1", "--dep", "1"]);
+    assert!(stderr(&output).contains("Task #1 depends on itself"));
+    let output = run(dir, &["add", "Fourth", "--dependencies", "8"]);
+    assert!(stderr(&output).contains("depends on #8, which doesn't exist"));
+
+    let output = run(dir, &["add-subtask", "--task", "1", "--existing", "2"]);
+    assert!(output.status.success());
+    let output = run(dir, &["add-subtask", "--task", "3", "--existing", "2"]);
+    assert!(stderr(&output).contains("Task #2 is a subtask of #1 and #3"));
+    let output = run(dir, &["add-subtask", "--task", "2", "--existing", "1"]);
+    assert!(stderr(&output).contains("Subtask cycle: #2 → #1 → #2"));
+}
+
+#[test]
+fn test_import_rejects_cycles_without_writing() {
+    let temp_dir = project();
+    let dir = temp_dir.path();
+    let file = dir.join("tasks.json");
+    std::fs::write(&file, r#"[
+        {"id": 1, "title": "A", "dependencies": [2]},
+        {"id": 2, "title": "B", "dependencies": [1]}
+    ]"#).unwrap();
+
+    let output = run(dir, &["import", file.to_str().unwrap(), "--format", "json"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Dependency cycle: #4 → #5 → #4"));
+    assert!(!dir.join(".trusty/tasks/4.md").exists());
+
+    // IDs in the file are mapped to new IDs; other IDs refer to existing tasks
+    std::fs::write(&file, r#"[
+        {"id": 10, "title": "A", "dependencies": [11, 1], "subtasks": [12]},
+        {"id": 11, "title": "B", "dependencies": [12]},
+        {"id": 12, "title": "C"}
+    ]"#).unwrap();
+    assert!(run(dir, &["import", file.to_str().unwrap(), "--format", "json"]).status.success());
+
+    let shown = trusty(dir, &["show", "4"]);
+    assert!(shown.contains("Dependencies: [1, 5]") || shown.contains("Dependencies: [5, 1]"));
+    assert!(shown.contains("IDs: [6]"));
+    // Existing tasks keep their own links
+    assert!(!trusty(dir, &["show", "2"]).contains("Dependencies"));
+}
+
+#[test]
+fn test_doctor_reports_and_fixes() {
+    let temp_dir = project();
+    let dir = temp_dir.path();
+    let tasks_dir = dir.join(".trusty/tasks");
+
+    assert!(trusty(dir, &["doctor"]).contains("No problems found in 3 task(s)"));
+
+    let second = std::fs::read_to_string(tasks_dir.join("2.md")).unwrap()
+        .replacen("---\n", "---\ndependencies:\n- 2\nsubtasks:\n- 2\n- 7\n", 1);
+    std::fs::write(tasks_dir.join("2.md"), second).unwrap();
+    std::fs::write(tasks_dir.join("9.md"), "not a task").unwrap();
+
+    let output = run(dir, &["doctor"]);
+    assert!(!output.status.success());
+    let report = stdout(&output);
+    assert!(report.contains("Task #2 depends on itself"));
+    assert!(report.contains("Task #2 is its own subtask"));
+    assert!(report.contains("Task #2 lists subtask #7, which doesn't exist"));
+    assert!(report.contains("9.md"));
+    assert!(stderr(&output).contains("Found 4 problem(s)"));
+
+    let output = run(dir, &["doctor", "--fix"]);
+    assert!(stdout(&output).contains("Removed missing subtask #7 from #2"));
+    // The unreadable file can't be fixed automatically
+    assert!(stderr(&output).contains("1 problem(s) need fixing by hand"));
+
+    std::fs::remove_file(tasks_dir.join("9.md")).unwrap();
+    assert!(run(dir, &["doctor"]).status.success());
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::trusty;
+use trusty::config::ProjectConfig;
+use trusty::dates;
+use trusty::storage;
+
+#[test]
+fn test_dates_are_parsed_and_stored() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Ship it", "--due", "+3d", "--start", "tomorrow"]);
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    let task = store.load_task(1).unwrap();
+    let today = dates::today();
+    assert_eq!(task.due, Some(dates::parse_date("+3d", today).unwrap()));
+    assert_eq!(task.scheduled, Some(dates::parse_date("tomorrow", today).unwrap()));
+
+    trusty(dir, &["edit", "1", "--due", "none", "--deferred-until", "2031-05-01"]);
+    let task = store.load_task(1).unwrap();
+    assert_eq!(task.due, None);
+    assert_eq!(task.deferred_until.map(|d| d.to_string()).as_deref(), Some("2031-05-01"));
+}
+
+#[test]
+fn test_deadlines_come_before_priority() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Important", "--priority", "high"]);
+    trusty(dir, &["add", "Due soon", "--priority", "low", "--due", "tomorrow"]);
+    trusty(dir, &["add", "Overdue", "--priority", "low", "--due", "2020-01-01"]);
+    trusty(dir, &["add", "Not yet", "--priority", "high", "--due", "2020-01-01", "--scheduled", "+1w"]);
+
+    assert!(trusty(dir, &["next"]).contains("#3 - Overdue"));
+    trusty(dir, &["complete", "3"]);
+    assert!(trusty(dir, &["next"]).contains("#2 - Due soon"));
+    trusty(dir, &["complete", "2"]);
+    // A task scheduled for later waits even when it's overdue
+    assert!(trusty(dir, &["next"]).contains("#1 - Important"));
+
+    let overdue = trusty(dir, &["list", "--overdue"]);
+    assert!(overdue.contains("1 overdue task(s)"));
+    assert!(overdue.contains("Not yet"));
+    assert!(!overdue.contains("Important"));
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::trusty;
+use trusty::config::ProjectConfig;
+use trusty::storage;
+
+#[test]
+fn test_estimates_roll_up_and_compare_with_actuals() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Billing", "-t", "backend", "--estimate", "1h"]);
+    trusty(dir, &["add-subtask", "--task", "1", "Invoices"]);
+    trusty(dir, &["add-subtask", "--task", "1", "Refunds"]);
+    trusty(dir, &["edit", "2", "--estimate", "2/4/12h", "--complexity", "medium"]);
+    trusty(dir, &["edit", "3", "--estimate", "3"]);
+
+    let show = trusty(dir, &["show", "1"]);
+    assert!(show.contains("Estimate: 1h (6h + 3pt with subtasks)"), "{}", show);
+
+    trusty(dir, &["log-time", "2", "7h30m"]);
+    trusty(dir, &["complete", "2"]);
+    trusty(dir, &["log-time", "3", "6h"]);
+    trusty(dir, &["complete", "3"]);
+
+    let report = trusty(dir, &["estimates"]);
+    assert!(report.contains("Hour estimates: 1 done task(s)"), "{}", report);
+    assert!(report.contains("backend          1 task(s)  est 5.0  actual 7.5h  1.50 h per estimated h"), "{}", report);
+    assert!(report.contains("medium           1 task(s)  est 5.0  actual 7.5h"), "{}", report);
+    assert!(report.contains("Point estimates: 1 done task(s)"), "{}", report);
+    assert!(report.contains("2.00 h per point"), "{}", report);
+
+    trusty(dir, &["edit", "1", "--estimate", "none"]);
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    assert_eq!(store.load_task(1).unwrap().estimate, None);
+}
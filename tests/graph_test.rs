@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use trusty::graph::{Problem, TaskGraph};
+use trusty::task::{Priority, Task, TaskStatus};
+
+fn task(id: u32, subtasks: &[u32], dependencies: &[u32]) -> Task {
+    let mut task = Task::new(id, format!("Task {}", id), String::new(), Priority::Medium);
+    task.subtasks = subtasks.to_vec();
+    task.dependencies = dependencies.iter().copied().collect();
+    task
+}
+
+fn ids(tasks: Vec<&Task>) -> Vec<u32> {
+    tasks.into_iter().map(|t| t.id).collect()
+}
+
+#[test]
+fn test_lookups_and_edges() {
+    let graph = TaskGraph::new(vec![
+        task(3, &[], &[1]),
+        task(1, &[2, 4], &[]),
+        task(2, &[5], &[]),
+        task(4, &[], &[1]),
+        task(5, &[], &[]),
+    ]);
+
+    assert_eq!(ids(graph.tasks().iter().collect()), vec![1, 2, 3, 4, 5]);
+    assert_eq!(graph.get(4).unwrap().title, "Task 4");
+    assert!(graph.get(9).is_none());
+
+    assert_eq!(graph.parent(5).unwrap().id, 2);
+    assert!(graph.parent(1).is_none());
+    assert_eq!(ids(graph.dependents(1)), vec![3, 4]);
+
+    assert_eq!(ids(graph.ancestors(5)), vec![2, 1]);
+    assert_eq!(ids(graph.descendants(1)), vec![2, 5, 4]);
+}
+
+#[test]
+fn test_subtask_cycles_terminate() {
+    let graph = TaskGraph::new(vec![task(1, &[2], &[]), task(2, &[3], &[]), task(3, &[1], &[])]);
+
+    assert_eq!(ids(graph.ancestors(1)), vec![3, 2]);
+    assert_eq!(ids(graph.descendants(1)), vec![2, 3]);
+    assert_eq!(graph.effective_status(graph.get(1).unwrap()), TaskStatus::Pending);
+}
+
+#[test]
+fn test_effective_status_and_progress_roll_up() {
+    let mut leaf_done = task(4, &[], &[]);
+    leaf_done.set_status(TaskStatus::Done);
+    let mut leaf_active = task(5, &[], &[]);
+    leaf_active.set_status(TaskStatus::InProgress);
+
+    let graph = TaskGraph::new(vec![
+        task(1, &[2, 3], &[]),
+        task(2, &[4], &[]),
+        task(3, &[5, 99], &[]),
+        leaf_done,
+        leaf_active,
+    ]);
+
+    let root = graph.get(1).unwrap();
+    assert_eq!(graph.effective_status(graph.get(2).unwrap()), TaskStatus::Done);
+    assert_eq!(graph.effective_status(root), TaskStatus::InProgress);
+    assert_eq!(graph.progress(root), (1, 2));
+
+    // Missing subtasks count towards the total but can't be done
+    assert_eq!(graph.progress(graph.get(3).unwrap()), (0, 2));
+}
+
+#[test]
+fn test_ready_sees_dependencies_outside_a_filtered_list() {
+    let mut done = task(1, &[], &[]);
+    done.set_status(TaskStatus::Done);
+    let graph = TaskGraph::new(vec![done, task(2, &[], &[1]), task(3, &[], &[1, 42])]);
+
+    assert!(graph.is_ready(graph.get(2).unwrap()));
+    // A dependency that doesn't exist keeps the task blocked
+    assert!(!graph.is_ready(graph.get(3).unwrap()));
+}
+
+#[test]
+fn test_checks_name_the_cycle() {
+    let graph = TaskGraph::new(vec![task(1, &[2], &[2]), task(2, &[3], &[3]), task(3, &[], &[]), task(4, &[3], &[])]);
+
+    assert_eq!(graph.check_dependency(3, 1), Err(Problem::DependencyCycle(vec![3, 1, 2, 3])));
+    assert_eq!(graph.check_dependency(3, 1).unwrap_err().to_string(), "Dependency cycle: #3 → #1 → #2 → #3");
+    assert_eq!(graph.check_dependency(2, 2), Err(Problem::SelfDependency(2)));
+    assert_eq!(graph.check_dependency(2, 7), Err(Problem::MissingDependency { task: 2, dep: 7 }));
+    assert_eq!(graph.check_dependency(4, 1), Ok(()));
+
+    assert_eq!(graph.check_subtask(3, 1), Err(Problem::SubtaskCycle(vec![3, 1, 2, 3])));
+    assert_eq!(graph.check_subtask(4, 2), Err(Problem::MultipleParents { subtask: 2, parents: vec![1, 4] }));
+    assert_eq!(graph.check_subtask(4, 4), Err(Problem::SelfSubtask(4)));
+    assert_eq!(graph.check_subtask(3, 4), Err(Problem::SubtaskCycle(vec![3, 4, 3])));
+    assert_eq!(graph.check_subtask(4, 1), Ok(()));
+}
+
+#[test]
+fn test_problems_are_reported_and_fixed() {
+    let tasks = vec![
+        task(1, &[2], &[2]),
+        task(2, &[3, 9, 9], &[1, 2]),
+        task(3, &[1], &[]),
+        task(4, &[3], &[]),
+    ];
+    let problems = TaskGraph::new(tasks.clone()).problems();
+    assert_eq!(problems, vec![
+        Problem::SelfDependency(2),
+        Problem::MissingSubtask { task: 2, subtask: 9 },
+        Problem::DuplicateSubtask { task: 2, subtask: 9 },
+        Problem::MultipleParents { subtask: 3, parents: vec![2, 4] },
+        Problem::DependencyCycle(vec![1, 2, 1]),
+        Problem::SubtaskCycle(vec![1, 2, 3, 1]),
+    ]);
+
+    let mut tasks: HashMap<u32, Task> = tasks.into_iter().map(|t| (t.id, t)).collect();
+    let mut problems = problems;
+    while let Some(problem) = problems.first() {
+        assert!(problem.fix(&mut tasks).is_some());
+        problems = TaskGraph::new(tasks.values().cloned().collect()).problems();
+    }
+
+    assert_eq!(tasks[&2].subtasks, vec![3]);
+    assert!(tasks[&4].subtasks.is_empty());
+    assert!(tasks[&3].subtasks.is_empty());
+    assert!(tasks[&2].dependencies.is_empty());
+    assert_eq!(tasks[&1].dependencies.len(), 1);
+}
+
+#[test]
+fn test_checklists_roll_up_when_enabled() {
+    let mut checked = task(1, &[], &[]);
+    checked.description = "- [x] Write it\n- [x] Ship it\n".to_string();
+    let mut parent = task(2, &[3], &[]);
+    parent.description = "- [ ] Announce it\n".to_string();
+    let mut subtask = task(3, &[], &[]);
+    subtask.status = TaskStatus::Done;
+    let tasks = vec![checked, parent, subtask];
+
+    let graph = TaskGraph::new(tasks.clone());
+    assert_eq!(graph.effective_status(&tasks[0]), TaskStatus::Pending);
+    assert_eq!(graph.effective_status(&tasks[1]), TaskStatus::Done);
+
+    let graph = TaskGraph::new(tasks.clone()).with_checklists(true);
+    assert_eq!(graph.effective_status(&tasks[0]), TaskStatus::Done);
+    assert_eq!(graph.effective_status(&tasks[1]), TaskStatus::InProgress);
+}
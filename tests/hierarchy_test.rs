@@ -0,0 +1,66 @@
+mod common;
+
+use std::path::Path;
+
+use common::{run, trusty};
+use trusty::config::ProjectConfig;
+use trusty::storage;
+
+fn setup(dir: &Path) {
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Checkout"]);
+    trusty(dir, &["add-subtask", "--task", "1", "Cart"]);
+    trusty(dir, &["add-subtask", "--task", "1", "Payment"]);
+    trusty(dir, &["add-subtask", "--task", "1", "Receipt"]);
+    trusty(dir, &["add-subtask", "--task", "3", "Card form"]);
+    trusty(dir, &["add", "Emails"]);
+}
+
+#[test]
+fn test_move_between_parents() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    setup(dir);
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+
+    let output = trusty(dir, &["move", "4", "--under", "6"]);
+    assert!(output.contains("Moved task #4 from #1 to task #6"), "{}", output);
+    assert_eq!(store.load_task(1).unwrap().subtasks, vec![2, 3]);
+    assert_eq!(store.load_task(6).unwrap().subtasks, vec![4]);
+    assert_eq!(store.load_task(4).unwrap().title, "Receipt");
+
+    // A task can't go under itself or anything below it
+    let output = run(dir, &["move", "1", "--under", "5"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Subtask cycle: #5 → #1 → #3 → #5"));
+    assert!(!run(dir, &["move", "3", "--under", "3"]).status.success());
+    assert!(!run(dir, &["move", "3", "--under", "42"]).status.success());
+    assert_eq!(store.load_task(1).unwrap().subtasks, vec![2, 3]);
+
+    trusty(dir, &["move", "3", "--top-level"]);
+    assert_eq!(store.load_task(1).unwrap().subtasks, vec![2]);
+    assert!(trusty(dir, &["move", "3", "--top-level"]).contains("already at the top level"));
+    assert!(!run(dir, &["move", "3"]).status.success());
+
+    // The move is one step for undo
+    trusty(dir, &["move", "3", "--under", "6"]);
+    trusty(dir, &["undo"]);
+    assert_eq!(store.load_task(6).unwrap().subtasks, vec![4]);
+}
+
+#[test]
+fn test_reorder_subtasks() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    setup(dir);
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+
+    trusty(dir, &["reorder", "1", "4", "--before", "2"]);
+    assert_eq!(store.load_task(1).unwrap().subtasks, vec![4, 2, 3]);
+    let output = trusty(dir, &["reorder", "1", "4", "--after", "3"]);
+    assert!(output.contains("Subtasks of #1: #2, #3, #4"), "{}", output);
+
+    assert!(!run(dir, &["reorder", "1", "5", "--before", "2"]).status.success());
+    assert!(!run(dir, &["reorder", "1", "2", "--after", "2"]).status.success());
+    assert!(!run(dir, &["reorder", "1", "2"]).status.success());
+    assert_eq!(store.load_task(1).unwrap().subtasks, vec![2, 3, 4]);
+}
@@ -0,0 +1,86 @@
+use chrono::{Duration, Utc};
+use trusty::config::ProjectConfig;
+use trusty::history::{self, HistoryChange};
+use trusty::storage::{self, TaskStore};
+use trusty::task::{Priority, Task, TaskStatus};
+
+fn setup() -> (tempfile::TempDir, Box<dyn TaskStore>) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let storage = storage::open_store(temp_dir.path(), &ProjectConfig::default()).unwrap();
+    (temp_dir, storage)
+}
+
+fn changes(task: &Task) -> Vec<(String, HistoryChange)> {
+    task.history.iter().map(|e| (e.actor.clone(), e.change.clone())).collect()
+}
+
+#[test]
+fn test_saves_append_events_with_actor() {
+    let (_dir, storage) = setup();
+    storage.save_task(&Task::new(1, "Write docs".to_string(), String::new(), Priority::Medium)).unwrap();
+
+    let mut task = storage.load_task(1).unwrap();
+    task.set_status(TaskStatus::InProgress);
+    storage.save_task(&task).unwrap();
+
+    {
+        let _actor = history::act_as("prune");
+        let mut task = storage.load_task(1).unwrap();
+        task.priority = Priority::Low;
+        task.title = "Write the docs".to_string();
+        task.tags.push("docs".to_string());
+        storage.save_task(&task).unwrap();
+    }
+
+    // Saving without changes adds nothing
+    storage.save_task(&storage.load_task(1).unwrap()).unwrap();
+
+    let task = storage.load_task(1).unwrap();
+    assert_eq!(changes(&task), vec![
+        ("human".to_string(), HistoryChange::Created { status: TaskStatus::Pending }),
+        ("human".to_string(), HistoryChange::Status { from: TaskStatus::Pending, to: TaskStatus::InProgress }),
+        ("prune".to_string(), HistoryChange::Priority { from: Priority::Medium, to: Priority::Low }),
+        ("prune".to_string(), HistoryChange::Edited { fields: vec!["title".to_string(), "tags".to_string()] }),
+    ]);
+}
+
+#[test]
+fn test_history_cannot_be_rewritten() {
+    let (_dir, storage) = setup();
+    let task = Task::new(1, "Audit me".to_string(), String::new(), Priority::Medium);
+    storage.save_task(&task).unwrap();
+
+    // Writing back a stale copy, as an undo would, still appends
+    let mut stale = task.clone();
+    stale.set_status(TaskStatus::Blocked);
+    storage.save_task(&stale).unwrap();
+    storage.save_task(&task).unwrap();
+
+    let history = storage.load_task(1).unwrap().history;
+    assert_eq!(history.len(), 3);
+    assert!(matches!(history[2].change, HistoryChange::Status { to: TaskStatus::Pending, .. }));
+}
+
+#[test]
+fn test_status_since_uses_history() {
+    let mut task = Task::new(1, "Long running".to_string(), String::new(), Priority::Medium);
+    task.updated_at = Utc::now() - Duration::days(10);
+
+    // Without history the last update is the best guess
+    assert_eq!(task.status_since(), task.updated_at);
+
+    let (_dir, storage) = setup();
+    storage.save_task(&task).unwrap();
+    let mut task = storage.load_task(1).unwrap();
+    task.set_status(TaskStatus::InProgress);
+    storage.save_task(&task).unwrap();
+
+    // Edits after the status change don't reset the clock
+    let mut task = storage.load_task(1).unwrap();
+    let entered = task.history.last().unwrap().at;
+    task.description = "More detail".to_string();
+    task.updated_at = Utc::now();
+    storage.save_task(&task).unwrap();
+
+    assert_eq!(storage.load_task(1).unwrap().status_since(), entered);
+}
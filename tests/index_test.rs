@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+use trusty::config::ProjectConfig;
+use trusty::storage::{self, TaskStorage, TaskStore};
+use trusty::relation::RelationKind;
+use trusty::task::{Priority, Task};
+
+/// Past the window in which freshly written files are left out of the index
+fn wait_for_index() {
+    std::thread::sleep(Duration::from_millis(2100));
+}
+
+fn open(dir: &tempfile::TempDir) -> Box<dyn TaskStore> {
+    storage::open_store(dir.path(), &ProjectConfig::default()).unwrap()
+}
+
+#[test]
+fn test_index_picks_up_outside_edits() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let storage = open(&temp_dir);
+    for id in 1..=3 {
+        storage.save_task(&Task::new(id, format!("Task {}", id), String::new(), Priority::Medium)).unwrap();
+    }
+    wait_for_index();
+    assert_eq!(storage.list_all_tasks().unwrap().len(), 3);
+    assert!(temp_dir.path().join("index").exists());
+
+    // Edit and delete files behind the store's back, as an editor or git checkout would
+    let tasks_dir = temp_dir.path().join("tasks");
+    let edited = std::fs::read_to_string(tasks_dir.join("2.md")).unwrap().replace("Task 2", "Renamed outside");
+    std::fs::write(tasks_dir.join("2.md"), edited).unwrap();
+    std::fs::remove_file(tasks_dir.join("3.md")).unwrap();
+
+    let tasks = open(&temp_dir).list_all_tasks().unwrap();
+    assert_eq!(tasks.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), vec!["Task 1", "Renamed outside"]);
+}
+
+#[test]
+fn test_index_keeps_reverse_edges_until_a_file_changes() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let storage = open(&temp_dir);
+    let mut parent = Task::new(1, "Parent".to_string(), String::new(), Priority::Medium);
+    parent.add_subtask(3);
+    let mut dependent = Task::new(2, "Dependent".to_string(), String::new(), Priority::Medium);
+    dependent.add_dependency(1);
+    let mut duplicate = Task::new(3, "Duplicate".to_string(), String::new(), Priority::Medium);
+    duplicate.add_relation(RelationKind::Duplicates, 2);
+    for task in [&parent, &dependent, &duplicate] {
+        storage.save_task(task).unwrap();
+    }
+    wait_for_index();
+    storage.load_graph().unwrap();
+
+    let index: serde_json::Value = serde_json::from_slice(&std::fs::read(temp_dir.path().join("index")).unwrap()).unwrap();
+    assert_eq!(index["edges"]["dependents"]["1"], serde_json::json!([2]));
+    assert_eq!(index["edges"]["parents"]["3"], serde_json::json!(1));
+    assert_eq!(index["edges"]["inverse_relations"]["2"], serde_json::json!([["duplicates", 3]]));
+
+    let graph = open(&temp_dir).load_graph().unwrap();
+    assert_eq!(graph.dependents(1).iter().map(|t| t.id).collect::<Vec<_>>(), vec![2]);
+    assert_eq!(graph.parent(3).map(|t| t.id), Some(1));
+
+    // Deleting a file behind the store's back drops the edges it held
+    std::fs::remove_file(temp_dir.path().join("tasks").join("2.md")).unwrap();
+    let graph = open(&temp_dir).load_graph().unwrap();
+    assert!(graph.dependents(1).is_empty());
+    assert_eq!(graph.parent(3).map(|t| t.id), Some(1));
+}
+
+#[test]
+fn test_list_10k_tasks_from_index() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let plain = TaskStorage::new(temp_dir.path().join("tasks")).unwrap();
+    for id in 1..=10_000 {
+        let mut task = Task::new(id, format!("Task {}", id), "Some notes".to_string(), Priority::Medium);
+        if id > 1 {
+            task.add_dependency(id - 1);
+        }
+        plain.save_task(&task).unwrap();
+    }
+    wait_for_index();
+
+    let cold = Instant::now();
+    let expected = open(&temp_dir).list_all_tasks().unwrap();
+    let cold = cold.elapsed();
+
+    // A fresh store, as in the next `trusty list`, only has to check file metadata
+    let warm = Instant::now();
+    let tasks = open(&temp_dir).list_all_tasks().unwrap();
+    let warm = warm.elapsed();
+
+    assert_eq!(tasks.len(), 10_000);
+    assert_eq!(tasks, expected);
+    println!("10k tasks: {:?} without index, {:?} with", cold, warm);
+    assert!(warm < cold, "index made listing slower: {:?} vs {:?}", warm, cold);
+    assert!(warm < Duration::from_secs(2), "listing 10k indexed tasks took {:?}", warm);
+}
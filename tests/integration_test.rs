@@ -1,4 +1,5 @@
-use trusty::storage::TaskStorage;
+use trusty::config::{ProjectConfig, StorageBackend};
+use trusty::storage::{JsonStore, SqliteStore, TaskStorage, TaskStore};
 use trusty::task::{Task, Priority, TaskStatus};
 
 fn setup_test_dir() -> tempfile::TempDir {
@@ -93,4 +94,165 @@ fn test_load_nonexistent_task() {
     
     let result = storage.load_task(999);
     assert!(result.is_err());
-}
\ No newline at end of file
+}
+
+fn exercise_store(storage: &dyn TaskStore) {
+    assert_eq!(storage.next_id().unwrap(), 1);
+    
+    let mut task1 = Task::new(1, "Task 1".to_string(), "First".to_string(), Priority::High);
+    task1.add_dependency(2);
+    task1.tags = vec!["backend".to_string()];
+    let task2 = Task::new(2, "Task 2".to_string(), "".to_string(), Priority::Low);
+    
+    storage.save_task(&task2).unwrap();
+    storage.save_task(&task1).unwrap();
+    assert_eq!(storage.next_id().unwrap(), 3);
+    
+    // Saving again overwrites instead of duplicating
+    task1.set_status(TaskStatus::Done);
+    storage.save_task(&task1).unwrap();
+    
+    let tasks = storage.list_all_tasks().unwrap();
+    assert_eq!(tasks.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 2]);
+    
+    let loaded = storage.load_task(1).unwrap();
+    assert_eq!(loaded.status, TaskStatus::Done);
+    assert!(loaded.dependencies.contains(&2));
+    assert_eq!(loaded.tags, vec!["backend".to_string()]);
+    
+    storage.delete_task(1).unwrap();
+    assert!(storage.load_task(1).is_err());
+    assert!(storage.delete_task(1).is_err());
+    assert_eq!(storage.list_all_tasks().unwrap().len(), 1);
+}
+
+#[test]
+fn test_markdown_store_contract() {
+    let temp_dir = setup_test_dir();
+    let storage = TaskStorage::new(temp_dir.path().join("tasks")).unwrap();
+    exercise_store(&storage);
+}
+
+#[test]
+fn test_json_store_contract() {
+    let temp_dir = setup_test_dir();
+    let storage = JsonStore::new(temp_dir.path().join("tasks.json")).unwrap();
+    exercise_store(&storage);
+}
+
+#[test]
+fn test_sqlite_store_contract() {
+    let temp_dir = setup_test_dir();
+    let storage = SqliteStore::new(temp_dir.path().join("tasks.db")).unwrap();
+    exercise_store(&storage);
+}
+
+#[test]
+fn test_open_store_uses_configured_backend() {
+    let temp_dir = setup_test_dir();
+    let config = ProjectConfig { storage: StorageBackend::Json, ..Default::default() };
+    config.save(temp_dir.path()).unwrap();
+    
+    let loaded = ProjectConfig::load(temp_dir.path()).unwrap();
+    assert_eq!(loaded.storage, StorageBackend::Json);
+    
+    let storage = trusty::storage::open_store(temp_dir.path(), &loaded).unwrap();
+    storage.save_task(&Task::new(1, "Stored".to_string(), "".to_string(), Priority::Medium)).unwrap();
+    
+    assert!(temp_dir.path().join("tasks.json").exists());
+    assert!(!temp_dir.path().join("tasks").exists());
+}
+
+#[test]
+fn test_unknown_frontmatter_keys_survive_save() {
+    let temp_dir = setup_test_dir();
+    let storage = TaskStorage::new(temp_dir.path().to_path_buf()).unwrap();
+    
+    let content = "---
+id: 1
+title: Hand edited
+status: pending
+priority: medium
+estimate: 3
+jira: PROJ-42
+links:
+  design: https://example.com/doc
+created_at: 2024-08-01T10:00:00Z
+updated_at: 2024-08-01T10:00:00Z
+---
+
+# Hand edited
+
+Body";
+    std::fs::write(storage.get_task_path(1), content).unwrap();
+    
+    let mut task = storage.load_task(1).unwrap();
+    assert_eq!(task.extra.get("jira").and_then(|v| v.as_str()), Some("PROJ-42"));
+    assert_eq!(task.extra.get("estimate").and_then(|v| v.as_i64()), Some(3));
+    assert_eq!(task.estimate, None);
+    
+    task.set_status(TaskStatus::InProgress);
+    task.estimate = Some("2h".parse().unwrap());
+    storage.save_task(&task).unwrap();
+    
+    let reloaded = storage.load_task(1).unwrap();
+    assert_eq!(reloaded.extra, task.extra);
+    assert_eq!(reloaded.extra["links"]["design"].as_str(), Some("https://example.com/doc"));
+    assert_eq!(reloaded.estimate, task.estimate);
+    
+    let saved = std::fs::read_to_string(storage.get_task_path(1)).unwrap();
+    assert!(saved.contains("jira: PROJ-42"));
+    assert!(saved.contains("estimate: 3") && saved.contains("sizing: 2h"), "{}", saved);
+}
+
+#[test]
+fn test_uid_tasks_survive_alias_collision() {
+    let temp_dir = setup_test_dir();
+    let storage = TaskStorage::new(temp_dir.path().to_path_buf()).unwrap();
+    
+    // Two branches each created task #7 with its own uid, and one references the other
+    let mut ours = Task::new(7, "Ours".to_string(), String::new(), Priority::High);
+    ours.uid = Some("aaaaaaaaaa".to_string());
+    let mut theirs = Task::new(7, "Theirs".to_string(), String::new(), Priority::Low);
+    theirs.uid = Some("bbbbbbbbbb".to_string());
+    storage.save_task(&ours).unwrap();
+    storage.save_task(&theirs).unwrap();
+    
+    // A task from their branch that depends on theirs by uid
+    let mut dependent = Task::new(3, "Dependent".to_string(), String::new(), Priority::Medium);
+    dependent.uid = Some("cccccccccc".to_string());
+    storage.save_task(&dependent).unwrap();
+    let path = temp_dir.path().join("cccccccccc.md");
+    let content = std::fs::read_to_string(&path).unwrap()
+        .replace("created_at:", "dependencies:\n- bbbbbbbbbb\ncreated_at:");
+    std::fs::write(&path, content).unwrap();
+    
+    let tasks = storage.list_all_tasks().unwrap();
+    let ids: Vec<u32> = tasks.iter().map(|t| t.id).collect();
+    assert_eq!(ids, vec![3, 7, 8]);
+    
+    let theirs = tasks.iter().find(|t| t.title == "Theirs").unwrap();
+    let dependent = storage.load_task(3).unwrap();
+    assert!(dependent.dependencies.contains(&theirs.id));
+    
+    // The new alias is persisted rather than recomputed on every load
+    assert_eq!(storage.list_all_tasks().unwrap(), tasks);
+    assert_eq!(storage.next_id().unwrap(), 9);
+}
+
+#[test]
+fn test_resolve_task_refs() {
+    let temp_dir = setup_test_dir();
+    let storage = TaskStorage::new(temp_dir.path().to_path_buf()).unwrap();
+    
+    for (id, uid) in [(1, "abcd111111"), (2, "abcd222222")] {
+        let mut task = Task::new(id, format!("Task {}", id), String::new(), Priority::Medium);
+        task.uid = Some(uid.to_string());
+        storage.save_task(&task).unwrap();
+    }
+    
+    assert_eq!(storage.resolve(&"#2".parse().unwrap()).unwrap(), 2);
+    assert_eq!(storage.resolve(&"abcd2".parse().unwrap()).unwrap(), 2);
+    assert!(storage.resolve(&"abcd".parse().unwrap()).is_err());
+    assert!(storage.resolve(&"ffff".parse().unwrap()).is_err());
+}
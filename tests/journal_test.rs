@@ -0,0 +1,128 @@
+mod common;
+
+use common::trusty;
+use trusty::config::ProjectConfig;
+use trusty::journal::{Journal, RecordingStore};
+use trusty::storage::{self, TaskStorage, TaskStore};
+use trusty::task::{Priority, Task, TaskStatus};
+
+fn setup() -> (tempfile::TempDir, TaskStorage, Journal) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let storage = TaskStorage::new(temp_dir.path().join("tasks")).unwrap();
+    let journal = Journal::new(temp_dir.path());
+    (temp_dir, storage, journal)
+}
+
+/// Run `f` against a recording wrapper and journal what it wrote, like `main` does for each command
+fn run(storage: &TaskStorage, journal: &Journal, command: &str, f: impl FnOnce(&dyn TaskStore)) {
+    let recorder = RecordingStore::new(storage);
+    f(&recorder);
+    journal.record(storage, command, recorder.into_changes()).unwrap();
+}
+
+fn add(storage: &dyn TaskStore, id: u32, title: &str) {
+    storage.save_task(&Task::new(id, title.to_string(), String::new(), Priority::Medium)).unwrap();
+}
+
+fn set_status(storage: &dyn TaskStore, id: u32, status: TaskStatus) {
+    let mut task = storage.load_task(id).unwrap();
+    task.set_status(status);
+    storage.save_task(&task).unwrap();
+}
+
+#[test]
+fn test_undo_and_redo_grouped_operation() {
+    let (_dir, storage, journal) = setup();
+    run(&storage, &journal, "add", |s| {
+        add(s, 1, "Parent");
+        add(s, 2, "Child");
+    });
+    let before = storage.list_all_tasks().unwrap();
+    
+    // A cascade touches several tasks, and some of them more than once
+    run(&storage, &journal, "complete 1 --all", |s| {
+        set_status(s, 1, TaskStatus::InProgress);
+        set_status(s, 1, TaskStatus::Done);
+        set_status(s, 2, TaskStatus::Done);
+    });
+    let after = storage.list_all_tasks().unwrap();
+    
+    let operations = journal.load().unwrap();
+    assert_eq!(operations.len(), 2);
+    assert_eq!(operations[1].changes.len(), 2);
+    
+    let undone = journal.undo(&storage, 1, false).unwrap();
+    assert_eq!(undone[0].command, "complete 1 --all");
+    assert_eq!(storage.list_all_tasks().unwrap(), before);
+    
+    journal.redo(&storage, 1, false).unwrap();
+    assert_eq!(storage.list_all_tasks().unwrap(), after);
+    
+    // Undoing both removes the created tasks again
+    journal.undo(&storage, 2, false).unwrap();
+    assert!(storage.list_all_tasks().unwrap().is_empty());
+}
+
+#[test]
+fn test_undo_refuses_to_clobber_later_edits() {
+    let (_dir, storage, journal) = setup();
+    run(&storage, &journal, "add", |s| add(s, 1, "Task"));
+    run(&storage, &journal, "set-status", |s| set_status(s, 1, TaskStatus::Done));
+    
+    // Edited outside trusty's journal, e.g. by hand
+    set_status(&storage, 1, TaskStatus::Blocked);
+    
+    assert!(journal.undo(&storage, 1, false).is_err());
+    assert_eq!(storage.load_task(1).unwrap().status, TaskStatus::Blocked);
+    
+    journal.undo(&storage, 1, true).unwrap();
+    assert_eq!(storage.load_task(1).unwrap().status, TaskStatus::Pending);
+}
+
+#[test]
+fn test_new_operation_clears_redo() {
+    let (_dir, storage, journal) = setup();
+    run(&storage, &journal, "add 1", |s| add(s, 1, "One"));
+    run(&storage, &journal, "add 2", |s| add(s, 2, "Two"));
+    
+    journal.undo(&storage, 1, false).unwrap();
+    run(&storage, &journal, "add 3", |s| add(s, 3, "Three"));
+    
+    assert!(journal.redo(&storage, 1, false).unwrap().is_empty());
+    let seqs: Vec<u64> = journal.load().unwrap().iter().map(|op| op.seq).collect();
+    assert_eq!(seqs, vec![1, 3]);
+}
+
+#[test]
+fn test_read_only_commands_are_not_journaled() {
+    let (_dir, storage, journal) = setup();
+    run(&storage, &journal, "add", |s| add(s, 1, "Task"));
+    run(&storage, &journal, "list", |s| {
+        s.list_all_tasks().unwrap();
+    });
+    run(&storage, &journal, "edit 1 (no changes)", |s| {
+        let task = s.load_task(1).unwrap();
+        s.save_task(&task).unwrap();
+    });
+    
+    assert_eq!(journal.load().unwrap().len(), 1);
+}
+
+#[test]
+fn test_failed_command_keeps_what_it_wrote_undoable() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["milestone", "add", "v1"]);
+    trusty(dir, &["add", "First"]);
+    trusty(dir, &["add", "Second"]);
+
+    // Assigns #1 and #2, then fails on the missing task
+    assert!(!common::run(dir, &["milestone", "assign", "v1", "1", "2", "999"]).status.success());
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    assert_eq!(store.load_task(2).unwrap().milestone.as_deref(), Some("v1"));
+
+    trusty(dir, &["undo"]);
+    assert_eq!(store.load_task(1).unwrap().milestone, None);
+    assert_eq!(store.load_task(2).unwrap().milestone, None);
+}
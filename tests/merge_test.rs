@@ -0,0 +1,241 @@
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+use chrono::{Duration, Timelike, Utc};
+use common::trusty;
+use trusty::history::{HistoryChange, HistoryEvent};
+use trusty::notes::Note;
+use trusty::relation::RelationKind;
+use trusty::storage::{merge_task_files, TaskStorage, TaskStore};
+use trusty::task::{Priority, Task, TaskStatus};
+
+/// Write `task` through the markdown backend and return the file contents
+fn render(task: &Task) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = TaskStorage::new(dir.path().to_path_buf()).unwrap();
+    storage.save_task(task).unwrap();
+    std::fs::read_to_string(storage.get_task_path(task.id)).unwrap()
+}
+
+/// Parse merged file contents back into a task
+fn parse(content: &str, id: u32) -> Task {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = TaskStorage::new(dir.path().to_path_buf()).unwrap();
+    std::fs::write(storage.get_task_path(id), content).unwrap();
+    storage.load_task(id).unwrap()
+}
+
+fn base_task() -> Task {
+    let mut task = Task::new(4, "Ship it".to_string(), "Original notes".to_string(), Priority::Medium);
+    task.tags = vec!["backend".to_string(), "api".to_string()];
+    task.add_dependency(1);
+    task.add_dependency(2);
+    task.updated_at = Utc::now() - Duration::hours(2);
+    task
+}
+
+#[test]
+fn test_merges_independent_field_changes() {
+    let base = base_task();
+    
+    let mut ours = base.clone();
+    ours.set_status(TaskStatus::InProgress);
+    ours.tags.push("urgent".to_string());
+    ours.add_dependency(3);
+    
+    let mut theirs = base.clone();
+    theirs.tags.retain(|t| t != "api");
+    theirs.tags.push("perf".to_string());
+    theirs.priority = Priority::High;
+    theirs.remove_dependency(1);
+    theirs.description = "Expanded notes".to_string();
+    
+    let outcome = merge_task_files(&render(&base), &render(&ours), &render(&theirs)).unwrap();
+    assert!(!outcome.conflicted);
+    
+    let merged = parse(&outcome.content, 4);
+    assert_eq!(merged.status, TaskStatus::InProgress);
+    assert_eq!(merged.priority, Priority::High);
+    assert_eq!(merged.tags, vec!["backend", "urgent", "perf"]);
+    assert_eq!(merged.dependencies, [2, 3].into_iter().collect());
+    assert_eq!(merged.description, "Expanded notes");
+}
+
+#[test]
+fn test_scalar_conflict_goes_to_latest_update() {
+    let base = base_task();
+    
+    let mut ours = base.clone();
+    ours.set_status(TaskStatus::Blocked);
+    ours.updated_at = Utc::now() - Duration::minutes(30);
+    
+    let mut theirs = base.clone();
+    theirs.set_status(TaskStatus::Done);
+    
+    let outcome = merge_task_files(&render(&base), &render(&ours), &render(&theirs)).unwrap();
+    let merged = parse(&outcome.content, 4);
+    assert_eq!(merged.status, TaskStatus::Done);
+    assert_eq!(merged.updated_at, theirs.updated_at);
+    assert_eq!(merged.completed_at, theirs.completed_at);
+    
+    // Swapping sides gives the same answer
+    let outcome = merge_task_files(&render(&base), &render(&theirs), &render(&ours)).unwrap();
+    assert_eq!(parse(&outcome.content, 4).status, TaskStatus::Done);
+}
+
+#[test]
+fn test_description_conflict_gets_markers() {
+    let base = base_task();
+    let mut ours = base.clone();
+    ours.description = "Our notes".to_string();
+    let mut theirs = base.clone();
+    theirs.description = "Their notes".to_string();
+    theirs.priority = Priority::Low;
+    
+    let outcome = merge_task_files(&render(&base), &render(&ours), &render(&theirs)).unwrap();
+    assert!(outcome.conflicted);
+    
+    // Frontmatter stays clean and parseable; only the body carries the markers
+    let merged = parse(&outcome.content, 4);
+    assert_eq!(merged.priority, Priority::Low);
+    assert_eq!(merged.description, "<<<<<<< ours\nOur notes\n=======\nTheir notes\n>>>>>>> theirs\n");
+}
+
+#[test]
+fn test_add_add_merge_without_ancestor() {
+    let ours = base_task();
+    let mut theirs = base_task();
+    theirs.tags = vec!["docs".to_string()];
+    
+    let outcome = merge_task_files("", &render(&ours), &render(&theirs)).unwrap();
+    assert!(!outcome.conflicted);
+    assert_eq!(parse(&outcome.content, 4).tags, vec!["backend", "api", "docs"]);
+}
+
+#[test]
+fn test_history_keeps_both_sides_in_order() {
+    let event = |minutes: i64, to: TaskStatus| HistoryEvent {
+        at: Utc::now() - Duration::minutes(minutes),
+        actor: "human".to_string(),
+        change: HistoryChange::Status { from: TaskStatus::Pending, to },
+    };
+    let mut base = base_task();
+    base.history = vec![event(60, TaskStatus::Blocked)];
+    
+    let mut ours = base.clone();
+    ours.history.push(event(5, TaskStatus::InProgress));
+    let mut theirs = base.clone();
+    theirs.history.push(event(30, TaskStatus::Deferred));
+    
+    let outcome = merge_task_files(&render(&base), &render(&ours), &render(&theirs)).unwrap();
+    let merged = parse(&outcome.content, 4);
+    assert_eq!(merged.history, vec![base.history[0].clone(), theirs.history[1].clone(), ours.history[1].clone()]);
+}
+
+#[test]
+fn test_relations_merge_kind_by_kind() {
+    let mut base = base_task();
+    base.add_relation(RelationKind::RelatesTo, 7);
+    base.add_relation(RelationKind::After, 8);
+    
+    let mut ours = base.clone();
+    ours.add_relation(RelationKind::RelatesTo, 9);
+    ours.remove_relation(RelationKind::After, 8);
+    let mut theirs = base.clone();
+    theirs.add_relation(RelationKind::Duplicates, 5);
+    
+    let outcome = merge_task_files(&render(&base), &render(&ours), &render(&theirs)).unwrap();
+    let merged = parse(&outcome.content, 4);
+    let relations: Vec<(RelationKind, u32)> = merged.relations.iter().map(|r| (r.kind, r.task)).collect();
+    assert_eq!(relations, vec![(RelationKind::RelatesTo, 7), (RelationKind::RelatesTo, 9), (RelationKind::Duplicates, 5)]);
+}
+
+#[test]
+fn test_notes_from_both_sides_are_kept() {
+    let note = |minutes: i64, author: &str| Note {
+        at: (Utc::now() - Duration::minutes(minutes)).with_nanosecond(0).unwrap(),
+        author: author.to_string(),
+        text: format!("Checked in by {}", author),
+    };
+    let mut base = base_task();
+    base.notes = vec![note(60, "alice")];
+    
+    let mut ours = base.clone();
+    ours.notes.push(note(5, "bob"));
+    let mut theirs = base.clone();
+    theirs.notes.push(note(30, "agent-7"));
+    theirs.description = "Expanded notes".to_string();
+    
+    let outcome = merge_task_files(&render(&base), &render(&ours), &render(&theirs)).unwrap();
+    assert!(!outcome.conflicted);
+    let merged = parse(&outcome.content, 4);
+    assert_eq!(merged.description, "Expanded notes");
+    assert_eq!(merged.notes, vec![base.notes[0].clone(), theirs.notes[1].clone(), ours.notes[1].clone()]);
+}
+
+#[test]
+fn test_reordered_subtasks_keep_their_order() {
+    let mut base = base_task();
+    base.subtasks = vec![5, 6, 7];
+    
+    let mut ours = base.clone();
+    ours.subtasks = vec![7, 5, 6];
+    let mut theirs = base.clone();
+    theirs.priority = Priority::High;
+    
+    let outcome = merge_task_files(&render(&base), &render(&ours), &render(&theirs)).unwrap();
+    let merged = parse(&outcome.content, 4);
+    assert_eq!(merged.subtasks, vec![7, 5, 6]);
+    assert_eq!(merged.priority, Priority::High);
+}
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_AUTHOR_NAME", "test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .output()
+        .expect("failed to run git");
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_git_merge_uses_driver() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    git(dir, &["init", "-q", "-b", "main"]);
+    trusty(dir, &["init", "--git"]);
+    
+    // Point the driver at the binary under test instead of whatever is on PATH
+    let driver = format!("{} merge-driver %O %A %B", env!("CARGO_BIN_EXE_trusty"));
+    git(dir, &["config", "merge.trusty.driver", &driver]);
+    
+    let attributes = std::fs::read_to_string(dir.join(".gitattributes")).unwrap();
+    assert!(attributes.contains(".trusty/tasks/*.md merge=trusty"));
+    
+    trusty(dir, &["add", "Shared task", "--tags", "a"]);
+    git(dir, &["add", "-A"]);
+    git(dir, &["commit", "-qm", "base"]);
+    
+    git(dir, &["checkout", "-qb", "feature"]);
+    trusty(dir, &["set-status", "--id", "1", "--status", "in-progress"]);
+    git(dir, &["commit", "-qam", "start"]);
+    
+    git(dir, &["checkout", "-q", "main"]);
+    trusty(dir, &["edit", "1", "--priority", "high"]);
+    git(dir, &["commit", "-qam", "prioritize"]);
+    
+    git(dir, &["merge", "-q", "--no-edit", "feature"]);
+    
+    let storage = TaskStorage::new(dir.join(".trusty").join("tasks")).unwrap();
+    let task = storage.load_task(1).unwrap();
+    assert_eq!(task.status, TaskStatus::InProgress);
+    assert_eq!(task.priority, Priority::High);
+    assert!(git(dir, &["status", "--porcelain"]).is_empty());
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::{run, trusty};
+use trusty::config::ProjectConfig;
+use trusty::fields::{FieldDef, FieldType};
+use trusty::milestone::Milestones;
+use trusty::storage;
+
+#[test]
+fn test_milestone_progress_and_blockers() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["milestone", "add", "v1.2", "--due", "+30d", "--description", "Search release"]);
+    assert!(!run(dir, &["milestone", "add", "v1.2"]).status.success());
+
+    trusty(dir, &["add", "Schema migration"]);
+    trusty(dir, &["add", "Search API", "--dependencies", "1", "--milestone", "v1.2"]);
+    trusty(dir, &["add", "Search UI"]);
+    trusty(dir, &["add", "Old search page"]);
+    trusty(dir, &["add", "Unplanned"]);
+    trusty(dir, &["milestone", "assign", "v1.2", "3", "4"]);
+    assert!(!run(dir, &["milestone", "assign", "v1.3", "5"]).status.success());
+    assert!(!run(dir, &["add", "Typo", "--milestone", "v1.3"]).status.success());
+
+    trusty(dir, &["complete", "3"]);
+    trusty(dir, &["set-status", "--id", "4", "--status", "cancelled"]);
+
+    let show = trusty(dir, &["milestone", "show", "v1.2"]);
+    assert!(show.contains("Search release"), "{}", show);
+    assert!(show.contains("50%  (1/2 done, 1 cancelled)"), "{}", show);
+    assert!(show.contains("#2 - Search API (waiting on #1 - Schema migration)"), "{}", show);
+    assert!(show.contains("Projected finish:"), "{}", show);
+    assert!(show.contains("on track"), "{}", show);
+
+    let list = trusty(dir, &["list", "--milestone", "v1.2", "--all"]);
+    assert!(list.contains("Search API") && list.contains("Search UI"), "{}", list);
+    assert!(!list.contains("Unplanned") && !list.contains("Schema migration"), "{}", list);
+    assert!(!run(dir, &["list", "--milestone", "v9"]).status.success());
+
+    assert!(trusty(dir, &["show", "2"]).contains("Milestone: v1.2"));
+    assert!(trusty(dir, &["milestone", "list"]).contains("v1.2"));
+}
+
+#[test]
+fn test_removing_a_milestone_unassigns_its_tasks() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["milestone", "add", "Onboarding"]);
+    trusty(dir, &["add", "Welcome email", "--milestone", "Onboarding"]);
+    trusty(dir, &["add", "Tour"]);
+    trusty(dir, &["edit", "2", "--milestone", "Onboarding"]);
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    assert_eq!(store.load_task(2).unwrap().milestone.as_deref(), Some("Onboarding"));
+    trusty(dir, &["edit", "2", "--milestone", "none"]);
+    assert_eq!(store.load_task(2).unwrap().milestone, None);
+
+    trusty(dir, &["milestone", "edit", "Onboarding", "--due", "2030-01-31"]);
+    let milestones = Milestones::new(&dir.join(".trusty"));
+    assert_eq!(milestones.get("Onboarding").unwrap().due.unwrap().to_string(), "2030-01-31");
+
+    assert!(trusty(dir, &["milestone", "remove", "Onboarding"]).contains("1 task(s) unassigned"));
+    assert_eq!(store.load_task(1).unwrap().milestone, None);
+    assert!(milestones.load().unwrap().is_empty());
+}
+
+#[test]
+fn test_milestone_custom_field_still_works() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    let mut config = ProjectConfig::load(&dir.join(".trusty")).unwrap();
+    config.custom_fields.insert("milestone".to_string(), FieldDef { kind: FieldType::String, values: vec![], description: None });
+    config.save(&dir.join(".trusty")).unwrap();
+
+    trusty(dir, &["milestone", "add", "v2"]);
+    trusty(dir, &["add", "Billing", "--field", "milestone=M3", "--milestone", "v2"]);
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    let task = store.load_task(1).unwrap();
+    assert_eq!(task.milestone.as_deref(), Some("v2"));
+    assert_eq!(task.extra.get("milestone").and_then(|v| v.as_str()), Some("M3"));
+    let file = std::fs::read_to_string(dir.join(".trusty/tasks/1.md")).unwrap();
+    assert!(file.contains("in_milestone: v2") && file.contains("milestone: M3"), "{}", file);
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use std::path::Path;
+
+use common::{run_as, succeeded, trusty};
+use trusty::config::ProjectConfig;
+use trusty::storage;
+
+fn trusty_as(dir: &Path, actor: &str, args: &[&str]) -> String {
+    succeeded(run_as(dir, actor, args), args)
+}
+
+#[test]
+fn test_notes_survive_description_edits() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Flaky import", "--description", "Imports fail about once a day."]);
+
+    trusty_as(dir, "alice", &["note", "1", "Reproduced with the March export."]);
+    trusty_as(dir, "agent-7", &["note", "#1", "Root cause: the connection pool is too small.\nRaising it to 20."]);
+    trusty(dir, &["edit", "1", "--description", "Imports fail when the pool runs out."]);
+
+    let file = std::fs::read_to_string(dir.join(".trusty/tasks/1.md")).unwrap();
+    assert!(file.contains("Imports fail when the pool runs out.\n\n## Notes\n\n### "), "{}", file);
+    assert!(file.contains(" — agent-7\n\nRoot cause"), "{}", file);
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    let task = store.load_task(1).unwrap();
+    assert_eq!(task.description, "Imports fail when the pool runs out.");
+    assert_eq!(task.notes.len(), 2);
+    assert_eq!(task.notes[0].author, "alice");
+    assert_eq!(task.notes[1].text, "Root cause: the connection pool is too small.\nRaising it to 20.");
+
+    let show = trusty(dir, &["show", "1"]);
+    assert!(show.contains("Notes:"), "{}", show);
+    assert!(show.contains("    Raising it to 20."), "{}", show);
+    let history = trusty(dir, &["history", "1"]);
+    assert!(history.contains("edited notes"), "{}", history);
+}
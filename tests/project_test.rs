@@ -0,0 +1,69 @@
+mod common;
+
+use std::process::Command;
+
+use common::{run, succeeded, trusty};
+use trusty::project;
+
+#[test]
+fn test_discover_finds_nearest_project() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let root = temp_dir.path();
+    let nested = root.join("sub").join("deeper");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::create_dir(root.join(".trusty")).unwrap();
+
+    assert_eq!(project::discover(&nested), Some(root.join(".trusty")));
+
+    // A nested project shadows the outer one
+    std::fs::create_dir(root.join("sub").join(".trusty")).unwrap();
+    assert_eq!(project::discover(&nested), Some(root.join("sub").join(".trusty")));
+}
+
+#[test]
+fn test_commands_run_from_subdirectories() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let root = temp_dir.path();
+    let src = root.join("src");
+    std::fs::create_dir(&src).unwrap();
+
+    trusty(root, &["init"]);
+    trusty(&src, &["add", "Found from src"]);
+
+    assert!(!src.join(".trusty").exists());
+    assert!(trusty(&src, &["show", "1"]).contains("Found from src"));
+}
+
+#[test]
+fn test_project_flag_and_env_override() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project_dir = temp_dir.path().join("project");
+    let elsewhere = temp_dir.path().join("elsewhere");
+    std::fs::create_dir_all(&project_dir).unwrap();
+    std::fs::create_dir_all(&elsewhere).unwrap();
+
+    let project = project_dir.to_str().unwrap();
+    trusty(&elsewhere, &["init", "--project", project]);
+    trusty(&elsewhere, &["add", "Remote task", "--project", project]);
+    assert!(project_dir.join(".trusty").join("tasks").join("1.md").exists());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_trusty"))
+        .args(["show", "1"])
+        .current_dir(&elsewhere)
+        .env(project::TRUSTY_DIR_ENV, project_dir.join(".trusty"))
+        .output()
+        .unwrap();
+    let shown = succeeded(output, &["show", "1"]);
+    assert!(shown.contains("Remote task"));
+    assert!(!elsewhere.join(".trusty").exists());
+}
+
+#[test]
+fn test_missing_project_is_an_error() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let output = run(temp_dir.path(), &["list"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No trusty project found"));
+    assert!(!temp_dir.path().join(".trusty").exists());
+}
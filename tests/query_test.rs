@@ -0,0 +1,89 @@
+mod common;
+
+use std::path::Path;
+
+use common::{run, trusty};
+use trusty::config::ProjectConfig;
+use trusty::storage;
+use trusty::task::TaskStatus;
+
+fn setup(dir: &Path) {
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "OAuth login", "--tags", "backend", "--priority", "high"]);
+    trusty(dir, &["add", "Token storage spike", "--tags", "backend,spike"]);
+    trusty(dir, &["add", "Cache spike", "--tags", "spike", "--dependencies", "1"]);
+    trusty(dir, &["add", "Login docs", "--priority", "low", "--due", "+2d"]);
+}
+
+#[test]
+fn test_list_and_next_take_a_query() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    setup(dir);
+    trusty(dir, &["complete", "2"]);
+
+    let list = trusty(dir, &["list", "tag:backend,spike", "(-tag:spike OR priority:high)"]);
+    assert!(list.contains("OAuth login"), "{}", list);
+    assert!(!list.contains("Cache spike") && !list.contains("Login docs"), "{}", list);
+
+    // A query on status shows finished tasks without --all
+    let list = trusty(dir, &["list", "status:done"]);
+    assert!(list.contains("Token storage spike") && !list.contains("OAuth login"), "{}", list);
+
+    let list = trusty(dir, &["list", "--all", "--", "-has:deps", "due<7d"]);
+    assert!(list.contains("Login docs") && !list.contains("Cache spike"), "{}", list);
+
+    assert!(trusty(dir, &["next", "login", "priority:low"]).contains("#4 - Login docs"));
+
+    let output = run(dir, &["list", "priorty:high"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("unknown filter 'priorty' (did you mean priority?)"), "{}", stderr);
+}
+
+#[test]
+fn test_set_status_where_updates_all_matches_in_one_step() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    setup(dir);
+
+    let output = trusty(dir, &["set-status", "--where", "tag:spike is:open", "--status", "cancelled"]);
+    assert!(output.contains("Updated 2 tasks"), "{}", output);
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    assert_eq!(store.load_task(2).unwrap().status, TaskStatus::Cancelled);
+    assert_eq!(store.load_task(3).unwrap().status, TaskStatus::Cancelled);
+    assert_eq!(store.load_task(1).unwrap().status, TaskStatus::Pending);
+
+    assert!(trusty(dir, &["set-status", "--where", "tag:nothing", "--status", "done"]).contains("No tasks match"));
+    assert!(!run(dir, &["set-status", "--id", "1", "--where", "tag:spike", "--status", "done"]).status.success());
+
+    trusty(dir, &["undo"]);
+    assert_eq!(store.load_task(2).unwrap().status, TaskStatus::Pending);
+    assert_eq!(store.load_task(3).unwrap().status, TaskStatus::Pending);
+}
+
+#[test]
+fn test_bulk_commands_take_where() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    setup(dir);
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+
+    trusty(dir, &["edit", "--where", "tag:spike", "--tag", "research", "--untag", "spike", "--priority", "low"]);
+    for id in [2, 3] {
+        let task = store.load_task(id).unwrap();
+        assert!(task.tags.contains(&"research".to_string()) && !task.tags.contains(&"spike".to_string()), "{:?}", task.tags);
+        assert_eq!(task.priority, trusty::task::Priority::Low);
+    }
+    assert_eq!(store.load_task(1).unwrap().tags, vec!["backend".to_string()]);
+
+    let output = trusty(dir, &["complete", "--where", "tag:research"]);
+    assert!(output.contains("Updated 2 tasks"), "{}", output);
+    assert_eq!(store.load_task(3).unwrap().status, TaskStatus::Done);
+
+    let output = trusty(dir, &["delete", "--where", "status:done"]);
+    assert!(output.contains("Moved 2 tasks to the trash") && output.contains("trash restore 2 3"), "{}", output);
+    assert_eq!(store.list_all_tasks().unwrap().iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 4]);
+
+    assert!(!run(dir, &["edit", "1", "--where", "tag:backend", "--tag", "x"]).status.success());
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::trusty;
+use trusty::config::ProjectConfig;
+use trusty::dates;
+use trusty::storage;
+use trusty::task::TaskStatus;
+
+#[test]
+fn test_completing_an_occurrence_creates_the_next() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Rotate staging credentials", "--description", "See the runbook", "-t", "ops,security",
+        "--recur", "monthly", "--recur-count", "2"]);
+    trusty(dir, &["add", "Unrelated"]);
+
+    let output = trusty(dir, &["complete", "1"]);
+    assert!(output.contains("Next occurrence: task #3"), "{}", output);
+    // Completing the same occurrence again doesn't create another
+    let output = trusty(dir, &["set-status", "--id", "1", "--status", "done"]);
+    assert!(!output.contains("Next occurrence"), "{}", output);
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    let (first, next) = (store.load_task(1).unwrap(), store.load_task(3).unwrap());
+    assert_eq!(first.recurrence, None);
+    assert_eq!(next.title, first.title);
+    assert_eq!(next.description, "See the runbook");
+    assert_eq!(next.tags, vec!["ops", "security"]);
+    assert_eq!(next.status, TaskStatus::Pending);
+    assert_eq!(next.series, Some(1));
+    assert_eq!(next.due, dates::today().checked_add_months(chrono::Months::new(1)));
+    assert_eq!(next.recurrence.as_ref().and_then(|r| r.remaining), Some(1));
+
+    // The second of two occurrences ends the series
+    let output = trusty(dir, &["complete", "3"]);
+    assert!(!output.contains("Next occurrence"), "{}", output);
+    assert_eq!(store.list_all_tasks().unwrap().len(), 3);
+    assert!(trusty(dir, &["recur", "list"]).contains("No recurring tasks"));
+}
+
+#[test]
+fn test_stop_a_series() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Triage dependabot PRs", "--recur", "every mon"]);
+    trusty(dir, &["complete", "1"]);
+
+    let list = trusty(dir, &["recur", "list"]);
+    assert!(list.contains("#2 - Triage dependabot PRs"), "{}", list);
+    assert!(list.contains("every mon"), "{}", list);
+
+    // Any task in the series names it
+    trusty(dir, &["recur", "stop", "1"]);
+    assert!(trusty(dir, &["recur", "list"]).contains("No recurring tasks"));
+    let output = trusty(dir, &["complete", "2"]);
+    assert!(!output.contains("Next occurrence"), "{}", output);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{run, trusty};
+use trusty::config::ProjectConfig;
+use trusty::relation::{Relation, RelationKind};
+use trusty::storage;
+
+#[test]
+fn test_relations_show_from_both_sides() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Login page"]);
+    trusty(dir, &["add", "Build login"]);
+    trusty(dir, &["add", "Password reset"]);
+
+    trusty(dir, &["relate", "2", "duplicates", "1"]);
+    trusty(dir, &["relate", "3", "follow-up-of", "#1"]);
+    trusty(dir, &["relate", "1", "relates-to", "3"]);
+    assert!(trusty(dir, &["relate", "3", "relates-to", "1"]).contains("already relates-to"));
+    assert!(!run(dir, &["relate", "1", "supersedes", "1"]).status.success());
+    assert!(!run(dir, &["relate", "1", "supersedes", "9"]).status.success());
+    assert!(!run(dir, &["relate", "1", "blocks", "2"]).status.success());
+
+    let show = trusty(dir, &["show", "1"]);
+    assert!(show.contains("duplicated-by #2 - Build login"), "{}", show);
+    assert!(show.contains("followed-up-by #3 - Password reset"), "{}", show);
+    assert!(show.contains("relates-to #3 - Password reset"), "{}", show);
+
+    // relates-to reads the same from either side, so either task can drop it
+    trusty(dir, &["unrelate", "3", "relates-to", "1"]);
+    assert!(!run(dir, &["unrelate", "3", "duplicates", "1"]).status.success());
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    assert!(store.load_task(1).unwrap().relations.is_empty());
+    assert_eq!(store.load_task(2).unwrap().relations, vec![Relation { kind: RelationKind::Duplicates, task: 1 }]);
+
+    // Trashing the original drops the link, and restoring it puts it back
+    trusty(dir, &["delete", "1"]);
+    assert!(store.load_task(2).unwrap().relations.is_empty());
+    trusty(dir, &["trash", "restore", "1"]);
+    assert_eq!(store.load_task(2).unwrap().relations.len(), 1);
+}
+
+#[test]
+fn test_after_orders_next_without_blocking() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Write the docs", "--priority", "high"]);
+    trusty(dir, &["add", "Record the screencast", "--priority", "low"]);
+
+    trusty(dir, &["relate", "1", "after", "2"]);
+    assert!(trusty(dir, &["next"]).contains("#2 - Record the screencast"));
+
+    // Only a suggestion: the task can still be started
+    trusty(dir, &["set-status", "--id", "1", "--status", "in-progress"]);
+    trusty(dir, &["complete", "2"]);
+    assert!(trusty(dir, &["show", "2"]).contains("before #1 - Write the docs"));
+}
@@ -0,0 +1,297 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use trusty::defer::WakeCondition;
+use trusty::estimate::{Estimate, EstimateUnit};
+use trusty::notes::Note;
+use trusty::recur::{Recurrence, Rule, Unit};
+use trusty::relation::{Relation, RelationKind};
+use trusty::storage::{JsonStore, SqliteStore, TaskStorage, TaskStore};
+use trusty::task::{Complexity, Priority, Task, TaskStatus};
+use trusty::timelog::TimeEntry;
+
+/// Strings that broke the old line-based frontmatter parser or are easy to
+/// get wrong in YAML.
+const TRICKY_STRINGS: &[&str] = &[
+    "",
+    " ",
+    "Fix: the parser",
+    "key: value: more",
+    "She said \"hi\"",
+    "escaped \\\" quote",
+    "C:\\Users\\dev\\path",
+    "it's a 'single' quote",
+    "a, b, c",
+    "[not, a, list]",
+    "{not: a map}",
+    "---",
+    "- dash item",
+    "# not a heading",
+    "#hashtag",
+    "yes",
+    "no",
+    "null",
+    "~",
+    "123",
+    "0x1F",
+    "1e10",
+    "2024-01-01",
+    "&anchor",
+    "*alias",
+    "!tag",
+    "%directive",
+    "@mention",
+    "`backticks`",
+    "trailing space ",
+    " leading space",
+    "tab\there",
+    "line one\nline two",
+    "windows\r\nline",
+    "ünïcödé — ✓ 日本語 🚀",
+    "zero\u{200b}width",
+    "combining e\u{301}",
+];
+
+const TRICKY_DESCRIPTIONS: &[&str] = &[
+    "",
+    "Plain description",
+    "---\nlooks like frontmatter\n---",
+    "Body with\n---\nseparator",
+    "# Heading inside description\n\nText",
+    "\n\nLeading blank lines",
+    "Trailing newline\n",
+    "Trailing blank lines\n\n\n",
+    "id: 99\nstatus: done",
+    "```yaml\ntitle: nope\n```",
+    "- [ ] checkbox\n- [x] done",
+    "Mixed\r\nline\r\nendings",
+    "Quotes \"double\" and 'single' and \\backslash\\",
+    "Emoji 🎉 and ünïcödé",
+];
+
+fn stores(dir: &std::path::Path) -> Vec<Box<dyn TaskStore>> {
+    vec![
+        Box::new(TaskStorage::new(dir.join("tasks")).unwrap()),
+        Box::new(JsonStore::new(dir.join("tasks.json")).unwrap()),
+        Box::new(SqliteStore::new(dir.join("tasks.db")).unwrap()),
+    ]
+}
+
+fn assert_roundtrip(storage: &dyn TaskStore, task: &Task) {
+    storage.save_task(task).unwrap();
+    let loaded = storage.load_task(task.id).unwrap();
+    assert_eq!(&loaded, task, "round-trip changed task #{}", task.id);
+}
+
+/// Small deterministic generator so failures are reproducible without extra dependencies
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn string(&mut self) -> String {
+        const ALPHABET: &[char] = &[
+            'a', 'Z', '0', ' ', ' ', ':', '"', '\'', '\\', ',', '[', ']', '{', '}', '#', '-', '-', '-',
+            '\n', '\n', '\r', '\t', '&', '*', '!', '|', '>', '%', '@', '`', 'é', '—', '✓', '日', '🚀',
+        ];
+        let len = self.below(40);
+        (0..len).map(|_| ALPHABET[self.below(ALPHABET.len())]).collect()
+    }
+
+    fn pick<T: Clone>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())].clone()
+    }
+
+    fn maybe<T>(&mut self, make: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        (self.below(2) == 0).then(|| make(self))
+    }
+
+    fn timestamp(&mut self) -> chrono::DateTime<Utc> {
+        Utc.timestamp_opt(self.below(2_000_000_000) as i64, 0).unwrap()
+    }
+
+    fn date(&mut self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2000, 1, 1).unwrap() + chrono::Days::new(self.below(20_000) as u64)
+    }
+
+    /// Half-hour steps, which survive a trip through text
+    fn amount(&mut self) -> f64 {
+        self.below(200) as f64 / 2.0
+    }
+}
+
+fn random_task(rng: &mut Lcg, id: u32) -> Task {
+    let mut task = Task::new(id, rng.string(), rng.string(), rng.pick(&[Priority::High, Priority::Medium, Priority::Low]));
+    task.status = rng.pick(&[
+        TaskStatus::Pending,
+        TaskStatus::InProgress,
+        TaskStatus::Done,
+        TaskStatus::Blocked,
+        TaskStatus::Deferred,
+        TaskStatus::Cancelled,
+    ]);
+    task.complexity = rng.pick(&[None, Some(Complexity::Simple), Some(Complexity::Medium), Some(Complexity::Complex)]);
+    for _ in 0..rng.below(4) {
+        task.dependencies.insert(rng.below(1000) as u32);
+    }
+    task.subtasks = (0..rng.below(4)).map(|_| rng.below(1000) as u32).collect();
+    task.tags = (0..rng.below(4)).map(|_| rng.string()).collect();
+    task.created_at = Utc.timestamp_opt(rng.below(2_000_000_000) as i64, rng.below(1_000_000_000) as u32).unwrap();
+    task.updated_at = Utc.timestamp_opt(rng.below(2_000_000_000) as i64, rng.below(1_000_000_000) as u32).unwrap();
+    if rng.below(2) == 0 {
+        task.completed_at = Some(Utc::now());
+    }
+    task.notes = (0..rng.below(3))
+        .map(|_| Note { at: rng.timestamp(), author: rng.pick(&["human", "agent-7", "ünïcödé"]).to_string(), text: rng.string() })
+        .collect();
+    // Relations are kept sorted, as `Task::add_relation` does
+    task.relations = (0..rng.below(3))
+        .map(|_| Relation { kind: rng.pick(&RelationKind::ALL), task: rng.below(1000) as u32 })
+        .collect();
+    task.relations.sort();
+    task.relations.dedup();
+    task.estimate = rng.maybe(|rng| {
+        let value = rng.amount();
+        let spread = rng.below(2) == 0;
+        Estimate {
+            value,
+            unit: rng.pick(&[EstimateUnit::Points, EstimateUnit::Hours]),
+            optimistic: spread.then_some(value / 2.0),
+            pessimistic: spread.then_some(value * 2.0),
+        }
+    });
+    task.milestone = rng.maybe(Lcg::string);
+    task.time_log = (0..rng.below(3))
+        .map(|_| TimeEntry {
+            started_at: rng.timestamp(),
+            minutes: rng.below(600) as u32,
+            actor: rng.pick(&["human", "agent-7"]).to_string(),
+            note: rng.maybe(Lcg::string),
+        })
+        .collect();
+    task.recurrence = rng.maybe(|rng| {
+        let every = Rule::Every { count: rng.below(12) as u32 + 1, unit: rng.pick(&[Unit::Day, Unit::Week, Unit::Month, Unit::Year]) };
+        let rule = rng.pick(&[
+            every,
+            Rule::Cron { days: Some(vec![1, 15]), months: None, weekdays: None },
+            Rule::Cron { days: None, months: Some(vec![3]), weekdays: Some(vec![1, 5]) },
+        ]);
+        Recurrence { rule, until: rng.maybe(Lcg::date), remaining: rng.maybe(|rng| rng.below(10) as u32) }
+    });
+    task.series = rng.maybe(|rng| rng.below(1000) as u32);
+    task.deferred_until = rng.maybe(Lcg::date);
+    task.wake_when = rng.maybe(|rng| match rng.below(2) {
+        0 => WakeCondition::DependenciesDone,
+        // Paths come from the command line, so they never start or end with whitespace
+        _ => WakeCondition::FileExists(rng.pick(&["build/done", "dir with spaces/ünï.md", "C:\\out\\x", "a: b", "#x", "\"q\""]).to_string()),
+    });
+    task
+}
+
+#[test]
+fn test_tricky_titles_and_tags_roundtrip() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    
+    for storage in stores(temp_dir.path()) {
+        for (i, s) in TRICKY_STRINGS.iter().enumerate() {
+            let mut task = Task::new(i as u32 + 1, s.to_string(), s.to_string(), Priority::Medium);
+            task.tags = vec![s.to_string(), "plain".to_string(), format!("{},{}", s, s)];
+            assert_roundtrip(storage.as_ref(), &task);
+        }
+    }
+}
+
+#[test]
+fn test_tricky_descriptions_roundtrip() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    
+    for storage in stores(temp_dir.path()) {
+        for (i, d) in TRICKY_DESCRIPTIONS.iter().enumerate() {
+            let mut task = Task::new(i as u32 + 1, "Title: with --- stuff".to_string(), d.to_string(), Priority::Low);
+            assert_roundtrip(storage.as_ref(), &task);
+            
+            // A notes section after the description must not change it
+            task.notes.push(Note { at: Utc.timestamp_opt(1_700_000_000, 0).unwrap(), author: "human".to_string(), text: d.to_string() });
+            assert_roundtrip(storage.as_ref(), &task);
+        }
+    }
+}
+
+#[test]
+fn test_generated_tasks_roundtrip() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut rng = Lcg(0x5eed);
+    
+    for storage in stores(temp_dir.path()) {
+        for id in 1..=300 {
+            let task = random_task(&mut rng, id);
+            assert_roundtrip(storage.as_ref(), &task);
+        }
+        assert_eq!(storage.list_all_tasks().unwrap().len(), 300);
+    }
+}
+
+#[test]
+fn test_markdown_frontmatter_is_plain_yaml() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let storage = TaskStorage::new(temp_dir.path().to_path_buf()).unwrap();
+    
+    let mut task = Task::new(7, "Deploy: v2".to_string(), "Body".to_string(), Priority::High);
+    task.add_dependency(3);
+    task.add_dependency(1);
+    task.tags = vec!["a, b".to_string()];
+    storage.save_task(&task).unwrap();
+    
+    let content = std::fs::read_to_string(storage.get_task_path(7)).unwrap();
+    let frontmatter = content.split("---\n").nth(1).unwrap();
+    let value: serde_yaml::Value = serde_yaml::from_str(frontmatter).unwrap();
+    
+    assert_eq!(value["title"].as_str(), Some("Deploy: v2"));
+    assert_eq!(value["status"].as_str(), Some("pending"));
+    assert_eq!(value["dependencies"], serde_yaml::from_str::<serde_yaml::Value>("[1, 3]").unwrap());
+    assert_eq!(value["tags"][0].as_str(), Some("a, b"));
+    assert!(content.contains("\n# Deploy: v2\n\nBody"));
+}
+
+#[test]
+fn test_legacy_task_files_still_load() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let storage = TaskStorage::new(temp_dir.path().to_path_buf()).unwrap();
+    
+    // Format written by earlier versions of trusty
+    let legacy = "---
+id: 4
+title: \"Fix C:\\projects \\\"quoted\\\" path\"
+status: in-progress
+priority: high
+complexity: medium
+dependencies: [2, 3]
+subtasks: [5]
+tags: [\"backend\", \"api\"]
+created_at: 2024-08-01T10:00:00+00:00
+updated_at: 2024-08-02T10:00:00+00:00
+---
+
+# Fix C:\\projects \"quoted\" path
+
+Legacy description";
+    std::fs::write(storage.get_task_path(4), legacy).unwrap();
+    
+    let task = storage.load_task(4).unwrap();
+    assert_eq!(task.title, "Fix C:\\projects \"quoted\" path");
+    assert_eq!(task.status, TaskStatus::InProgress);
+    assert_eq!(task.complexity, Some(Complexity::Medium));
+    assert_eq!(task.dependencies.len(), 2);
+    assert_eq!(task.subtasks, vec![5]);
+    assert_eq!(task.tags, vec!["backend".to_string(), "api".to_string()]);
+    assert_eq!(task.description, "Legacy description");
+    
+    // Re-saving upgrades the file without losing anything
+    storage.save_task(&task).unwrap();
+    assert_eq!(storage.load_task(4).unwrap(), task);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use std::path::Path;
+
+use common::{run_as, succeeded, trusty};
+use trusty::config::ProjectConfig;
+use trusty::storage;
+
+fn trusty_as(dir: &Path, actor: &str, args: &[&str]) -> String {
+    succeeded(run_as(dir, actor, args), args)
+}
+
+#[test]
+fn test_timers_are_per_actor() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Login form"]);
+    trusty(dir, &["add", "Session store"]);
+
+    trusty_as(dir, "alice", &["start", "1"]);
+    trusty_as(dir, "bob", &["start", "2"]);
+    // Starting another task stops and logs the running timer first
+    let output = trusty_as(dir, "alice", &["start", "2"]);
+    assert!(output.contains("Stopped the timer on task #1"), "{}", output);
+    assert!(trusty_as(dir, "alice", &["stop"]).contains("on task #2"));
+    assert!(trusty_as(dir, "alice", &["stop"]).contains("No timer is running"));
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    let login = store.load_task(1).unwrap();
+    assert_eq!(login.time_log.len(), 1);
+    assert_eq!(login.time_log[0].actor, "alice");
+    assert_eq!(login.time_log[0].minutes, 1);
+
+    // Bob's timer kept running through all of that
+    assert!(trusty_as(dir, "bob", &["stop"]).contains("on task #2"));
+    assert_eq!(store.load_task(2).unwrap().time_log.len(), 2);
+}
+
+#[test]
+fn test_logged_time_rolls_up_and_reports() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    trusty(dir, &["init"]);
+    trusty(dir, &["add", "Auth", "-t", "backend"]);
+    trusty(dir, &["add-subtask", "--task", "1", "OAuth flow"]);
+    trusty(dir, &["add", "Readme", "-t", "docs"]);
+
+    trusty(dir, &["log-time", "1", "30m", "planning", "--date", "today"]);
+    trusty(dir, &["log-time", "2", "1h30m", "--date", "today"]);
+    trusty(dir, &["log-time", "3", "1.5h", "--date", "today"]);
+    trusty(dir, &["log-time", "3", "2h", "--date", "2001-01-01"]);
+
+    let show = trusty(dir, &["show", "1"]);
+    assert!(show.contains("Time logged: 2h (30m on this task, the rest on subtasks)"), "{}", show);
+
+    let sheet = trusty(dir, &["timesheet", "--week"]);
+    assert!(sheet.contains("backend                    2h"), "{}", sheet);
+    assert!(sheet.contains("docs                   1h 30m"), "{}", sheet);
+    assert!(sheet.contains("Total: 3h 30m"), "{}", sheet);
+
+    let store = storage::open_store(&dir.join(".trusty"), &ProjectConfig::default()).unwrap();
+    assert_eq!(store.load_task(1).unwrap().time_log[0].note.as_deref(), Some("planning"));
+}
@@ -0,0 +1,92 @@
+use trusty::storage::{TaskStorage, TaskStore};
+use trusty::task::{Priority, Task, TaskRef};
+use trusty::trash::{RefKind, Trash};
+
+fn setup() -> (tempfile::TempDir, TaskStorage, Trash) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let storage = TaskStorage::new(temp_dir.path().join("tasks")).unwrap();
+    let trash = Trash::new(temp_dir.path());
+    
+    // #1 has subtasks #2 and #3; #4 depends on #2
+    let mut parent = Task::new(1, "Parent".to_string(), String::new(), Priority::High);
+    parent.add_subtask(2);
+    parent.add_subtask(3);
+    storage.save_task(&parent).unwrap();
+    storage.save_task(&Task::new(2, "First".to_string(), String::new(), Priority::Medium)).unwrap();
+    storage.save_task(&Task::new(3, "Second".to_string(), String::new(), Priority::Medium)).unwrap();
+    let mut dependent = Task::new(4, "Dependent".to_string(), String::new(), Priority::Low);
+    dependent.add_dependency(2);
+    storage.save_task(&dependent).unwrap();
+    
+    (temp_dir, storage, trash)
+}
+
+#[test]
+fn test_delete_removes_references_and_restore_puts_them_back() {
+    let (_dir, storage, trash) = setup();
+    
+    let entries = trash.delete(&storage, &[2]).unwrap();
+    assert_eq!(entries[0].referenced_by.len(), 2);
+    assert!(entries[0].referenced_by.iter().any(|r| r.task_id == 1 && r.kind == RefKind::Subtask && r.position == Some(0)));
+    
+    assert!(storage.load_task(2).is_err());
+    assert_eq!(storage.load_task(1).unwrap().subtasks, vec![3]);
+    assert!(storage.load_task(4).unwrap().dependencies.is_empty());
+    
+    let entry = trash.find(&TaskRef::Id(2)).unwrap();
+    let (id, missing) = trash.restore(&storage, entry).unwrap();
+    assert_eq!(id, 2);
+    assert!(missing.is_empty());
+    assert_eq!(storage.load_task(1).unwrap().subtasks, vec![2, 3]);
+    assert!(storage.load_task(4).unwrap().dependencies.contains(&2));
+    assert!(trash.list().unwrap().is_empty());
+}
+
+#[test]
+fn test_restore_into_trashed_parent() {
+    let (_dir, storage, trash) = setup();
+    
+    trash.delete(&storage, &[3]).unwrap();
+    trash.delete(&storage, &[1]).unwrap();
+    
+    // The parent is still in the trash, so the subtask link goes into its entry
+    let (_, missing) = trash.restore(&storage, trash.find(&TaskRef::Id(3)).unwrap()).unwrap();
+    assert!(missing.is_empty());
+    trash.restore(&storage, trash.find(&TaskRef::Id(1)).unwrap()).unwrap();
+    
+    assert_eq!(storage.load_task(1).unwrap().subtasks, vec![2, 3]);
+}
+
+#[test]
+fn test_batch_delete_keeps_internal_references() {
+    let (_dir, storage, trash) = setup();
+    let before = storage.list_all_tasks().unwrap();
+    
+    let entries = trash.delete(&storage, &[1, 2, 3, 4]).unwrap();
+    assert!(entries.iter().all(|e| e.referenced_by.is_empty()));
+    assert!(storage.list_all_tasks().unwrap().is_empty());
+    
+    for entry in trash.list().unwrap() {
+        trash.restore(&storage, entry).unwrap();
+    }
+    assert_eq!(storage.list_all_tasks().unwrap(), before);
+}
+
+#[test]
+fn test_restore_with_taken_id_and_purge() {
+    let (_dir, storage, trash) = setup();
+    
+    trash.delete(&storage, &[3]).unwrap();
+    storage.save_task(&Task::new(3, "Reused".to_string(), String::new(), Priority::Low)).unwrap();
+    
+    let (id, _) = trash.restore(&storage, trash.find(&TaskRef::Id(3)).unwrap()).unwrap();
+    assert_eq!(id, 5);
+    assert_eq!(storage.load_task(5).unwrap().title, "Second");
+    assert!(storage.load_task(1).unwrap().subtasks.contains(&5));
+    
+    trash.delete(&storage, &[4]).unwrap();
+    let entry = trash.find(&TaskRef::Id(4)).unwrap();
+    trash.purge(&entry).unwrap();
+    assert!(trash.list().unwrap().is_empty());
+    assert!(trash.find(&TaskRef::Id(4)).is_err());
+}
@@ -0,0 +1,90 @@
+use trusty::config::ProjectConfig;
+use trusty::graph::TaskGraph;
+use trusty::storage;
+use trusty::task::{Priority, Task, TaskStatus};
+use trusty::workflow::{self, Workflow};
+
+fn workflow() -> Workflow {
+    serde_yaml::from_str(r#"
+statuses: [review]
+transitions:
+  pending: [in-progress, cancelled]
+  in-progress: [review, pending]
+  review: [done, in-progress]
+  cancelled: []
+guards:
+  done: [dependencies-done, subtasks-done]
+  cancelled: [resolution]
+"#).unwrap()
+}
+
+fn task(id: u32, status: TaskStatus) -> Task {
+    let mut task = Task::new(id, format!("Task {}", id), String::new(), Priority::Medium);
+    task.status = status;
+    task
+}
+
+#[test]
+fn test_transitions_and_guards() {
+    let workflow = workflow();
+    workflow.validate().unwrap();
+    let review = workflow.parse_status("review").unwrap();
+    assert_eq!(review, TaskStatus::Custom("review".to_string()));
+
+    let mut parent = task(1, review.clone());
+    parent.dependencies.insert(2);
+    parent.subtasks = vec![3];
+    let graph = TaskGraph::new(vec![parent.clone(), task(2, TaskStatus::InProgress), task(3, TaskStatus::Cancelled)]);
+
+    assert_eq!(workflow.violations(&parent, &TaskStatus::Done, &graph, None), vec!["dependency #2 isn't done"]);
+    assert_eq!(workflow.violations(&parent, &TaskStatus::Blocked, &graph, None), vec!["review tasks can only move to done, in-progress"]);
+    assert!(workflow.violations(&parent, &TaskStatus::InProgress, &graph, None).is_empty());
+
+    let pending = task(4, TaskStatus::Pending);
+    assert_eq!(workflow.violations(&pending, &TaskStatus::Cancelled, &graph, None), vec!["a resolution is required (--resolution)"]);
+    assert!(workflow.violations(&pending, &TaskStatus::Cancelled, &graph, Some("Duplicate")).is_empty());
+    assert_eq!(workflow.violations(&task(5, TaskStatus::Cancelled), &TaskStatus::Pending, &graph, None), vec!["cancelled is a final status"]);
+
+    // Without a workflow every change is allowed
+    assert!(Workflow::default().violations(&parent, &TaskStatus::Done, &graph, None).is_empty());
+    assert!(Workflow::default().parse_status("review").is_err());
+}
+
+#[test]
+fn test_invalid_workflows_are_rejected() {
+    let builtin: Workflow = serde_yaml::from_str("statuses: [done]").unwrap();
+    assert!(builtin.validate().is_err());
+    let unknown: Workflow = serde_yaml::from_str("transitions:\n  pending: [qa]").unwrap();
+    assert!(unknown.validate().unwrap_err().to_string().contains("Invalid status: qa"));
+}
+
+#[test]
+fn test_set_status_moves_tasks_together() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let storage = storage::open_store(temp_dir.path(), &ProjectConfig::default()).unwrap();
+    let workflow = workflow();
+    let review = TaskStatus::Custom("review".to_string());
+
+    let mut parent = task(1, review.clone());
+    parent.subtasks = vec![2];
+    storage.save_task(&parent).unwrap();
+    storage.save_task(&task(2, TaskStatus::Pending)).unwrap();
+
+    // The subtask may not skip review, so the parent doesn't move either
+    let err = workflow::set_status(storage.as_ref(), &workflow, &[1, 2], &TaskStatus::Done, None, false).unwrap_err();
+    assert!(err.to_string().contains("Can't move task #2 from pending to done"));
+    assert_eq!(storage.load_task(1).unwrap().status, review);
+
+    storage.save_task(&task(2, review.clone())).unwrap();
+    workflow::set_status(storage.as_ref(), &workflow, &[1, 2], &TaskStatus::Done, None, false).unwrap();
+    assert_eq!(storage.load_task(1).unwrap().status, TaskStatus::Done);
+
+    // Custom statuses survive a round trip through the task file
+    workflow::set_status(storage.as_ref(), &workflow, &[2], &TaskStatus::Cancelled, Some("Obsolete"), true).unwrap();
+    let cancelled = storage.load_task(2).unwrap();
+    assert_eq!(cancelled.resolution.as_deref(), Some("Obsolete"));
+    workflow::set_status(storage.as_ref(), &workflow, &[2], &review, None, true).unwrap();
+    let reopened = storage.load_task(2).unwrap();
+    assert_eq!(reopened.status, review);
+    assert_eq!(reopened.resolution, None);
+}